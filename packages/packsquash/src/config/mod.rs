@@ -247,7 +247,24 @@ pub struct GlobalOptions {
 	/// spooling buffer for the output ZIP file.
 	///
 	/// **Default value**: `half of the available memory reported by the OS / (number of CPU hardware threads + 1)`
-	pub spooling_buffers_size: usize
+	pub spooling_buffers_size: usize,
+	/// A map from block identifiers, such as `minecraft:oak_stairs`, to the names of the block
+	/// state properties of that block. When a blockstate file for a block in this map is
+	/// processed, any variant or multipart condition that references a property not listed here
+	/// will be reported as an error, as Minecraft will not be able to apply it. Blocks not in
+	/// this map are not checked.
+	///
+	/// **Default value**: empty map (do not check the block state properties of any block)
+	pub block_state_properties: IndexMap<String, Vec<String>>,
+	/// The path to a folder with the include shaders of vanilla Minecraft, i.e., the contents of
+	/// the `assets/minecraft/shaders/include` folder of the game JAR for the targeted version.
+	/// Shaders that `#moj_import` include shaders can only be minified or prettified if every
+	/// imported include shader is known, and by default only the include shaders of the pack
+	/// are. Include shaders in the pack take precedence over the ones in this folder, as they
+	/// do in the game.
+	///
+	/// **Default value**: `None` (only resolve imports of include shaders in the pack)
+	pub vanilla_include_shaders_directory: Option<PathBuf>
 }
 
 impl Default for GlobalOptions {
@@ -279,7 +296,9 @@ impl Default for GlobalOptions {
 			// In MiB. By default, half of available memory / (hardware threads + 1 for the output ZIP)
 			spooling_buffers_size: (available_memory / 2097152 / (hardware_threads.get() as u64 + 1))
 				.try_into()
-				.unwrap_or(usize::MAX)
+				.unwrap_or(usize::MAX),
+			block_state_properties: IndexMap::new(),
+			vanilla_include_shaders_directory: None
 		}
 	}
 }
@@ -616,8 +635,21 @@ pub struct AudioFileOptions {
 	/// get the different positional effects. If `is_positional_audio` is set to `None`, this
 	/// channel mixing also influences whether the sound is considered positional or not.
 	///
+	/// Surround sound files, such as 5.1 or 7.1 ones, are always downmixed to stereo first,
+	/// according to their channel layout, and then mixed to this number of channels.
+	///
+	/// This option can also be set to `auto`, which downmixes stereo sounds to mono when their
+	/// channels are nearly identical, as tuned by `automatic_channel_mixing_threshold`. Such
+	/// sounds waste space on a redundant channel, and do not get positional audio effects.
+	///
 	/// **Default value**: do not downmix or upmix (keep the channels of the input file)
 	pub channels: ChannelMixingOption,
+	/// The maximum energy of the difference between the channels of a stereo sound, relative to
+	/// the energy of their sum, in decibels, for them to be considered nearly identical when
+	/// `channels` is set to `auto`. Lower values require the channels to be more similar.
+	///
+	/// **Default value**: `-40.0`
+	pub automatic_channel_mixing_threshold: f32,
 	/// The bitrate control mode that will be used for transcoding the audio file. Different bitrate
 	/// control modes have different trade-offs between audio quality, file size, bandwidth
 	/// predictability and encoding speed.
@@ -626,7 +658,7 @@ pub struct AudioFileOptions {
 	pub bitrate_control_mode: AudioBitrateControlMode,
 	/// The metric to use as a target for the specified bitrate control mode when trancoding.
 	/// Depending on the selected bitrate control mode, this will be interpreted as a quality
-	/// factor, average bitrate, approximate bitrate, or maximum bitrate.
+	/// factor, average bitrate, approximate bitrate, maximum bitrate, or maximum file size.
 	///
 	/// **Default value**: `0.25` for stereo audio (interpreted as a quality factor, ≈68 kbit/s for
 	/// stereo, 44.1 kHz audio) and `0.0` for mono audio, interpreted as quality factors
@@ -637,8 +669,21 @@ pub struct AudioFileOptions {
 	/// further signal processing work. If the specified sampling frequency is higher than the
 	/// sampling frequency of the input audio file, no resampling will be done.
 	///
+	/// This option can also be set to `auto`, which selects the lowest standard sampling
+	/// frequency that preserves the measured bandwidth of the audio, as tuned by
+	/// `automatic_sampling_frequency_threshold`. This saves space on sounds without high
+	/// frequencies, such as voices and low rumbles, while keeping the quality of brighter ones.
+	///
 	/// **Default value**: `40050` (40.05 kHz) for stereo audio, `32000` for mono audio
-	pub sampling_frequency: Option<NonZeroU32>,
+	pub sampling_frequency: Option<SamplingFrequencyOption>,
+	/// The maximum energy of the frequencies a sampling frequency can't represent, relative to
+	/// the energy of the whole audio, in decibels, for that sampling frequency to be considered
+	/// to preserve the bandwidth of the audio when `sampling_frequency` is set to `auto`. Lower
+	/// values preserve more high-frequency content, at the cost of selecting higher sampling
+	/// frequencies.
+	///
+	/// **Default value**: `-50.0`
+	pub automatic_sampling_frequency_threshold: f32,
 	/// Sets the pitch shift coefficient that will have to be used to play back the sound
 	/// at the original pitch. This pitch shift coefficient can be used directly in Minecraft
 	/// commands like `/playsound`.
@@ -650,6 +695,28 @@ pub struct AudioFileOptions {
 	///
 	/// **Default value**: `1.0` (the audio pitch is not shifted)
 	pub target_pitch: f32,
+	/// If set, a high-pass filter with this cutoff frequency, in Hz, will be applied to the audio
+	/// before resampling it. High-pass filtering removes DC offsets and low-frequency rumble,
+	/// which are inaudible on most speakers but take bits to encode. Values around `20` to `40`
+	/// Hz are usually safe for most sounds.
+	///
+	/// **Default value**: `None` (do not high-pass filter)
+	pub high_pass_filter_cutoff_frequency: Option<NonZeroU32>,
+	/// If set, a low-pass filter with this cutoff frequency, in Hz, will be applied to the audio
+	/// before resampling it. Vorbis encoders still spend bits on high frequencies that most
+	/// people can't hear, especially on the small speakers Minecraft is often played on, so
+	/// cutting them off makes audio easier to compress. Cutoff frequencies not below half the
+	/// sampling frequency of the audio are ignored, as there is nothing to filter out then.
+	///
+	/// **Default value**: `None` (do not low-pass filter)
+	pub low_pass_filter_cutoff_frequency: Option<NonZeroU32>,
+	/// If set, a peak limiter with this threshold, in dBFS, will be applied to the audio after
+	/// high-pass and low-pass filtering it, and before resampling it. The limiter smoothly
+	/// reduces the gain of the audio to keep its peaks below the threshold, which avoids the
+	/// clipping that filtering and lossy encoding may otherwise cause on loud sounds.
+	///
+	/// **Default value**: `None` (do not limit peaks)
+	pub limiter_threshold: Option<f32>,
 	/// If `true`, the generated Ogg Vorbis files will be mangled in a way so that they will be
 	/// harder to play outside of Minecraft. The obfuscation technique used is not robust against
 	/// some scenarios or expert knowledge, but it does not increase file size.
@@ -660,6 +727,90 @@ pub struct AudioFileOptions {
 	///
 	/// **Default value**: `false`
 	pub ogg_obfuscation: bool,
+	/// If set, transcoded audio files whose signal-to-noise ratio (SNR), in decibels, is below
+	/// this value will fail to be processed. The SNR is computed by decoding the transcoded file
+	/// and comparing it with the processed audio samples that were encoded, after filtering,
+	/// resampling, channel mixing and pitch shifting, so it only measures the quality loss caused
+	/// by lossy encoding. When this option is set, the SNR is also shown in the processing status of
+	/// transcoded files, so setting it to a very low value, such as `-100`, is a handy way to
+	/// get that information without failing any file.
+	///
+	/// Computing the SNR requires keeping every audio sample in memory and decoding the
+	/// transcoded file, so it is slower and uses more memory. Keep in mind that perceptual
+	/// codecs such as Vorbis are designed to discard information that is not audible, so audio
+	/// that sounds transparent may still have moderate SNR values, typically around 10 to 25 dB.
+	///
+	/// **Default value**: `None` (the SNR is not computed)
+	pub minimum_signal_to_noise_ratio: Option<f32>,
+	/// If set, transcoded audio files will be normalized to this integrated loudness, in LUFS,
+	/// as measured according to the EBU R128 standard and the ITU-R BS.1770 recommendation. This
+	/// is done by applying a gain to the processed audio samples before encoding them, so sounds
+	/// from different sources play at a consistent loudness. The gain is limited so that the
+	/// true peak of the audio does not exceed `true_peak_ceiling`. Silent audio is left as is.
+	///
+	/// The loudness of each file is normalized independently. Normalizing requires keeping every
+	/// audio sample in memory before encoding it, so it uses more memory. Common targets are
+	/// `-14` to `-16` LUFS for music and `-23` LUFS for broadcast-style mixes.
+	///
+	/// **Default value**: `None` (do not normalize loudness)
+	pub loudness_normalization_target: Option<f32>,
+	/// The maximum true peak, in dBTP, that audio files may have after normalizing their
+	/// loudness. Limiting the true peak below 0 dBTP leaves some headroom to avoid clipping
+	/// when the audio is decoded. This option has no effect if `loudness_normalization_target`
+	/// is not set.
+	///
+	/// **Default value**: `-1.0`
+	pub true_peak_ceiling: f32,
+	/// If set, leading and trailing audio frames whose samples are all quieter than this
+	/// threshold, in dBFS, will be trimmed off transcoded audio files. Digital silence at the
+	/// start of a sound delays its playback and, like silence at its end, wastes space. A
+	/// threshold around `-60` dBFS removes silence and inaudible noise floors without cutting
+	/// into quiet but meaningful audio.
+	///
+	/// Music streams, i.e. files under the `sounds/music` and `sounds/records` asset folders or
+	/// that `sounds.json` files mark as streamed, and files with loop point metadata are never
+	/// trimmed, because trimming would change their timing. Silence is trimmed after the rest of
+	/// the signal processing is done.
+	///
+	/// **Default value**: `None` (do not trim silence)
+	pub silence_trimming_threshold: Option<f32>,
+	/// The duration, in milliseconds, of the linear fade in and fade out applied to the edges of
+	/// audio files whose silence was trimmed. A short fade of a few milliseconds avoids audible
+	/// clicks when the audio does not start or end at a zero crossing. This option has no effect
+	/// if `silence_trimming_threshold` is not set.
+	///
+	/// **Default value**: `0` (do not fade)
+	pub silence_trimming_fade_duration: u16,
+	/// If `true`, a perceptual fingerprint of every transcoded audio file will be computed while
+	/// it is decoded, and a warning will be shown for each group of audio files that sound
+	/// nearly identical. Such files are usually copies of the same sound that were re-exported
+	/// or had their gain changed, so they are not byte-for-byte identical and can not be
+	/// deduplicated automatically, but the pack may work as well and be smaller if its
+	/// `sounds.json` files referenced a single one of them. Files that are too short, too quiet
+	/// or not transcoded are not fingerprinted. Files reused from a previous ZIP file, because
+	/// they did not change since it was generated, are not decoded, so they are not fingerprinted
+	/// either, and near duplicates of them are not detected.
+	///
+	/// **Default value**: `false`
+	pub near_duplicate_detection: bool,
+	/// Audio file options that override the ones above for sounds that some sound event in a
+	/// `sounds.json` file streams, i.e., plays with `stream` set to `true`. Minecraft streams
+	/// long sounds, such as music and ambient tracks, which usually tolerate lower quality
+	/// factors or sampling frequencies well, as they play in the background.
+	///
+	/// **Default value**: no overrides
+	pub streamed_sound_overrides: SoundOptionOverrides,
+	/// Audio file options that override the ones above for sounds that sound events in
+	/// `sounds.json` files reference, but do not stream. These are usually short sound effects
+	/// that Minecraft plays at some position, which only get positional audio effects, such as
+	/// distance attenuation, if they are mono. Therefore, setting `channels` to `1` here is
+	/// usually a good idea for packs that do not define stereo sound effects on purpose.
+	///
+	/// Sounds that no sound event references, such as those that replace the sounds of the
+	/// game without redefining its sound events, are not affected by these overrides.
+	///
+	/// **Default value**: no overrides
+	pub non_streamed_sound_overrides: SoundOptionOverrides,
 	/// Crate-private option set by the [MinecraftQuirk::OggObfuscationIncompatibility]
 	/// workaround to not obfuscate Ogg Vorbis files.
 	///
@@ -675,32 +826,122 @@ impl Default for AudioFileOptions {
 			two_pass_vorbis_optimization_and_validation: true,
 			empty_audio_optimization: true,
 			channels: Default::default(),
+			automatic_channel_mixing_threshold: -40.0,
 			bitrate_control_mode: Default::default(),
 			target_bitrate_control_metric: None,
 			sampling_frequency: None,
+			automatic_sampling_frequency_threshold: -50.0,
 			target_pitch: 1.0,
+			high_pass_filter_cutoff_frequency: None,
+			low_pass_filter_cutoff_frequency: None,
+			limiter_threshold: None,
 			ogg_obfuscation: false,
+			minimum_signal_to_noise_ratio: None,
+			loudness_normalization_target: None,
+			true_peak_ceiling: -1.0,
+			silence_trimming_threshold: None,
+			silence_trimming_fade_duration: 0,
+			near_duplicate_detection: false,
+			streamed_sound_overrides: Default::default(),
+			non_streamed_sound_overrides: Default::default(),
 			minecraft_version_supports_ogg_obfuscation: true
 		}
 	}
 }
 
+impl AudioFileOptions {
+	/// Returns a copy of these options with the specified overrides applied.
+	pub(crate) fn with_overrides(mut self, overrides: SoundOptionOverrides) -> Self {
+		if let Some(channels) = overrides.channels {
+			self.channels = channels;
+		}
+
+		if let Some(sampling_frequency) = overrides.sampling_frequency {
+			self.sampling_frequency = Some(sampling_frequency);
+		}
+
+		if let Some(bitrate_control_mode) = overrides.bitrate_control_mode {
+			self.bitrate_control_mode = bitrate_control_mode;
+		}
+
+		if let Some(target_bitrate_control_metric) = overrides.target_bitrate_control_metric {
+			self.target_bitrate_control_metric = Some(target_bitrate_control_metric);
+		}
+
+		self
+	}
+}
+
+impl SoundOptionOverrides {
+	/// Returns whether these overrides do not override any option.
+	pub(crate) fn is_empty(&self) -> bool {
+		self.channels.is_none()
+			&& self.sampling_frequency.is_none()
+			&& self.bitrate_control_mode.is_none()
+			&& self.target_bitrate_control_metric.is_none()
+	}
+}
+
+/// Audio file options that override others for sounds with some properties, as defined by
+/// the sound events that reference them, contained in [`AudioFileOptions`]. Each of these
+/// options has the same meaning as the audio file option with the same name.
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(default, deny_unknown_fields)]
+#[non_exhaustive]
+pub struct SoundOptionOverrides {
+	/// If set, overrides the `channels` audio file option.
+	///
+	/// **Default value**: `None` (do not override)
+	pub channels: Option<ChannelMixingOption>,
+	/// If set, overrides the `sampling_frequency` audio file option.
+	///
+	/// **Default value**: `None` (do not override)
+	pub sampling_frequency: Option<SamplingFrequencyOption>,
+	/// If set, overrides the `bitrate_control_mode` audio file option.
+	///
+	/// **Default value**: `None` (do not override)
+	pub bitrate_control_mode: Option<AudioBitrateControlMode>,
+	/// If set, overrides the `target_bitrate_control_metric` audio file option.
+	///
+	/// **Default value**: `None` (do not override)
+	pub target_bitrate_control_metric: Option<f32>
+}
+
 /// A channel mixing strategy for some audio file, contained in [`AudioFileOptions`].
 #[derive(Deserialize, Clone, Copy, Default)]
-#[serde(untagged)]
+#[serde(rename_all = "snake_case")]
 pub enum ChannelMixingOption {
+	/// Downmix stereo sounds to mono when their channels are nearly identical, and
+	/// keep the channels of the input file otherwise.
+	Auto,
 	/// Downmix or upmix the sound channels in the input file to generate an output
 	/// file with the specified number of channels.
 	///
 	/// Currently, only `1` or `2` channels make sense for a resource pack audio
 	/// file, as other channel counts are rejected by Minecraft.
+	#[serde(untagged)]
 	ToChannels(ChannelCount),
 	/// Do not change the number or layout of the sound channels of the input file
-	/// in any way.
+	/// in any way, except for downmixing surround sound to stereo, as Minecraft
+	/// only supports mono and stereo sounds.
 	#[default]
+	#[serde(untagged)]
 	Skip
 }
 
+/// A sampling frequency selection strategy for some audio file, contained in
+/// [`AudioFileOptions`].
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum SamplingFrequencyOption {
+	/// Select the lowest standard sampling frequency that preserves the measured
+	/// bandwidth of the audio.
+	Auto,
+	/// Resample the audio to the specified sampling frequency, in Hz.
+	#[serde(untagged)]
+	ToFrequency(NonZeroU32)
+}
+
 /// Represents a bitrate control mode that can be used by the PackSquash Vorbis encoder,
 /// a modified version of the reference encoder with the aoTuV and Lancer patches applied.
 #[derive(Default, Deserialize, Clone, Copy)]
@@ -783,7 +1024,27 @@ pub enum AudioBitrateControlMode {
 	///
 	/// The bitrate is interpreted in kbit/s.
 	#[serde(rename = "CABR")]
-	ConstrainedAbr
+	ConstrainedAbr,
+	/// **Target File Size**: the encoder will interpret the target metric as a maximum file size,
+	/// and search for the highest quality factor whose output fits in that size, by encoding the
+	/// audio several times with quality factors selected by bisection. The size checked is the
+	/// one after the two-pass optimization and validation step, if it is enabled.
+	///
+	/// Some advantages of this bitrate control mode over the others include:
+	/// - The resulting file size is guaranteed to not exceed the specified maximum, while
+	///   keeping the subjective quality as consistent as with CQF.
+	/// - Unlike with CABR, the quality is not lowered more than necessary to meet the maximum
+	///   size, as the encoder knows the whole audio signal beforehand.
+	///
+	/// Some disadvantages of this bitrate control mode over the others include:
+	/// - Performance is much worse, as the audio is encoded up to nine times, and every audio
+	///   sample is kept in memory while doing so.
+	/// - Audio files that do not fit in the specified size even at the lowest quality factor
+	///   fail to be processed.
+	///
+	/// The file size is interpreted in KiB.
+	#[serde(rename = "SIZE")]
+	TargetFileSize
 }
 
 /// A helper struct that contains an integer that must be a valid number of
@@ -805,6 +1066,11 @@ impl TryFrom<NonZeroU8> for ChannelCount {
 	}
 }
 
+impl ChannelCount {
+	/// The channel count of mono audio.
+	pub(crate) const MONO: Self = Self(NonZeroU8::new(1).unwrap());
+}
+
 impl From<ChannelCount> for NonZeroU8 {
 	fn from(value: ChannelCount) -> Self {
 		value.0
@@ -835,9 +1101,24 @@ pub struct JsonFileOptions {
 	/// format that can contain comments. If `false`, comments will only be allowed in JSON files
 	/// with those specific extensions.
 	///
+	/// Regardless of this option, files with those extensions are parsed as
+	/// [JSON5](https://spec.json5.org/), so they may also contain trailing commas, single-quoted
+	/// strings, unquoted object keys and other syntax extensions. Such files are always converted
+	/// to strict JSON.
+	///
 	/// **Default value**: `true` (allow comments in the JSON file, no matter its extension)
 	#[serde(rename = "always_allow_json_comments")]
-	pub always_allow_comments: bool
+	pub always_allow_comments: bool,
+	/// If `true`, PackSquash will delete translations in language files of locales other than
+	/// `en_us` that are identical to the translation for the same key in the `en_us` language
+	/// file of the same namespace in the pack. Minecraft falls back to `en_us` translations
+	/// for keys missing in the selected language, so these translations are redundant.
+	/// However, some launchers and mods do not implement this fallback, and if other packs
+	/// applied below this one translate the same keys, their translations will be shown
+	/// instead. If `false`, every translation will be kept.
+	///
+	/// **Default value**: `false` (do not delete translations that duplicate `en_us` ones)
+	pub delete_redundant_translations: bool
 }
 
 impl Default for JsonFileOptions {
@@ -845,7 +1126,8 @@ impl Default for JsonFileOptions {
 		Self {
 			minify: true,
 			delete_bloat: true,
-			always_allow_comments: true
+			always_allow_comments: true,
+			delete_redundant_translations: false
 		}
 	}
 }
@@ -900,6 +1182,31 @@ pub struct PngFileOptions {
 	///
 	/// **Default value**: `0.85`
 	pub color_quantization_dithering_level: UnitIntervalFloat,
+	/// The minimum quality color quantized images must have, in the `0-100` range, as estimated
+	/// by the quantizer. This option has no effect if `color_quantization_target` is set to not
+	/// perform color quantization.
+	///
+	/// When this option is greater than zero, PackSquash tries to quantize images to
+	/// progressively smaller palettes of 256, 16, 4 and 2 colors, up to the number of colors of
+	/// the quantization target, and keeps the smallest palette that reaches this quality. If
+	/// not even the biggest palette reaches this quality, the image is not quantized at all,
+	/// even if the quantization target would otherwise require it. This allows quantizing
+	/// images as much as possible without noticeable quality loss, and values between 80 and
+	/// 95 are good starting points for that. Values greater than 100 are treated as 100.
+	///
+	/// **Default value**: `0` (any quality is acceptable, and the number of colors of the
+	/// quantization target is used)
+	pub color_quantization_minimum_quality: u8,
+	/// If set, color quantized images whose mean structural similarity index (SSIM) to the
+	/// original image is below this value, between 0 and 1, will fail to be processed. The
+	/// SSIM and peak signal-to-noise ratio (PSNR) of color quantized images are always shown
+	/// in their processing status, and this option allows catching unacceptable quality losses
+	/// before they reach players. Changes to the colors of completely transparent pixels do not
+	/// count as quality loss. This option has no effect when quantization is not performed, or
+	/// when its result is discarded because it does not save space.
+	///
+	/// **Default value**: `None` (no minimum SSIM)
+	pub minimum_structural_similarity: Option<UnitIntervalFloat>,
 	/// The maximum width and height of the images that will be accepted. This parameter
 	/// sets a high bound of memory usage by PackSquash and helps authoring packs with
 	/// reasonable texture sizes.
@@ -916,6 +1223,53 @@ pub struct PngFileOptions {
 	///
 	/// **Default value**: `false`
 	pub skip_alpha_optimizations: bool,
+	/// Controls which textures will have their alpha channel binarized, snapping the alpha
+	/// value of every pixel to fully transparent or fully opaque, depending on whether it is
+	/// below `alpha_binarization_threshold`.
+	///
+	/// Textures rendered by the cutout render types, such as those of leaves, plants and glass
+	/// panes, discard pixels whose alpha is below a threshold and render the rest as fully
+	/// opaque, so partially transparent pixels just waste space in them. Binarizing their alpha
+	/// allows for smaller palettes and more efficient color types. However, the threshold is
+	/// 0.1 for the cutout render type, but 0.5 for the mipmapped cutout render type used by
+	/// textures such as leaves, so a single threshold may make some pixels visible that were
+	/// not. Textures rendered by translucent render types, such as water and stained glass,
+	/// should not be binarized, because that changes how they look.
+	///
+	/// **Default value**: [AlphaBinarization::Never] (do not binarize the alpha channel)
+	pub alpha_binarization: AlphaBinarization,
+	/// The alpha value below which pixels are made fully transparent when binarizing the alpha
+	/// channel of a texture. Pixels with an alpha value equal or greater than this are made fully
+	/// opaque. This option has no effect if `alpha_binarization` does not apply to a texture.
+	///
+	/// The default value matches the threshold used by Minecraft for the cutout render type.
+	/// Set it to `128` for textures rendered by the mipmapped cutout render type, such as
+	/// leaves.
+	///
+	/// **Default value**: `26` (alpha values below 0.1 become fully transparent)
+	pub alpha_binarization_threshold: u8,
+	/// If `true`, the pixels of the texture are treated as exact data that must stay bit-exact,
+	/// rather than as colors. Such textures are only recompressed losslessly, keeping their
+	/// color type and bit depth, so the options of this file that may change pixels, such as
+	/// color quantization, alpha binarization and downscaling, have no effect on them.
+	///
+	/// Biome colormaps and the images in the `misc` texture directory are always treated this
+	/// way. This option is meant to mark other textures that carry data, such as those that core
+	/// shaders decode as data in text rendering tricks, with file options globs.
+	///
+	/// **Default value**: `false`
+	pub preserve_exact_pixel_data: bool,
+	/// Controls how images with 16 bits per channel are reduced to the 8 bits per channel
+	/// Minecraft uses when loading them. This reduction is lossy, and is done before color
+	/// quantization.
+	///
+	/// When this option is set to [SixteenBitDepthReduction::None], the extra precision of such
+	/// images is just truncated away, as Minecraft does. Rounding is more accurate, and
+	/// dithering can avoid banding in smooth gradients, such as those of skies and GUI
+	/// backgrounds, at the cost of some compressibility.
+	///
+	/// **Default value**: [SixteenBitDepthReduction::None] (truncate the extra precision)
+	pub sixteen_bit_depth_reduction: SixteenBitDepthReduction,
 	/// If `true`, single-color textures that are estimated to be safe to resize will be downsized
 	/// to the minimum resolution that maintains the current maximum mipmap level. This can provide
 	/// significant space savings for this kind of textures, but in some edge cases (using
@@ -924,6 +1278,57 @@ pub struct PngFileOptions {
 	///
 	/// **Default value**: `false`
 	pub downsize_if_single_color: bool,
+	/// If `true`, pixel-identical frames of animated textures laid out as vertical strips, which
+	/// are common in e.g. ping-pong animations, will be removed, and the frame list of the
+	/// animation metadata file of the texture will be rewritten or added so that the animation
+	/// plays exactly the same. This requires decoding animated textures before processing any
+	/// pack file, which is done concurrently, but may still slightly slow down packs with many
+	/// of them. When this option is disabled for every texture, they are not decoded.
+	///
+	/// **Default value**: `false`
+	pub deduplicate_animation_frames: bool,
+	/// If set, textures whose width or height exceed this resolution will be downscaled so that
+	/// they fit in it, keeping their aspect ratio. Textures made up of several cells, like the
+	/// frames of animated textures and the glyphs of bitmap fonts, are downscaled cell by cell,
+	/// so that each cell fits in this resolution, and the frame dimensions declared in the
+	/// animation metadata are rewritten accordingly. The game draws bitmap font glyphs at a
+	/// scale derived from the `height` of their font provider and the height of their cells, so
+	/// glyphs keep their in-game size, and the `height` and `ascent` of font providers stay
+	/// consistent with the downscaled glyphs. Cells with power of two dimensions are downscaled
+	/// by power of two factors, so that atlas sprites keep working well with mipmapping.
+	/// Material maps used by shader packs, such as `_n` normal and `_s` specular maps, are not
+	/// downscaled, because resampling does not preserve the data they encode. Textures that have
+	/// material maps are not downscaled either, so that they keep the same size as their
+	/// material maps.
+	///
+	/// Downscaling is a lossy process that can significantly reduce the size and memory usage of
+	/// high resolution packs, at the cost of detail. It is useful to generate lower resolution
+	/// variants of a pack.
+	///
+	/// **Default value**: `None` (do not downscale)
+	pub downscaling_target_resolution: Option<NonZeroU16>,
+	/// The filter to use when downscaling textures. This option has no effect if neither
+	/// `downscaling_target_resolution` nor `pack_icon_resolution` are set.
+	///
+	/// **Default value**: [DownscalingFilter::Lanczos]
+	pub downscaling_filter: DownscalingFilter,
+	/// If set, the `pack.png` pack icon will be downscaled to fit in a square with this
+	/// resolution, taking precedence over `downscaling_target_resolution`. Minecraft shows pack
+	/// icons at small sizes, so icons much bigger than 64x64 pixels usually waste space, as the
+	/// game downsamples them anyway. Icons keep their aspect ratio, and icons smaller than this
+	/// resolution are not upscaled. This option has no effect on other textures.
+	///
+	/// Regardless of this option, PackSquash shows a warning for pack icons that are not square,
+	/// because Minecraft may refuse to show them.
+	///
+	/// **Default value**: `None` (do not resize the pack icon)
+	pub pack_icon_resolution: Option<NonZeroU16>,
+	/// If set, the `pack.png` pack icon will be composited over this background color, specified
+	/// as an array of red, green and blue components in the `0-255` range, making it fully opaque.
+	/// Opaque images can be stored more efficiently. This option has no effect on other textures.
+	///
+	/// **Default value**: `None` (do not flatten the alpha channel of the pack icon)
+	pub pack_icon_background_color: Option<[u8; 3]>,
 	/// Controls whether PackSquash should assume that this texture may be stitched by the game as
 	/// a part of an internal or custom atlas that is directory-listed. For performance reasons,
 	/// Minecraft stitches most game textures into atlases, including those of item and block models,
@@ -932,10 +1337,14 @@ pub struct PngFileOptions {
 	///
 	/// Currently, this option only affects how PackSquash protects images when
 	/// [`size_increasing_zip_obfuscation`](GlobalOptions::size_increasing_zip_obfuscation) is in
-	/// effect. `true` is a safe default value that causes textures to be less protected. Setting
-	/// it to `false` for better protection is, however, only recommended if you have detailed
-	/// knowledge of how the game stitches textures and are willing to test the correctness of this
-	/// assumption on a case-by-case basis.
+	/// effect. When this option is `true`, PackSquash reads the atlas definitions of the pack,
+	/// which are combined with the vanilla ones, to tell whether the texture is stitched into an
+	/// atlas from a directory listing, and better protects it if it is not. Textures stitched into
+	/// atlases that are defined outside the pack, such as by mods, can't be detected this way,
+	/// so their atlas definitions should be included in the pack when using this feature. Setting
+	/// this option to `false` unconditionally assumes that the texture is not directory-listed,
+	/// which is only recommended if you have detailed knowledge of how the game stitches textures
+	/// and are willing to test the correctness of this assumption on a case-by-case basis.
 	///
 	/// This option may be changed in the future to have more side effects. It may also be removed,
 	/// depending on how PackSquash improves its atlas texture detection capabilities.
@@ -968,9 +1377,20 @@ impl Default for PngFileOptions {
 			image_data_compression_iterations: 5,
 			color_quantization_target: Default::default(),
 			color_quantization_dithering_level: UnitIntervalFloat(0.85),
+			color_quantization_minimum_quality: 0,
+			minimum_structural_similarity: None,
 			maximum_width_and_height: NonZeroU16::new(8192).unwrap(),
 			skip_alpha_optimizations: false,
+			alpha_binarization: Default::default(),
+			alpha_binarization_threshold: 26,
+			preserve_exact_pixel_data: false,
+			sixteen_bit_depth_reduction: Default::default(),
 			downsize_if_single_color: false,
+			deduplicate_animation_frames: false,
+			downscaling_target_resolution: None,
+			downscaling_filter: Default::default(),
+			pack_icon_resolution: None,
+			pack_icon_background_color: None,
 			may_be_directory_listed_atlas_sprite: true,
 			working_around_grayscale_reduction_quirk: false,
 			working_around_color_type_change_quirk: false,
@@ -1031,6 +1451,50 @@ impl ColorQuantizationTarget {
 	}
 }
 
+/// Possible sets of textures whose alpha channel will be binarized.
+#[derive(Default, Deserialize, Copy, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum AlphaBinarization {
+	/// The alpha channel will not be binarized.
+	#[default]
+	Never,
+	/// The alpha channel of block textures that vanilla Minecraft renders with cutout render
+	/// types will be binarized.
+	CutoutTextures,
+	/// The alpha channel will always be binarized. This is useful for textures of custom
+	/// blocks or models rendered with cutout render types.
+	Always
+}
+
+/// Possible methods to reduce the depth of images with 16 bits per channel to 8 bits per
+/// channel.
+#[derive(Default, Deserialize, Copy, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum SixteenBitDepthReduction {
+	/// The least significant bits of every channel will be truncated, like Minecraft does.
+	#[default]
+	None,
+	/// Every channel will be rounded to the nearest 8-bit value.
+	Round,
+	/// The color channels will be rounded with ordered dithering, which trades some noise for
+	/// less banding. The alpha channel is rounded to the nearest 8-bit value.
+	Dither
+}
+
+/// Possible filters to resample textures with when downscaling them.
+#[derive(Default, Deserialize, Copy, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum DownscalingFilter {
+	/// Each downscaled pixel takes the color of the nearest original pixel. This keeps the
+	/// sharp edges and palette of pixel art textures, but may skip fine details.
+	Nearest,
+	/// Each downscaled pixel is a weighted average of nearby original pixels, according to a
+	/// three-lobe Lanczos kernel. This keeps fine details and looks smoother, which is best for
+	/// high resolution, realistic textures.
+	#[default]
+	Lanczos
+}
+
 /// A helper struct that contains an 32-bit floating point number guaranteed to be
 /// in the `[0, 1]` interval.
 #[derive(Deserialize, Clone, Copy)]
@@ -1088,7 +1552,32 @@ pub struct ShaderFileOptions {
 	/// it is the most appropriate course of action.
 	///
 	/// **Default value**: `None` (`true` for every vertex and fragment shader)
-	pub is_top_level_shader: Option<bool>
+	pub is_top_level_shader: Option<bool>,
+	/// If `true`, minified top-level shaders will have their local variables, function
+	/// parameters and functions other than `main` renamed to the shortest identifiers
+	/// possible, which further reduces their size. Global variables, such as uniforms,
+	/// samplers and `in` or `out` variables, are always kept as-is, because Minecraft and
+	/// shader JSON definitions reference them by name.
+	///
+	/// This option only has an effect when the shader source transformation strategy is
+	/// [`ShaderSourceTransformationStrategy::Minify`] and the shader is top-level. Minified
+	/// shaders are harder to debug with mangled identifiers.
+	///
+	/// **Default value**: `false`
+	pub identifier_mangling: bool,
+	/// If `true`, minified top-level shaders will have their constant expressions folded,
+	/// and their dead code removed: functions that are not reachable from `main`, global
+	/// variables that are not referenced, branches whose condition is constant and can't be
+	/// taken, such as those of `if (false)` statements, and statements after `return`,
+	/// `break` or `continue`. Global variables with qualifiers other than `const`, such as
+	/// uniforms, samplers and `in` or `out` variables, are always kept.
+	///
+	/// These optimizations keep the shader semantically equivalent, but they may remove
+	/// debugging aids. This option only has an effect when the shader source transformation
+	/// strategy is [`ShaderSourceTransformationStrategy::Minify`] and the shader is top-level.
+	///
+	/// **Default value**: `false`
+	pub dead_code_elimination: bool
 }
 
 /// A strategy that may be used to transform GLSL shader source code.
@@ -43,6 +43,7 @@ use tokio::sync::Semaphore;
 use tokio::{fs::File, io::AsyncRead, runtime::Builder};
 
 use config::ProcessedSquashOptions;
+use pack_index::PackIndex;
 use pack_meta::{PackMeta, PackMetaError};
 use squash_zip::{SquashZip, SquashZipError};
 
@@ -56,7 +57,7 @@ use crate::config::{
 use crate::pack_file::asset_type::{
 	tweak_asset_types_mask_from_global_options, PackFileAssetTypeMatcher, PackFileAssetTypeMatches
 };
-use crate::pack_file::PackFileProcessData;
+use crate::pack_file::{PackFileContext, PackFileProcessData};
 pub use crate::squash_zip::relative_path::RelativePath;
 use crate::squash_zip::{system_id, PreviousZipParseError};
 use crate::vfs::{IteratorTraversalOptions, VfsPackFileIterEntry, VirtualFileSystem};
@@ -65,6 +66,7 @@ pub mod config;
 pub mod vfs;
 
 mod pack_file;
+mod pack_index;
 mod pack_meta;
 mod squash_zip;
 mod zopfli_iterations_time_model;
@@ -235,6 +237,38 @@ impl PackSquasher {
 		let options_holder = Arc::new(options_holder);
 
 		runtime.block_on(async {
+			// Gather the cross-file data that some pack files need before processing any of them
+			let pack_index = Arc::new(PackIndex::new(&*vfs, &options_holder).await);
+
+			if let Some(tx) = &pack_file_status_sender {
+				for (sound_definitions_path, sound_file_path) in pack_index.missing_sound_files() {
+					tx.send(PackSquasherStatus::Warning(
+						PackSquasherWarning::MissingSoundFile {
+							sound_definitions_path: sound_definitions_path.clone(),
+							sound_file_path: sound_file_path.clone()
+						}
+					))
+					.await
+					.ok();
+				}
+
+				for sound_file_path in pack_index.unreferenced_sound_files() {
+					tx.send(PackSquasherStatus::Warning(
+						PackSquasherWarning::UnreferencedSoundFile(sound_file_path.clone())
+					))
+					.await
+					.ok();
+				}
+
+				if let Some(pack_icon_path) = pack_index.non_square_pack_icon() {
+					tx.send(PackSquasherStatus::Warning(
+						PackSquasherWarning::NonSquarePackIcon(pack_icon_path.to_string())
+					))
+					.await
+					.ok();
+				}
+			}
+
 			let pack_file_iter = vfs.file_iterator(
 				&options_holder.options.pack_directory,
 				IteratorTraversalOptions {
@@ -373,6 +407,7 @@ impl PackSquasher {
 				let asset_type_matcher = Arc::clone(&asset_type_matcher);
 				let squash_zip = Arc::clone(&squash_zip);
 				let vfs = Arc::clone(&vfs);
+				let pack_index = Arc::clone(&pack_index);
 
 				let in_flight_tasks_semaphore = Arc::clone(&in_flight_tasks_semaphore);
 				let pack_file_optimization_failed = Arc::clone(&pack_file_optimization_failed);
@@ -433,6 +468,7 @@ impl PackSquasher {
 								}),
 								&*squash_zip,
 								&*vfs,
+								&pack_index,
 								&asset_type_matches,
 								&pack_file_data,
 								&*pack_file_optimization_failed,
@@ -546,6 +582,14 @@ impl PackSquasher {
 
 			// Finally, send warnings about relevant conditions
 			if let Some(tx) = pack_file_status_sender {
+				for sound_file_paths in pack_index.sound_fingerprints().near_duplicate_clusters() {
+					tx.send(PackSquasherStatus::Warning(
+						PackSquasherWarning::NearDuplicateSoundFiles(sound_file_paths)
+					))
+					.await
+					.ok();
+				}
+
 				if let Some(system_id) = system_id::get_system_id() {
 					if system_id.has_low_entropy {
 						tx.send(PackSquasherStatus::Warning(
@@ -627,7 +671,26 @@ pub enum PackSquasherWarning {
 	/// The number of parallel tasks used to process pack files was limited
 	/// due to limits on the number of concurrent open file descriptors.
 	#[cfg(unix)]
-	ConcurrencyLimitedDueToOpenFdLimits
+	ConcurrencyLimitedDueToOpenFdLimits,
+	/// A sound event defined in a `sounds.json` file references a sound
+	/// file that is not in the pack, so the game will fail to play it unless
+	/// another pack provides it.
+	MissingSoundFile {
+		/// The relative path of the `sounds.json` file.
+		sound_definitions_path: String,
+		/// The relative path of the missing sound file.
+		sound_file_path: String
+	},
+	/// A sound file in the pack is not referenced by any sound event defined
+	/// in a `sounds.json` file, so the game will never play it.
+	UnreferencedSoundFile(String),
+	/// The pack icon is not square, so the game may not show it. Contains the
+	/// relative path of the pack icon.
+	NonSquarePackIcon(String),
+	/// Several sound files in the pack sound nearly identical, so they
+	/// might be redundant copies of the same sound. Contains the relative
+	/// paths of such files, sorted in ascending order.
+	NearDuplicateSoundFiles(Vec<String>)
 }
 
 /// A status message concerning an in-progress squash operation.
@@ -698,6 +761,7 @@ async fn match_and_process_pack_file<R: AsyncRead + AsyncSeek + Unpin>(
 	file_options: Option<FileOptions>,
 	squash_zip: &SquashZip<R>,
 	vfs: &impl VirtualFileSystem,
+	pack_index: &PackIndex,
 	asset_type_matches: &PackFileAssetTypeMatches,
 	pack_file_data: &VfsPackFileIterEntry,
 	pack_file_optimization_failed: &AtomicBool,
@@ -706,8 +770,9 @@ async fn match_and_process_pack_file<R: AsyncRead + AsyncSeek + Unpin>(
 	let mut pack_file_open_error = None;
 	let mut vfs_file_meta = None;
 
-	let process_data =
-		asset_type_matches.process_data(file_options, || match vfs.open(&pack_file_data.file_path) {
+	let process_data = asset_type_matches.process_data(
+		file_options,
+		|| match vfs.open(&pack_file_data.file_path) {
 			Ok(vfs_file) => {
 				vfs_file_meta = Some((vfs_file.metadata, vfs_file.file_size_hint));
 				Some((vfs_file.file_read, vfs_file.file_size_hint))
@@ -716,7 +781,13 @@ async fn match_and_process_pack_file<R: AsyncRead + AsyncSeek + Unpin>(
 				pack_file_open_error = Some(err);
 				None
 			}
-		});
+		},
+		&PackFileContext {
+			relative_path: &pack_file_data.relative_path,
+			global_options: &squash_options.global_options,
+			pack_index
+		}
+	);
 
 	let pack_file_process_failed;
 	let have_process_data;
@@ -787,10 +858,11 @@ async fn process_pack_file<F: AsyncRead + AsyncSeek + Unpin>(
 		None => relative_path
 	};
 
-	let copy_previous_file = squash_zip.file_process_time(&pack_file_path).map_or_else(
-		|| false,
-		|squash_time| edit_time.is_some() && Some(squash_time) >= edit_time
-	);
+	let copy_previous_file = !pack_file_process_data.depends_on_other_pack_files
+		&& squash_zip.file_process_time(&pack_file_path).map_or_else(
+			|| false,
+			|squash_time| edit_time.is_some() && Some(squash_time) >= edit_time
+		);
 
 	let mut optimization_error = None;
 	let optimization_strategy;
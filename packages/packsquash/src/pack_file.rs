@@ -11,10 +11,16 @@ use thiserror::Error;
 use tokio::io::AsyncRead;
 use tokio_stream::Stream;
 
+pub use json_file::parse_json;
+pub use png_file::decode_png_pixels;
+pub use shader_file::has_moj_import_directive;
 pub use util::strip_utf8_bom;
 
+use crate::config::GlobalOptions;
 use crate::pack_file::asset_type::PackFileAssetType;
+use crate::pack_index::PackIndex;
 use crate::squash_zip::FileListingCircumstances;
+use crate::RelativePath;
 
 pub mod asset_type;
 
@@ -103,6 +109,13 @@ trait PackFile {
 	fn may_be_directory_listed_atlas_texture_sprite(&self) -> bool {
 		false
 	}
+
+	/// Returns whether the optimized representation of this pack file depends on the contents of
+	/// other pack files. Such pack files are always processed again, because a previous result for
+	/// them may be stale even if they were not modified.
+	fn depends_on_other_pack_files(&self) -> bool {
+		false
+	}
 }
 
 /// Factory trait for a [`PackFile`] that allows it to be instantiated in an standard way. It is separated
@@ -116,7 +129,8 @@ trait PackFileConstructor<R: AsyncRead + Unpin + 'static>: PackFile + Sized {
 	type OptimizationSettings;
 
 	/// Instantiates this pack file with the provided optimization settings, specific to its asset
-	/// type, lazily associating it with the read struct that `file_read_producer` returns.
+	/// type, lazily associating it with the read struct that `file_read_producer` returns. The
+	/// pack file context may be used to get more information about the pack file.
 	///
 	/// This operation will not yield a pack file instance if the pack file should be skipped, or if the
 	/// read struct producer function returns `None`. It is the responsibility of the caller to deal with
@@ -125,10 +139,22 @@ trait PackFileConstructor<R: AsyncRead + Unpin + 'static>: PackFile + Sized {
 	fn new(
 		file_read_producer: impl FnOnce() -> Option<AsyncReadAndSizeHint<R>>,
 		asset_type: PackFileAssetType,
-		optimization_settings: Self::OptimizationSettings
+		optimization_settings: Self::OptimizationSettings,
+		pack_file_context: &PackFileContext<'_>
 	) -> Option<Self>;
 }
 
+/// Information about a pack file that is not contained in its data, such as its location within
+/// the pack or data gathered from other pack files.
+pub struct PackFileContext<'a> {
+	/// The relative path of the pack file.
+	pub relative_path: &'a RelativePath<'a>,
+	/// The global options of the squash operation the pack file is processed in.
+	pub global_options: &'a GlobalOptions,
+	/// Cross-file data about the pack the pack file belongs to.
+	pub pack_index: &'a PackIndex
+}
+
 /// Contains the different pieces of data obtained or related to processing some pack file.
 pub struct PackFileProcessData {
 	/// A stream that contains the byte chunks of the processed pack file data.
@@ -142,5 +168,7 @@ pub struct PackFileProcessData {
 	pub canonical_extension: Option<&'static str>,
 	/// The circumstances affecting how this file is listed (i.e., enumerated) alongside other
 	/// pack files of its type by the game.
-	pub listing_circumstances: FileListingCircumstances
+	pub listing_circumstances: FileListingCircumstances,
+	/// Whether the processed pack file data depends on the contents of other pack files.
+	pub depends_on_other_pack_files: bool
 }
@@ -24,7 +24,9 @@ use crate::{
 	RelativePath
 };
 
-use super::{AsyncReadAndSizeHint, PackFile, PackFileConstructor, PackFileProcessData};
+use super::{
+	AsyncReadAndSizeHint, PackFile, PackFileConstructor, PackFileContext, PackFileProcessData
+};
 
 /// Represents a relevant pack file asset type, stored in a pack file. A [`PackFile`] can
 /// represent assets of several types. An asset type adds constraints on the data format
@@ -50,6 +52,16 @@ pub enum PackFileAssetType {
 	/// A Minecraft block or entity model in vanilla format, maybe with comments and
 	/// `.jsonc` extension.
 	MinecraftModelWithComments,
+	/// A Minecraft language file, used in Minecraft version 1.13 and above, with `.json`
+	/// extension. These files map translation keys to strings in some language.
+	MinecraftLanguage,
+	/// A Minecraft language file, maybe with comments and `.jsonc` extension.
+	MinecraftLanguageWithComments,
+	/// A Minecraft blockstate file, with `.json` extension. These files define the models
+	/// used to render each state of a block.
+	MinecraftBlockState,
+	/// A Minecraft blockstate file, maybe with comments and `.jsonc` extension.
+	MinecraftBlockStateWithComments,
 	/// An OptiFine custom entity model, with `.jem` extension.
 	#[cfg(feature = "optifine-support")]
 	#[doc(cfg(feature = "optifine-support"))]
@@ -104,9 +116,10 @@ pub enum PackFileAssetType {
 	/// Any audio asset in Ogg Vorbis format. Minecraft expects the `.ogg` extension for them.
 	GenericOggVorbisAudio,
 	/// Any audio asset in a supported format, other than Ogg Vorbis. Currently, the other
-	/// supported audio formats and extensions are `.mp3`, `.flac`, `.wav` and `.m4a`. As
-	/// Minecraft does not support these formats, they will be converted to Ogg Vorbis,
-	/// with `.ogg` extension.
+	/// supported audio formats and extensions are `.mp3`, `.flac`, `.wav`, `.aiff` (also
+	/// `.aif` and `.aifc`), `.m4a` (with AAC or ALAC audio) and, if the `opus-support` feature
+	/// is enabled, `.opus`. As Minecraft does not support these formats, they will be converted
+	/// to Ogg Vorbis, with `.ogg` extension.
 	GenericAudio,
 
 	/// The `pack.png` pack icon file, located at the root directory of the pack.
@@ -119,6 +132,27 @@ pub enum PackFileAssetType {
 	/// with alpha blending that are exacerbated with the optimizations PackSquash does. As of
 	/// 15th November, 2021, all released Minecraft versions are affected by these problems.
 	EyeLayer,
+	/// A texture whose pixels are decoded as exact data instead of colors, such as biome
+	/// colormaps and the images in the `misc` texture directory, with `.png` extension.
+	/// These textures must stay bit-exact, so they are only recompressed losslessly, keeping
+	/// their color type.
+	DataTexture,
+	/// A normal map texture of the LabPBR or OldPBR material formats used by shader packs,
+	/// with `_n.png` suffix. Its channels encode the normal vector, ambient occlusion and
+	/// height of the material instead of colors.
+	PbrNormalMap,
+	/// A specular map texture of the LabPBR or OldPBR material formats used by shader packs,
+	/// with `_s.png` suffix. Its channels encode the smoothness, reflectance, porosity and
+	/// emissiveness of the material instead of colors.
+	PbrSpecularMap,
+	/// An emissive map texture used by some shader packs, with `_e.png` suffix. Its channels
+	/// encode the emissiveness of the material instead of colors.
+	PbrEmissiveMap,
+	/// A block texture that vanilla Minecraft renders with the cutout render types, such as
+	/// those of leaves, plants and glass panes, with `.png` extension. These render types
+	/// discard pixels whose alpha is below 0.1, or 0.5 for mipmapped textures such as leaves,
+	/// and render the rest as fully opaque.
+	CutoutTexture,
 	/// A texture that may be used as an input render target in a shader program via a sampler
 	/// uniform.
 	AuxiliaryShaderTargetTexture,
@@ -208,6 +242,20 @@ impl PackFileAssetType {
 			Self::MinecraftModelWithComments => {
 				compile_hardcoded_pack_file_glob_pattern("assets/*/models/{block,item}/**/?*.jsonc")
 			}
+			Self::MinecraftLanguage => {
+				// Minecraft does not look for language files in subdirectories
+				compile_hardcoded_pack_file_glob_pattern("assets/*/lang/?*.json")
+			}
+			Self::MinecraftLanguageWithComments => {
+				compile_hardcoded_pack_file_glob_pattern("assets/*/lang/?*.jsonc")
+			}
+			Self::MinecraftBlockState => {
+				// Block identifiers may contain slashes, which map to subdirectories
+				compile_hardcoded_pack_file_glob_pattern("assets/*/blockstates/**/?*.json")
+			}
+			Self::MinecraftBlockStateWithComments => {
+				compile_hardcoded_pack_file_glob_pattern("assets/*/blockstates/**/?*.jsonc")
+			}
 			#[cfg(feature = "optifine-support")]
 			Self::OptifineCustomEntityModel => compile_hardcoded_pack_file_glob_pattern(
 				"assets/minecraft/{mcpatcher,optifine}/cem/?*.jem"
@@ -275,7 +323,11 @@ impl PackFileAssetType {
 				compile_hardcoded_pack_file_glob_pattern("assets/*/sounds/**/?*.{ogg,oga}")
 			}
 			Self::GenericAudio => {
-				compile_hardcoded_pack_file_glob_pattern("assets/*/sounds/**/?*.{mp3,flac,wav,m4a}")
+				compile_hardcoded_pack_file_glob_pattern(if cfg!(feature = "opus-support") {
+					"assets/*/sounds/**/?*.{mp3,flac,wav,aif,aiff,aifc,m4a,opus}"
+				} else {
+					"assets/*/sounds/**/?*.{mp3,flac,wav,aif,aiff,aifc,m4a}"
+				})
 			}
 
 			Self::PackIcon => compile_hardcoded_pack_file_glob_pattern("pack.png"),
@@ -294,6 +346,61 @@ impl PackFileAssetType {
 					spider_eyes.png,\
 					phantom_eyes.png}"
 			),
+			Self::DataTexture => {
+				compile_hardcoded_pack_file_glob_pattern("assets/*/textures/{colormap,misc}/?*.png")
+			}
+			// These textures are matched before cutout textures on purpose, as some cutout texture
+			// patterns may also match their material maps
+			Self::PbrNormalMap => {
+				compile_hardcoded_pack_file_glob_pattern("assets/*/textures/**/?*_n.png")
+			}
+			Self::PbrSpecularMap => {
+				compile_hardcoded_pack_file_glob_pattern("assets/*/textures/**/?*_s.png")
+			}
+			Self::PbrEmissiveMap => {
+				compile_hardcoded_pack_file_glob_pattern("assets/*/textures/**/?*_e.png")
+			}
+			Self::CutoutTexture => compile_hardcoded_pack_file_glob_pattern(
+				// Stained glass and other textures rendered by translucent render types,
+				// such as water and ice, do not match this pattern on purpose
+				"assets/minecraft/textures/block/{\
+					*_leaves.png,\
+					*_sapling.png,\
+					*_door_top.png,\
+					*_door_bottom.png,\
+					*_trapdoor.png,\
+					glass.png,\
+					glass_pane_top.png,\
+					*rail*.png,\
+					*_stage[0-9].png,\
+					*_stem.png,\
+					*_tulip.png,\
+					dandelion.png,\
+					poppy.png,\
+					blue_orchid.png,\
+					allium.png,\
+					azure_bluet.png,\
+					oxeye_daisy.png,\
+					cornflower.png,\
+					lily_of_the_valley.png,\
+					wither_rose.png,\
+					grass.png,\
+					short_grass.png,\
+					tall_grass_*.png,\
+					grass_block_side_overlay.png,\
+					fern.png,\
+					large_fern_*.png,\
+					dead_bush.png,\
+					vine.png,\
+					ladder.png,\
+					cobweb.png,\
+					iron_bars.png,\
+					torch.png,\
+					*_torch.png,\
+					sugar_cane.png,\
+					red_mushroom.png,\
+					brown_mushroom.png}"
+			),
 			Self::AuxiliaryShaderTargetTexture => {
 				compile_hardcoded_pack_file_glob_pattern("assets/minecraft/textures/effect/**/?*.png")
 			}
@@ -442,6 +549,10 @@ impl PackFileAssetType {
 			Self::MinecraftMetadataWithComments => Some("mcmeta"),
 			Self::MinecraftModel => None,
 			Self::MinecraftModelWithComments => Some("json"),
+			Self::MinecraftLanguage => None,
+			Self::MinecraftLanguageWithComments => Some("json"),
+			Self::MinecraftBlockState => None,
+			Self::MinecraftBlockStateWithComments => Some("json"),
 			#[cfg(feature = "optifine-support")]
 			Self::OptifineCustomEntityModel => None,
 			#[cfg(feature = "optifine-support")]
@@ -469,6 +580,11 @@ impl PackFileAssetType {
 			Self::PackIcon
 			| Self::BannerLayer
 			| Self::EyeLayer
+			| Self::DataTexture
+			| Self::PbrNormalMap
+			| Self::PbrSpecularMap
+			| Self::PbrEmissiveMap
+			| Self::CutoutTexture
 			| Self::AuxiliaryShaderTargetTexture => None,
 			#[cfg(feature = "optifine-support")]
 			Self::OptifineTexture => None,
@@ -504,16 +620,10 @@ impl PackFileAssetTypeMatcher {
 	/// [`RelativePath`]. A mask is used to limit what asset types can match. If the mask contains
 	/// the custom asset type, [PackFileAssetType::Custom], it will be silently excluded from the mask.
 	pub fn new(asset_types_mask: EnumSet<PackFileAssetType>) -> Self {
-		let mut globset_builder = GlobSetBuilder::new();
 		let asset_types_mask = asset_types_mask - PackFileAssetType::Custom;
 
-		// The iteration is done in ascending discriminant order
-		for asset_type in asset_types_mask.iter() {
-			globset_builder.add(asset_type.to_glob_pattern());
-		}
-
 		Self {
-			asset_type_globset: globset_builder.build().unwrap(),
+			asset_type_globset: compile_asset_types_globset(asset_types_mask),
 			asset_types_mask
 		}
 	}
@@ -570,14 +680,20 @@ impl PackFileAssetTypeMatches {
 	pub fn process_data<R: AsyncRead + Send + Unpin + 'static>(
 		&self,
 		file_options: Option<FileOptions>,
-		file_read_producer: impl FnOnce() -> Option<AsyncReadAndSizeHint<R>>
+		file_read_producer: impl FnOnce() -> Option<AsyncReadAndSizeHint<R>>,
+		pack_file_context: &PackFileContext<'_>
 	) -> Option<PackFileProcessData> {
 		for asset_type in &*self.matches {
 			macro_rules! return_pack_file_to_process_data {
 				($file_type:ident, $optimization_settings:expr) => {
 					return pack_file_to_process_data(
 						*asset_type,
-						$file_type::new(file_read_producer, *asset_type, $optimization_settings)
+						$file_type::new(
+							file_read_producer,
+							*asset_type,
+							$optimization_settings,
+							pack_file_context
+						)
 					)
 				};
 			}
@@ -623,6 +739,30 @@ impl PackFileAssetTypeMatches {
 				{
 					return_pack_file_to_process_data!(JsonFile, optimization_settings)
 				}
+				PackFileAssetType::MinecraftLanguage
+					if let Some(FileOptions::JsonFileOptions(optimization_settings)) =
+						file_options =>
+				{
+					return_pack_file_to_process_data!(JsonFile, optimization_settings)
+				}
+				PackFileAssetType::MinecraftLanguageWithComments
+					if let Some(FileOptions::JsonFileOptions(optimization_settings)) =
+						file_options =>
+				{
+					return_pack_file_to_process_data!(JsonFile, optimization_settings)
+				}
+				PackFileAssetType::MinecraftBlockState
+					if let Some(FileOptions::JsonFileOptions(optimization_settings)) =
+						file_options =>
+				{
+					return_pack_file_to_process_data!(JsonFile, optimization_settings)
+				}
+				PackFileAssetType::MinecraftBlockStateWithComments
+					if let Some(FileOptions::JsonFileOptions(optimization_settings)) =
+						file_options =>
+				{
+					return_pack_file_to_process_data!(JsonFile, optimization_settings)
+				}
 				#[cfg(feature = "optifine-support")]
 				PackFileAssetType::OptifineCustomEntityModel
 					if let Some(FileOptions::JsonFileOptions(optimization_settings)) =
@@ -735,6 +875,36 @@ impl PackFileAssetTypeMatches {
 				{
 					return_pack_file_to_process_data!(PngFile, optimization_settings)
 				}
+				PackFileAssetType::DataTexture
+					if let Some(FileOptions::PngFileOptions(optimization_settings)) =
+						file_options =>
+				{
+					return_pack_file_to_process_data!(PngFile, optimization_settings)
+				}
+				PackFileAssetType::PbrNormalMap
+					if let Some(FileOptions::PngFileOptions(optimization_settings)) =
+						file_options =>
+				{
+					return_pack_file_to_process_data!(PngFile, optimization_settings)
+				}
+				PackFileAssetType::PbrSpecularMap
+					if let Some(FileOptions::PngFileOptions(optimization_settings)) =
+						file_options =>
+				{
+					return_pack_file_to_process_data!(PngFile, optimization_settings)
+				}
+				PackFileAssetType::PbrEmissiveMap
+					if let Some(FileOptions::PngFileOptions(optimization_settings)) =
+						file_options =>
+				{
+					return_pack_file_to_process_data!(PngFile, optimization_settings)
+				}
+				PackFileAssetType::CutoutTexture
+					if let Some(FileOptions::PngFileOptions(optimization_settings)) =
+						file_options =>
+				{
+					return_pack_file_to_process_data!(PngFile, optimization_settings)
+				}
 				PackFileAssetType::AuxiliaryShaderTargetTexture
 					if let Some(FileOptions::PngFileOptions(optimization_settings)) =
 						file_options =>
@@ -867,6 +1037,20 @@ pub fn tweak_asset_types_mask_from_global_options(
 	asset_types_mask
 }
 
+/// Compiles a glob set that matches the [`RelativePath`]s of pack files of any of the specified
+/// asset types, except for [PackFileAssetType::Custom], which can't be matched.
+pub fn compile_asset_types_globset(asset_types: EnumSet<PackFileAssetType>) -> GlobSet {
+	let mut globset_builder = GlobSetBuilder::new();
+
+	// The iteration is done in ascending discriminant order, so glob indexes can be mapped
+	// back to asset types
+	for asset_type in (asset_types - PackFileAssetType::Custom).iter() {
+		globset_builder.add(asset_type.to_glob_pattern());
+	}
+
+	globset_builder.build().unwrap()
+}
+
 /// Converts a maybe [`PackFile`] of some statically-known concrete type, of some asset type, to the
 /// more generic [`PackFileProcessData`] struct that abstracts client away code from the implementation
 /// types of the [`PackFile`].
@@ -887,6 +1071,7 @@ fn pack_file_to_process_data(
 			is_directory_listed_atlas_texture_sprite: pack_file
 				.may_be_directory_listed_atlas_texture_sprite()
 		},
+		depends_on_other_pack_files: pack_file.depends_on_other_pack_files(),
 		optimized_byte_chunks_stream: Box::new(pack_file.process().map(|byte_chunk_result| {
 			match byte_chunk_result {
 				Ok((optimization_strategy, optimized_bytes)) => Ok((
@@ -10,19 +10,28 @@ use std::borrow::Cow;
 use std::cell::Cell;
 use std::cmp;
 use std::io::{Cursor, Read, Seek};
-use std::num::NonZeroU32;
+use std::num::{NonZeroU32, NonZeroU8};
+use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
 use tokio::io::AsyncRead;
 use tokio_util::codec::{Decoder, FramedRead};
 use vorbis_rs::{VorbisBitrateManagementStrategy, VorbisEncoderBuilder};
 
-use crate::config::{AudioBitrateControlMode, AudioFileOptions, ChannelMixingOption};
+use crate::config::{
+	AudioBitrateControlMode, AudioFileOptions, ChannelCount, ChannelMixingOption,
+	SamplingFrequencyOption
+};
 use crate::pack_file::asset_type::PackFileAssetType;
 use crate::pack_file::AsyncReadAndSizeHint;
-use signal_processor::decode_and_process_sample_blocks;
+use crate::pack_index::{SoundFingerprint, SoundFingerprints, SoundProperties};
+use signal_processor::{
+	decode_and_process_sample_blocks, has_extraneous_data, signal_to_noise_ratio, trim_silence,
+	FilterChainParameters, LoudnessMeter, SignalAnalyzer, SoundFingerprinter
+};
 use vorbis_stream_mangler::ValidatingAndObfuscatingOggVorbisStreamMangler;
 
-use super::{PackFile, PackFileConstructor};
+use super::{PackFile, PackFileConstructor, PackFileContext};
 
 #[cfg(test)]
 mod tests;
@@ -38,6 +47,13 @@ const POSITIONAL_AUDIO_SAMPLING_FREQUENCY: NonZeroU32 = NonZeroU32::new(32_000).
 /// "Non-positional" sounds are named as such because they are played at a constant volume
 /// by the game, without taking into account the position of any listeners.
 const NON_POSITIONAL_AUDIO_SAMPLING_FREQUENCY: NonZeroU32 = NonZeroU32::new(40_050).unwrap();
+/// The number of frames in each block of buffered samples handed off to the Vorbis encoder.
+const BUFFERED_SAMPLE_BLOCK_SIZE: usize = 4096;
+/// The number of times the quality factor range is halved while searching for the highest
+/// quality factor that fits an audio file in its target file size.
+const TARGET_FILE_SIZE_QUALITY_SEARCH_STEPS: u8 = 7;
+/// The number of leading bytes of audio files that are searched for loop point metadata.
+const LOOP_MARKER_SEARCH_WINDOW: usize = 64 * 1024;
 /// The default target quality for positional sounds, used when transcoding.
 const POSITIONAL_AUDIO_TARGET_QUALITY: f32 = 0.0;
 /// The default target quality for non-positional sounds, used when transcoding. For stereo,
@@ -52,6 +68,9 @@ pub struct AudioFile<T: AsyncRead + Send + Unpin + 'static> {
 	read: T,
 	file_length_hint: usize,
 	is_ogg: bool,
+	is_music_stream: bool,
+	sound_fingerprints: Option<(Arc<SoundFingerprints>, String)>,
+	depends_on_other_pack_files: bool,
 	optimization_settings: AudioFileOptions
 }
 
@@ -59,6 +78,10 @@ pub struct AudioFile<T: AsyncRead + Send + Unpin + 'static> {
 pub struct OptimizerDecoder {
 	optimization_settings: AudioFileOptions,
 	is_ogg: bool,
+	is_music_stream: bool,
+	/// The fingerprints to add the fingerprint of the processed audio file to, alongside its
+	/// relative path, if it should be fingerprinted.
+	sound_fingerprints: Option<(Arc<SoundFingerprints>, String)>,
 	reached_eof: bool
 }
 
@@ -72,7 +95,7 @@ pub enum OptimizationError {
 	Vorbis(#[from] vorbis_rs::VorbisError),
 	#[error("Could not find a decodable audio track. Is this file in a supported format, and its extension correct?")]
 	NoAudioTrack,
-	#[error("Unknown or invalid channel count. Minecraft only supports mono and stereo sounds")]
+	#[error("Unknown or invalid channel count or layout. Is this file corrupt?")]
 	UnsupportedChannelCount,
 	#[error("Unknown sampling frequency. Is this file corrupt?")]
 	UnknownSamplingFrequency,
@@ -82,12 +105,21 @@ pub enum OptimizationError {
 	InvalidTargetSamplingFrequency { sampling_frequency: NonZeroU32 },
 	#[error("An invalid target bitrate was specified in the options")]
 	InvalidTargetBitrate,
+	#[error("An invalid target file size was specified in the options")]
+	InvalidTargetFileSize,
+	#[error("The transcoded audio does not fit in the target file size of {target_file_size} bytes, even at the lowest quality. Please raise it, or lower the sampling frequency")]
+	TargetFileSizeUnreachable { target_file_size: usize },
 	#[error("Resample error: {0}")]
 	ResamplingFailure(#[from] ResampleError),
 	#[error("{0}")]
 	TwoPassOptimization(#[from] ogg_to_ogg::RemuxError),
 	#[error("The Minecraft sample count limit for audio files was exceeded. Please reduce the sampling frequency or duration")]
 	TooLongForMinecraft,
+	#[error("The transcoded audio has a SNR of {signal_to_noise_ratio:.2} dB, below the minimum of {minimum_signal_to_noise_ratio} dB. Try tweaking the bitrate control options")]
+	InsufficientQuality {
+		signal_to_noise_ratio: f64,
+		minimum_signal_to_noise_ratio: f32
+	},
 	#[error("I/O error: {0}")]
 	Io(#[from] std::io::Error)
 }
@@ -138,23 +170,60 @@ impl Decoder for OptimizerDecoder {
 			.optimization_settings
 			.minecraft_version_supports_ogg_obfuscation
 			&& self.optimization_settings.ogg_obfuscation;
+		// Trimming silence would shift the timing of music streams and loop points
+		let silence_trimming_threshold = self
+			.optimization_settings
+			.silence_trimming_threshold
+			.filter(|_| !self.is_music_stream && !has_loop_markers(&input_file));
 
 		// First pass: transcode the input audio file to an efficient Ogg Vorbis representation.
 		// This is necessary if the input audio file is not Ogg Vorbis, or if some modification
-		// to the audio data is done (currently, channel mixing, resampling and pitch shifting)
-		let (transcoded_file, channel_mixing_done, pitch_shifting_done) = if skip_transcoding {
-			(ByteBuffer::Bytes(input_file.clone()), false, false)
+		// to the audio data is done (currently, channel mixing, resampling, pitch shifting,
+		// filtering, silence trimming and loudness normalization)
+		let (
+			transcoded_file,
+			channel_mixing_done,
+			pitch_shifting_done,
+			filtering_done,
+			signal_to_noise_ratio,
+			trimmed_silence_duration,
+			loudness_normalization_gain,
+			target_file_size_quality
+		) = if skip_transcoding {
+			(
+				ByteBuffer::Bytes(input_file.clone()),
+				false,
+				false,
+				false,
+				None,
+				None,
+				None,
+				None
+			)
 		} else {
-			let (transcoded_file, channel_mixing_done) = process_and_transcode(
-				input_file.clone().reader(),
+			let transcoded_audio = process_and_transcode(
+				input_file.clone(),
 				self.is_ogg,
+				silence_trimming_threshold,
+				self.sound_fingerprints.is_some(),
 				&self.optimization_settings
 			)?;
 
+			if let (Some((sound_fingerprints, sound_file_path)), Some(fingerprint)) =
+				(&self.sound_fingerprints, transcoded_audio.fingerprint)
+			{
+				sound_fingerprints.add(sound_file_path.clone(), fingerprint);
+			}
+
 			(
-				ByteBuffer::CowSlice(transcoded_file),
-				channel_mixing_done,
-				self.optimization_settings.target_pitch != 1.0
+				ByteBuffer::CowSlice(transcoded_audio.file),
+				transcoded_audio.channel_mixing_done,
+				self.optimization_settings.target_pitch != 1.0,
+				filter_chain_parameters(&self.optimization_settings).has_filters(),
+				transcoded_audio.signal_to_noise_ratio,
+				transcoded_audio.trimmed_silence_duration,
+				transcoded_audio.loudness_normalization_gain,
+				transcoded_audio.target_file_size_quality
 			)
 		};
 
@@ -172,9 +241,16 @@ impl Decoder for OptimizerDecoder {
 
 		// Third pass: check whether the transcoded and two-pass optimized file is actually smaller.
 		// If not, quickly run OptiVorbis over the original file, which is practically guaranteed to
-		// never return a file bigger than its input, and return that
+		// never return a file bigger than its input, and return that. OptiVorbis expects a single
+		// Vorbis logical stream, though, so this can't be done for files with extraneous data
 		let optimized_file_is_input_file;
-		let can_use_input_as_output = self.is_ogg && !pitch_shifting_done && !channel_mixing_done;
+		let can_use_input_as_output = self.is_ogg
+			&& !pitch_shifting_done
+			&& !filtering_done
+			&& !channel_mixing_done
+			&& trimmed_silence_duration.is_none()
+			&& loudness_normalization_gain.is_none()
+			&& !has_extraneous_data(&input_file);
 
 		let optimized_file = if do_two_pass_optimization_and_validation
 			&& input_file.len() < transcoded_and_optimized_file.as_ref().len()
@@ -189,7 +265,7 @@ impl Decoder for OptimizerDecoder {
 			transcoded_and_optimized_file
 		};
 
-		let optimization_strategy = match (skip_transcoding, do_two_pass_optimization_and_validation, optimized_file_is_input_file) {
+		let optimization_strategy: Cow<'static, str> = match (skip_transcoding, do_two_pass_optimization_and_validation, optimized_file_is_input_file) {
 			(false, false, false) => "Transcoded",
 			(false, true, false) => "Transcoded, validated and optimized",
 			(true, false, false) => "Copied",
@@ -197,34 +273,176 @@ impl Decoder for OptimizerDecoder {
 			(_, _, true) => "Validated and optimized, but transcoding yielded a bigger file. Try tweaking options for extra savings"
 		}.into();
 
+		let optimization_strategy = match trimmed_silence_duration {
+			Some(duration) => Cow::Owned(format!(
+				"{optimization_strategy}, trimmed {} ms of silence",
+				duration.as_millis()
+			)),
+			None => optimization_strategy
+		};
+
+		let optimization_strategy = match loudness_normalization_gain {
+			Some(gain) => Cow::Owned(format!(
+				"{optimization_strategy}, loudness normalized with {gain:+.2} dB gain"
+			)),
+			None => optimization_strategy
+		};
+
+		// The quality of the transcoded audio only matters if it is the output
+		let optimization_strategy =
+			match target_file_size_quality.filter(|_| !optimized_file_is_input_file) {
+				Some(quality) => Cow::Owned(format!(
+					"{optimization_strategy}, fit in target file size at quality {quality:.2}"
+				)),
+				None => optimization_strategy
+			};
+
+		let optimization_strategy =
+			match signal_to_noise_ratio.filter(|_| !optimized_file_is_input_file) {
+				Some(signal_to_noise_ratio) => {
+					// minimum_signal_to_noise_ratio is always set when the SNR was computed
+					let minimum_signal_to_noise_ratio = self
+						.optimization_settings
+						.minimum_signal_to_noise_ratio
+						.unwrap();
+
+					if signal_to_noise_ratio < minimum_signal_to_noise_ratio as f64 {
+						return Err(OptimizationError::InsufficientQuality {
+							signal_to_noise_ratio,
+							minimum_signal_to_noise_ratio
+						});
+					}
+
+					Cow::Owned(format!(
+						"{optimization_strategy} (SNR {signal_to_noise_ratio:.2} dB)"
+					))
+				}
+				None => optimization_strategy
+			};
+
 		Ok(Some((optimization_strategy, optimized_file)))
 	}
 }
 
+/// The result of processing and transcoding an audio file to Ogg Vorbis.
+struct TranscodedAudio {
+	/// The transcoded Ogg Vorbis file.
+	file: Cow<'static, [u8]>,
+	/// Whether the channels of the audio were downmixed or upmixed.
+	channel_mixing_done: bool,
+	/// The signal-to-noise ratio of the transcoded file, in decibels, if computed.
+	signal_to_noise_ratio: Option<f64>,
+	/// The duration of the leading and trailing silence that was trimmed off, if any.
+	trimmed_silence_duration: Option<Duration>,
+	/// The gain applied to normalize the loudness of the audio, in decibels, if any.
+	loudness_normalization_gain: Option<f64>,
+	/// The quality factor chosen to fit the audio in its target file size, if any.
+	target_file_size_quality: Option<f32>,
+	/// The perceptual fingerprint of the processed audio, if computed and meaningful.
+	fingerprint: Option<SoundFingerprint>
+}
+
 /// Processes the input audio file and transcodes it to Ogg Vorbis, according to the
-/// provided optimization settings. The audio signal processing done may include resampling,
-/// pitch shifting and channel mixing. Empty sound files (e.g., without audio samples, or only
-/// containing audio samples which are complete silence) may be special-cased for optimization,
-/// yielding a minimal empty Ogg Vorbis file.
+/// provided optimization settings. The audio signal processing done may include filtering,
+/// resampling, pitch shifting, channel mixing and trimming silence quieter than the specified
+/// threshold, in dBFS. Empty sound files (e.g., without audio samples, or only containing
+/// audio samples which are complete silence) may be special-cased for optimization, yielding
+/// a minimal empty Ogg Vorbis file.
+///
+/// If the channels or sampling frequency are to be automatically selected, the input audio
+/// file is decoded and processed twice: first to analyze it, and then to transcode it.
+///
+/// If a minimum signal-to-noise ratio is set in the optimization settings, the ratio of the
+/// transcoded file is computed and returned too, unless it's not meaningful for the file.
+/// Likewise, if silence is trimmed or a loudness normalization target is set, the processed
+/// audio samples are buffered, trimmed and amplified to reach it before being encoded. The
+/// processed audio samples are buffered too when targeting a file size, so that they can be
+/// encoded several times to find the highest quality factor that fits in that size.
+///
+/// If requested, a perceptual fingerprint of the processed audio samples is computed as they
+/// are decoded, before silence is trimmed and loudness is normalized.
 fn process_and_transcode(
-	input_file: impl Read + Send + Sync + 'static,
+	input_file: Bytes,
 	is_ogg: bool,
+	silence_trimming_threshold: Option<f32>,
+	fingerprint_sound: bool,
 	optimization_settings: &AudioFileOptions
-) -> Result<(Cow<'static, [u8]>, bool), OptimizationError> {
+) -> Result<TranscodedAudio, OptimizationError> {
+	let signal_analyzer = if matches!(optimization_settings.channels, ChannelMixingOption::Auto)
+		|| matches!(
+			optimization_settings.sampling_frequency,
+			Some(SamplingFrequencyOption::Auto)
+		) {
+		Some(analyze_signal(
+			input_file.clone(),
+			is_ogg,
+			optimization_settings
+		)?)
+	} else {
+		None
+	};
+
 	// FIXME write to a SpooledTempFile whose maximum memory buffer size
 	// is controlled by a global budget, once that refactor is complete
 	let mut transcoded_file = vec![];
 	let encoder = Cell::new(None);
+	let sound_fingerprinter = Cell::new(None);
+
+	let target_file_size = match optimization_settings.bitrate_control_mode {
+		AudioBitrateControlMode::TargetFileSize => Some(target_bitrate_control_metric_to_file_size(
+			optimization_settings
+		)?),
+		_ => None
+	};
+
+	// Keep the encoded samples around to compare them with the transcoded file, if needed
+	let mut encoded_samples = optimization_settings
+		.minimum_signal_to_noise_ratio
+		.map(|_| Vec::<Vec<f32>>::new());
+
+	// Keep the processed samples around to trim their silence and normalize their loudness
+	// before encoding them, if needed. The whole signal must be known to do that
+	let mut buffered_samples = (silence_trimming_threshold.is_some()
+		|| optimization_settings
+			.loudness_normalization_target
+			.is_some()
+		|| target_file_size.is_some())
+	.then(Vec::<Vec<f32>>::new);
+	let mut output_sampling_frequency = None;
+	let mut output_channel_count = None;
+
+	let mut encode_block = |block: &[Vec<f32>]| {
+		if let Some(mut vorbis_encoder) = encoder.take() {
+			vorbis_encoder.encode_audio_block(block)?;
+			encoder.set(Some(vorbis_encoder));
+		}
+
+		if let Some(encoded_samples) = &mut encoded_samples {
+			append_sample_block(encoded_samples, block);
+		}
+
+		Ok::<_, OptimizationError>(())
+	};
 
 	let mut channel_mixing_done = false;
 	let is_silence = decode_and_process_sample_blocks(
-		input_file,
+		input_file.reader(),
 		is_ogg,
 		match optimization_settings.channels {
 			ChannelMixingOption::ToChannels(count) => Some(count),
+			// Downmix near-identical stereo channels to mono
+			ChannelMixingOption::Auto => signal_analyzer
+				.as_ref()
+				.and_then(SignalAnalyzer::side_to_mid_energy_ratio)
+				.filter(|side_to_mid_energy_ratio| {
+					*side_to_mid_energy_ratio
+						< optimization_settings.automatic_channel_mixing_threshold as f64
+				})
+				.map(|_| ChannelCount::MONO),
 			ChannelMixingOption::Skip => None
 		},
-		|input_sampling_frequency, input_channel_count, output_channel_count| {
+		|input_sampling_frequency, input_channel_count, channel_count| {
+			let output_channel_count = *output_channel_count.insert(channel_count);
 			let is_positional_audio = output_channel_count.get() == 1;
 
 			channel_mixing_done = input_channel_count != output_channel_count;
@@ -233,20 +451,38 @@ fn process_and_transcode(
 			// this point: it doesn't add meaningful audio information or helps using
 			// different signal processing filters, but it definitely increases space
 			// costs. Let's not do that
-			let output_sampling_frequency = cmp::min(
-				optimization_settings
-					.sampling_frequency
-					.unwrap_or(if is_positional_audio {
-						POSITIONAL_AUDIO_SAMPLING_FREQUENCY
-					} else {
-						NON_POSITIONAL_AUDIO_SAMPLING_FREQUENCY
-					}),
+			let sampling_frequency = *output_sampling_frequency.insert(cmp::min(
+				match (optimization_settings.sampling_frequency, &signal_analyzer) {
+					(Some(SamplingFrequencyOption::ToFrequency(sampling_frequency)), _) => {
+						sampling_frequency
+					}
+					(Some(SamplingFrequencyOption::Auto), Some(signal_analyzer)) => signal_analyzer
+						.bandwidth_preserving_sampling_frequency(
+							optimization_settings.automatic_sampling_frequency_threshold as f64
+						),
+					_ => {
+						if is_positional_audio {
+							POSITIONAL_AUDIO_SAMPLING_FREQUENCY
+						} else {
+							NON_POSITIONAL_AUDIO_SAMPLING_FREQUENCY
+						}
+					}
+				},
 				input_sampling_frequency
-			);
+			));
+
+			if fingerprint_sound {
+				sound_fingerprinter.set(SoundFingerprinter::new(sampling_frequency));
+			}
+
+			// The encoder settings for a target file size are only known after buffering
+			if target_file_size.is_some() {
+				return Ok(sampling_frequency);
+			}
 
 			encoder.set(Some(
 				VorbisEncoderBuilder::new_with_serial(
-					output_sampling_frequency,
+					sampling_frequency,
 					output_channel_count,
 					&mut transcoded_file,
 					// Use a fixed serial for better compressibility when not using OptiVorbis,
@@ -279,22 +515,83 @@ fn process_and_transcode(
 							)?
 						}
 					}
+					AudioBitrateControlMode::TargetFileSize => unreachable!()
 				})
 				.build()?
 			));
 
-			Ok(output_sampling_frequency)
+			Ok(sampling_frequency)
 		},
 		optimization_settings.target_pitch,
+		filter_chain_parameters(optimization_settings),
 		|block| {
-			if let Some(mut vorbis_encoder) = encoder.take() {
-				vorbis_encoder.encode_audio_block(block)?;
-				encoder.set(Some(vorbis_encoder));
+			if let Some(mut fingerprinter) = sound_fingerprinter.take() {
+				fingerprinter.process(block);
+				sound_fingerprinter.set(Some(fingerprinter));
 			}
 
-			Ok(())
+			match &mut buffered_samples {
+				Some(buffered_samples) => {
+					append_sample_block(buffered_samples, block);
+					Ok(())
+				}
+				None => encode_block(block)
+			}
 		}
 	)?;
+	let fingerprint = sound_fingerprinter
+		.into_inner()
+		.and_then(SoundFingerprinter::fingerprint);
+
+	// Trim the silence of the buffered samples, measure their loudness, amplify them to reach
+	// the target loudness without exceeding the true peak ceiling, and finally encode them
+	let mut trimmed_silence_duration = None;
+	let mut loudness_normalization_gain = None;
+	let mut target_file_size_samples = None;
+	if let Some(mut samples) = buffered_samples.filter(|samples| !samples.is_empty()) {
+		// The sampling frequency is always known when some samples were processed
+		let sampling_frequency = output_sampling_frequency.unwrap();
+
+		if let Some(threshold) = silence_trimming_threshold {
+			let fade_length = (sampling_frequency.get() as u64
+				* optimization_settings.silence_trimming_fade_duration as u64
+				/ 1000) as usize;
+
+			let trimmed_frames =
+				trim_silence(&mut samples, 10f32.powf(threshold / 20.0), fade_length);
+
+			if trimmed_frames > 0 {
+				trimmed_silence_duration = Some(Duration::from_secs_f64(
+					trimmed_frames as f64 / sampling_frequency.get() as f64
+				));
+			}
+		}
+
+		if let Some(target_loudness) = optimization_settings.loudness_normalization_target {
+			let mut loudness_meter = LoudnessMeter::new(sampling_frequency, samples.len());
+			loudness_meter.process(&samples);
+
+			if let Some(loudness) = loudness_meter.integrated_loudness() {
+				let gain = (target_loudness as f64 - loudness)
+					.min(optimization_settings.true_peak_ceiling as f64 - loudness_meter.true_peak());
+				let linear_gain = 10f64.powf(gain / 20.0) as f32;
+
+				for sample in samples.iter_mut().flatten() {
+					*sample *= linear_gain;
+				}
+
+				loudness_normalization_gain = Some(gain);
+			}
+		}
+
+		if target_file_size.is_some() {
+			target_file_size_samples = Some(samples);
+		} else {
+			for block in sample_blocks(&samples) {
+				encode_block(&block)?;
+			}
+		}
+	}
 
 	// Explicitly finish the Ogg Vorbis stream, so we don't ignore any errors there
 	if let Some(vorbis_encoder) = encoder.take() {
@@ -302,6 +599,32 @@ fn process_and_transcode(
 	}
 	drop(encoder);
 
+	// Search for the highest quality factor that fits the buffered samples in the target
+	// file size, if any and they will not be replaced by an empty file. The sampling
+	// frequency and channel count are always known when the input file was decoded
+	let mut target_file_size_quality = None;
+	if let (Some(target_file_size), Some(sampling_frequency), Some(channel_count)) = (
+		target_file_size.filter(|_| !is_silence || !optimization_settings.empty_audio_optimization),
+		output_sampling_frequency,
+		output_channel_count
+	) {
+		let samples = target_file_size_samples.unwrap_or_default();
+		let (quality, file) = encode_to_target_file_size(
+			&samples,
+			sampling_frequency,
+			channel_count,
+			target_file_size,
+			optimization_settings.two_pass_vorbis_optimization_and_validation
+		)?;
+
+		transcoded_file = file;
+		target_file_size_quality = Some(quality);
+
+		if let Some(encoded_samples) = &mut encoded_samples {
+			*encoded_samples = samples;
+		}
+	}
+
 	Ok(
 		if is_silence && optimization_settings.empty_audio_optimization {
 			// Use a specially crafted minimal Ogg Vorbis file to represent
@@ -318,16 +641,221 @@ fn process_and_transcode(
 			// no audio data. Minecraft handles this fine, but programs that
 			// insist on decoding at least a sample may treat this as an error
 			// condition (e.g., GStreamer)
-			(
-				Cow::Borrowed(include_bytes!("audio_file/empty.ogg")),
-				channel_mixing_done
-			)
+			TranscodedAudio {
+				file: Cow::Borrowed(include_bytes!("audio_file/empty.ogg")),
+				channel_mixing_done,
+				signal_to_noise_ratio: None,
+				trimmed_silence_duration: None,
+				loudness_normalization_gain: None,
+				target_file_size_quality: None,
+				fingerprint: None
+			}
 		} else {
-			(Cow::Owned(transcoded_file), channel_mixing_done)
+			let signal_to_noise_ratio = encoded_samples
+				.map(|encoded_samples| {
+					signal_to_noise_ratio(&encoded_samples, Cursor::new(&transcoded_file))
+				})
+				.transpose()?
+				.flatten();
+
+			TranscodedAudio {
+				file: Cow::Owned(transcoded_file),
+				channel_mixing_done,
+				signal_to_noise_ratio,
+				trimmed_silence_duration,
+				loudness_normalization_gain,
+				target_file_size_quality,
+				fingerprint
+			}
 		}
 	)
 }
 
+/// Encodes the specified planar samples to Ogg Vorbis with the highest quality factor whose
+/// output fits in the specified target file size, in bytes, by bisecting the quality factor
+/// range. If the output will be optimized with OptiVorbis, its optimized size is the one
+/// checked against the target. Returns the chosen quality factor, in the [-2, 10] range, and
+/// the encoded file.
+fn encode_to_target_file_size(
+	samples: &[Vec<f32>],
+	sampling_frequency: NonZeroU32,
+	channel_count: NonZeroU8,
+	target_file_size: usize,
+	measure_optimized_size: bool
+) -> Result<(f32, Vec<u8>), OptimizationError> {
+	let encode = |quality: f32| {
+		let mut file = vec![];
+
+		let mut encoder = VorbisEncoderBuilder::new_with_serial(
+			sampling_frequency,
+			channel_count,
+			&mut file,
+			// Use the same serial as for any other transcoded file
+			1
+		)
+		.minimum_page_data_size(Some(u16::MAX))
+		.bitrate_management_strategy(VorbisBitrateManagementStrategy::QualityVbr {
+			target_quality: quality / 10.0
+		})
+		.build()?;
+
+		for block in sample_blocks(samples) {
+			encoder.encode_audio_block(&block)?;
+		}
+		encoder.finish()?;
+
+		let file_size = if measure_optimized_size {
+			validate_and_optimize(Cursor::new(&file), false)?.len()
+		} else {
+			file.len()
+		};
+
+		Ok::<_, OptimizationError>((file_size <= target_file_size).then_some(file))
+	};
+
+	let (mut lowest_quality, mut highest_quality) = (-2.0, 10.0);
+
+	if let Some(file) = encode(highest_quality)? {
+		return Ok((highest_quality, file));
+	}
+
+	let mut best_fit = encode(lowest_quality)?
+		.ok_or(OptimizationError::TargetFileSizeUnreachable { target_file_size })?;
+
+	// Invariant: the lowest quality fits in the target file size, but the highest does not
+	for _ in 0..TARGET_FILE_SIZE_QUALITY_SEARCH_STEPS {
+		let quality = (lowest_quality + highest_quality) / 2.0;
+
+		match encode(quality)? {
+			Some(file) => {
+				lowest_quality = quality;
+				best_fit = file;
+			}
+			None => highest_quality = quality
+		}
+	}
+
+	Ok((lowest_quality, best_fit))
+}
+
+/// Splits the specified planar samples into blocks of at most [BUFFERED_SAMPLE_BLOCK_SIZE]
+/// frames, ready to be handed off to the Vorbis encoder.
+fn sample_blocks(samples: &[Vec<f32>]) -> impl Iterator<Item = Vec<Vec<f32>>> + '_ {
+	let sample_count = samples.first().map_or(0, Vec::len);
+
+	(0..sample_count)
+		.step_by(BUFFERED_SAMPLE_BLOCK_SIZE)
+		.map(move |block_start| {
+			let block_end = cmp::min(block_start + BUFFERED_SAMPLE_BLOCK_SIZE, sample_count);
+
+			samples
+				.iter()
+				.map(|channel_samples| channel_samples[block_start..block_end].to_vec())
+				.collect()
+		})
+}
+
+/// Decodes and processes the input audio file like [process_and_transcode] does, without
+/// resampling or channel mixing it, and analyzes the resulting signal.
+fn analyze_signal(
+	input_file: Bytes,
+	is_ogg: bool,
+	optimization_settings: &AudioFileOptions
+) -> Result<SignalAnalyzer, OptimizationError> {
+	let signal_analyzer = Cell::new(None);
+
+	decode_and_process_sample_blocks(
+		input_file.reader(),
+		is_ogg,
+		None,
+		|input_sampling_frequency, _, output_channel_count| {
+			signal_analyzer.set(Some(SignalAnalyzer::new(
+				input_sampling_frequency,
+				output_channel_count.get() as usize
+			)));
+
+			Ok(input_sampling_frequency)
+		},
+		optimization_settings.target_pitch,
+		filter_chain_parameters(optimization_settings),
+		|block| {
+			if let Some(mut analyzer) = signal_analyzer.take() {
+				analyzer.process(block);
+				signal_analyzer.set(Some(analyzer));
+			}
+
+			Ok(())
+		}
+	)?;
+
+	// The analyzer is always created when the input file is successfully decoded
+	Ok(signal_analyzer.into_inner().unwrap())
+}
+
+/// Appends the specified block of planar samples to the specified planar sample buffer.
+fn append_sample_block(samples: &mut Vec<Vec<f32>>, block: &[Vec<f32>]) {
+	samples.resize_with(block.len(), Vec::new);
+	for (channel_samples, block_channel_samples) in samples.iter_mut().zip(block) {
+		channel_samples.extend_from_slice(block_channel_samples);
+	}
+}
+
+/// Returns the parameters of the filter chain to apply to the audio before resampling it, as
+/// defined in the specified optimization settings.
+fn filter_chain_parameters(optimization_settings: &AudioFileOptions) -> FilterChainParameters {
+	FilterChainParameters {
+		high_pass_cutoff_frequency: optimization_settings.high_pass_filter_cutoff_frequency,
+		low_pass_cutoff_frequency: optimization_settings.low_pass_filter_cutoff_frequency,
+		limiter_threshold: optimization_settings.limiter_threshold
+	}
+}
+
+/// Checks whether the specified asset path belongs to a music stream, such as a background
+/// music track or a music disc. These are conventionally stored in the `music` and `records`
+/// sound folders of a namespace.
+fn is_music_stream(relative_path: &str) -> bool {
+	let mut path_components = relative_path.split('/');
+
+	path_components.next() == Some("assets")
+		&& path_components.nth(1) == Some("sounds")
+		&& matches!(path_components.next(), Some("music" | "records"))
+}
+
+/// Checks whether the specified audio file contains metadata that defines loop points. These
+/// are commonly stored in `LOOPSTART` Vorbis comments or ID3 frames near the start of the file,
+/// or in the sampler chunk of WAV files.
+fn has_loop_markers(file: &[u8]) -> bool {
+	if file.starts_with(b"RIFF") && file.get(8..12) == Some(&b"WAVE"[..]) {
+		let mut chunks = &file[12..];
+		while let Some((chunk_header, chunk_data)) = chunks.split_first_chunk::<8>() {
+			let chunk_length = u32::from_le_bytes(chunk_header[4..].try_into().unwrap()) as usize;
+
+			// The sampler chunk stores its number of sample loops at offset 28
+			if &chunk_header[..4] == b"smpl"
+				&& chunk_data
+					.get(28..32)
+					.is_some_and(|loop_count| loop_count != [0; 4])
+			{
+				return true;
+			}
+
+			// Chunks are padded to an even length
+			chunks = chunk_data
+				.get(chunk_length + chunk_length % 2..)
+				.unwrap_or_default();
+		}
+	}
+
+	let file_header = &file[..cmp::min(file.len(), LOOP_MARKER_SEARCH_WINDOW)];
+	[&b"LOOPSTART"[..], b"LOOP_START"]
+		.iter()
+		.any(|loop_marker| {
+			file_header
+				.windows(loop_marker.len())
+				.any(|window| window.eq_ignore_ascii_case(loop_marker))
+		})
+}
+
 /// Validates and optimizes the specified Ogg Vorbis file in two passes, using OptiVorbis.
 fn validate_and_optimize<T: Read + Seek>(
 	input_file: T,
@@ -385,6 +913,20 @@ fn target_bitrate_control_metric_to_quality(
 	target_bitrate_control_metric / 10.0
 }
 
+/// Converts the applicable target bitrate control metric, as defined in the specified
+/// optimization settings, to a target file size in bytes.
+fn target_bitrate_control_metric_to_file_size(
+	optimization_settings: &AudioFileOptions
+) -> Result<usize, OptimizationError> {
+	let target_bitrate_control_metric = optimization_settings
+		.target_bitrate_control_metric
+		.filter(|target_file_size| *target_file_size > 0.0)
+		.ok_or(OptimizationError::InvalidTargetFileSize)?;
+
+	// Convert the more user-friendly unit of KiB to bytes
+	Ok((target_bitrate_control_metric * 1024.0) as usize)
+}
+
 /// Converts the applicable target bitrate control metric, as defined in the specified
 /// optimization settings, to a bitrate ready to pass on to a Vorbis encoder.
 fn target_bitrate_control_metric_to_bitrate(
@@ -412,6 +954,8 @@ impl<T: AsyncRead + Send + Unpin + 'static> PackFile for AudioFile<T> {
 			OptimizerDecoder {
 				optimization_settings: self.optimization_settings,
 				is_ogg: self.is_ogg,
+				is_music_stream: self.is_music_stream,
+				sound_fingerprints: self.sound_fingerprints,
 				reached_eof: false
 			},
 			self.file_length_hint
@@ -421,6 +965,10 @@ impl<T: AsyncRead + Send + Unpin + 'static> PackFile for AudioFile<T> {
 	fn is_compressed(&self) -> bool {
 		true
 	}
+
+	fn depends_on_other_pack_files(&self) -> bool {
+		self.depends_on_other_pack_files
+	}
 }
 
 impl<T: AsyncRead + Send + Unpin + 'static> PackFileConstructor<T> for AudioFile<T> {
@@ -429,13 +977,44 @@ impl<T: AsyncRead + Send + Unpin + 'static> PackFileConstructor<T> for AudioFile
 	fn new(
 		file_read_producer: impl FnOnce() -> Option<AsyncReadAndSizeHint<T>>,
 		asset_type: PackFileAssetType,
-		optimization_settings: Self::OptimizationSettings
+		optimization_settings: Self::OptimizationSettings,
+		pack_file_context: &PackFileContext<'_>
 	) -> Option<Self> {
+		let relative_path = pack_file_context.relative_path.as_str();
+		let sound_properties = pack_file_context.pack_index.sound_properties(relative_path);
+
+		// How sounds are processed depends on the sound events that reference them, which are
+		// defined in sounds.json files
+		let depends_on_other_pack_files = sound_properties.is_some()
+			|| !optimization_settings.streamed_sound_overrides.is_empty()
+			|| !optimization_settings
+				.non_streamed_sound_overrides
+				.is_empty();
+
+		// Apply the overrides for the properties sound events give to this sound, if any
+		let optimization_settings = match sound_properties {
+			Some(SoundProperties { streamed: true }) => {
+				optimization_settings.with_overrides(optimization_settings.streamed_sound_overrides)
+			}
+			Some(SoundProperties { streamed: false }) => optimization_settings
+				.with_overrides(optimization_settings.non_streamed_sound_overrides),
+			None => optimization_settings
+		};
+
 		file_read_producer().map(|(read, file_length_hint)| Self {
 			read,
 			// The file is too big to fit in memory if this conversion fails anyway
 			file_length_hint: file_length_hint.try_into().unwrap_or(usize::MAX),
 			is_ogg: matches!(asset_type, PackFileAssetType::GenericOggVorbisAudio),
+			is_music_stream: is_music_stream(relative_path)
+				|| sound_properties.is_some_and(|properties| properties.streamed),
+			sound_fingerprints: optimization_settings.near_duplicate_detection.then(|| {
+				(
+					Arc::clone(pack_file_context.pack_index.sound_fingerprints()),
+					relative_path.to_string()
+				)
+			}),
+			depends_on_other_pack_files,
 			optimization_settings
 		})
 	}
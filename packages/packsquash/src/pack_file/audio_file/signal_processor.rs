@@ -9,8 +9,9 @@ use dasp_signal::Signal;
 use rubato::{FftFixedIn, Resampler};
 use std::io::{ErrorKind, Read};
 use std::num::{NonZeroU32, NonZeroU8};
-use symphonia::core::audio::SampleBuffer;
-use symphonia::core::codecs::{Decoder, DecoderOptions, CODEC_TYPE_NULL};
+use std::sync::LazyLock;
+use symphonia::core::audio::{Channels, SampleBuffer};
+use symphonia::core::codecs::{CodecRegistry, Decoder, DecoderOptions};
 use symphonia::core::formats::{FormatOptions, FormatReader};
 use symphonia::core::io::{MediaSourceStream, MediaSourceStreamOptions, ReadOnlySource};
 use symphonia::core::meta::{Limit, MetadataOptions};
@@ -18,6 +19,30 @@ use symphonia::core::probe::Hint;
 use symphonia::core::units::Duration;
 use vorbis_rs::{VorbisDecoder, VorbisError, VorbisLibraryErrorKind};
 
+pub use filter_chain::FilterChainParameters;
+pub use loudness_meter::LoudnessMeter;
+pub use ogg_stream_filter::has_extraneous_data;
+pub use signal_analyzer::SignalAnalyzer;
+pub use silence_trimmer::trim_silence;
+pub use sound_fingerprinter::SoundFingerprinter;
+
+use downmixer::{vorbis_channel_layout, StereoDownmixMatrix};
+use filter_chain::FilterChain;
+use ogg_stream_filter::OggVorbisStreamFilter;
+#[cfg(feature = "opus-support")]
+use opus_decoder::OpusDecoder;
+
+mod biquad;
+mod downmixer;
+mod filter_chain;
+mod loudness_meter;
+mod ogg_stream_filter;
+#[cfg(feature = "opus-support")]
+mod opus_decoder;
+mod signal_analyzer;
+mod silence_trimmer;
+mod sound_fingerprinter;
+
 /// The count of frames (i.e. an audio sample for each channel) that will be accumulated
 /// in a block before being handed off to resamplers and consumers. This controls the
 /// trade-off between efficiency (reducing call overhead by operating on many frames at
@@ -25,14 +50,24 @@ use vorbis_rs::{VorbisDecoder, VorbisError, VorbisLibraryErrorKind};
 /// a good trade-off for general usage.
 const FRAME_BLOCK_SIZE: usize = 512;
 
+/// The registry of codecs that [SymphoniaDecoder] can decode, which complements the
+/// codecs supported by Symphonia with our own decoders.
+static CODEC_REGISTRY: LazyLock<CodecRegistry> = LazyLock::new(|| {
+	let mut codec_registry = CodecRegistry::new();
+	symphonia::default::register_enabled_codecs(&mut codec_registry);
+	#[cfg(feature = "opus-support")]
+	codec_registry.register_all::<OpusDecoder>();
+	codec_registry
+});
+
 /// Decodes audio samples from the specified source, and applies digital signal
-/// processing algorithms to optionally resample, channel mix and pitch shift
-/// them. After that, the maybe processed samples are yielded in blocks to the
+/// processing algorithms to optionally filter, resample, channel mix and pitch
+/// shift them. After that, the maybe processed samples are yielded in blocks to the
 /// provided consumer in planar format.
 ///
 /// This audio decoding and processing code used here is specialized for mono
-/// and stereo signals, and doesn't work for surround signals. Attempting to
-/// decode a surround signal will promptly return an error.
+/// and stereo signals. Surround signals are downmixed to stereo as they are
+/// decoded, before any other processing is done.
 pub fn decode_and_process_sample_blocks(
 	source: impl Read + Send + Sync + 'static,
 	is_ogg: bool,
@@ -43,6 +78,7 @@ pub fn decode_and_process_sample_blocks(
 		NonZeroU8
 	) -> Result<NonZeroU32, OptimizationError>,
 	target_pitch: f32,
+	filter_chain_parameters: FilterChainParameters,
 	processed_sample_block_consumer: impl FnMut(&[Vec<f32>]) -> Result<(), OptimizationError>
 ) -> Result<bool, OptimizationError> {
 	// For Ogg Vorbis files, it's best to use our version of the reference implementation
@@ -50,23 +86,38 @@ pub fn decode_and_process_sample_blocks(
 	// its encoder anyway, so using the Symphonia Vorbis decoder will increase the executable
 	// size and negate some of the benefits of the patches we're using for the only benefit of
 	// development convenience. Needless to say, we didn't come this far with this meticulously
-	// crafted audio processing code and library selection for "development convenience"
+	// crafted audio processing code and library selection for "development convenience".
+	// Our decoder only supports Ogg files with a single Vorbis logical stream at a time,
+	// so filter out any other multiplexed logical stream, such as cover art
 	let mut decoder = if is_ogg {
-		SignalDecoder::Vorbis(VorbisDecoder::new(source)?)
+		SignalDecoder::Vorbis(VorbisDecoder::new(OggVorbisStreamFilter::new(source))?)
 	} else {
 		SignalDecoder::Symphonia(SymphoniaDecoder::new(source)?)
 	};
 	let input_channels = decoder.channels();
 	let input_sampling_frequency = decoder.sampling_frequency();
 
-	// Minecraft only supports mono and stereo sounds. Sound files with more channels
-	// are rejected with errors, so there's no point in us supporting them either
-	if input_channels.get() > 2 {
-		return Err(OptimizationError::UnsupportedChannelCount);
-	}
+	// Minecraft only supports mono and stereo sounds, so downmix sound files with more
+	// channels to stereo first. The rest of the pipeline sees the downmixed signal
+	let downmix_matrix = if input_channels.get() > 2 {
+		Some(StereoDownmixMatrix::new(
+			decoder
+				.channel_layout()
+				.ok_or(OptimizationError::UnsupportedChannelCount)?
+		))
+	} else {
+		None
+	};
+	let downmix_matrix = downmix_matrix.as_ref();
+	let downmixed_input_channels = if downmix_matrix.is_some() {
+		NonZeroU8::new(2).unwrap()
+	} else {
+		input_channels
+	};
 
-	let output_channels =
-		target_channels.map_or(input_channels, |target_channels| target_channels.into());
+	let output_channels = target_channels.map_or(downmixed_input_channels, |target_channels| {
+		target_channels.into()
+	});
 	let target_sampling_frequency = target_sampling_frequency_producer(
 		input_sampling_frequency,
 		input_channels,
@@ -89,7 +140,7 @@ pub fn decode_and_process_sample_blocks(
 				FRAME_BLOCK_SIZE,
 				2,
 				channels_to_resample(
-					input_channels.get() as usize,
+					downmixed_input_channels.get() as usize,
 					output_channels.get() as usize
 				)
 			)
@@ -99,6 +150,21 @@ pub fn decode_and_process_sample_blocks(
 		None
 	};
 
+	// Filters work on the samples that would be handed off to the resampler, if any,
+	// at the input sampling frequency
+	let filter_chain = FilterChain::new(
+		filter_chain_parameters,
+		input_sampling_frequency,
+		if resampler.is_some() {
+			channels_to_resample(
+				downmixed_input_channels.get() as usize,
+				output_channels.get() as usize
+			)
+		} else {
+			output_channels.get() as usize
+		}
+	);
+
 	let mut last_vorbis_error = None;
 	let mut last_symphonia_error = None;
 	let mut _hole_in_data_found = false;
@@ -108,42 +174,49 @@ pub fn decode_and_process_sample_blocks(
 			match decoder {
 				SignalDecoder::Vorbis(decoder) => EitherSignal::A(VorbisSignal::new(
 					decoder,
+					downmix_matrix,
 					&mut last_vorbis_error,
 					&mut _hole_in_data_found
 				)),
-				SignalDecoder::Symphonia(decoder) => {
-					EitherSignal::B(SymphoniaSignal::new(decoder, &mut last_symphonia_error))
-				}
+				SignalDecoder::Symphonia(decoder) => EitherSignal::B(SymphoniaSignal::new(
+					decoder,
+					downmix_matrix,
+					&mut last_symphonia_error
+				))
 			}
 		};
 	}
 
-	let is_silent = match (input_channels.get(), output_channels.get()) {
+	let is_silent = match (downmixed_input_channels.get(), output_channels.get()) {
 		(1, 1) => execute_dasp_pipeline::<1, 1, _>(
 			input_signal!(),
 			target_pitch,
 			resampler,
+			filter_chain,
 			processed_sample_block_consumer
 		)?,
 		(1, 2) => execute_dasp_pipeline::<1, 2, _>(
 			input_signal!(),
 			target_pitch,
 			resampler,
+			filter_chain,
 			processed_sample_block_consumer
 		)?,
 		(2, 2) => execute_dasp_pipeline::<2, 2, _>(
 			input_signal!(),
 			target_pitch,
 			resampler,
+			filter_chain,
 			processed_sample_block_consumer
 		)?,
 		(2, 1) => execute_dasp_pipeline::<2, 1, _>(
 			input_signal!(),
 			target_pitch,
 			resampler,
+			filter_chain,
 			processed_sample_block_consumer
 		)?,
-		_ => unreachable!("Unexpected channel count: {}", input_channels)
+		_ => unreachable!("Unexpected channel count: {}", downmixed_input_channels)
 	};
 
 	if let Some(vorbis_error) = last_vorbis_error {
@@ -159,10 +232,53 @@ pub fn decode_and_process_sample_blocks(
 	Ok(is_silent)
 }
 
+/// Decodes the specified Ogg Vorbis file and computes its signal-to-noise ratio, in decibels,
+/// relative to the specified reference samples in planar format, which were encoded to it.
+/// Only the samples present in both signals are compared.
+///
+/// `None` is returned if the reference signal is silent or has a different number of channels,
+/// as in those cases the ratio is not meaningful.
+pub fn signal_to_noise_ratio(
+	reference_samples: &[Vec<f32>],
+	distorted_file: impl Read
+) -> Result<Option<f64>, OptimizationError> {
+	let mut decoder = VorbisDecoder::new(distorted_file)?;
+	if decoder.channels().get() as usize != reference_samples.len() {
+		return Ok(None);
+	}
+
+	let mut signal_energy = 0.0;
+	let mut noise_energy = 0.0;
+	let mut position = 0;
+
+	while let Some(audio_samples) = decoder.decode_audio_block()? {
+		let mut block_length = 0;
+
+		for (reference_channel_samples, channel_samples) in
+			reference_samples.iter().zip(audio_samples.samples())
+		{
+			let reference_channel_samples =
+				&reference_channel_samples[position.min(reference_channel_samples.len())..];
+			block_length = channel_samples.len();
+
+			for (reference_sample, sample) in reference_channel_samples.iter().zip(*channel_samples) {
+				let (reference_sample, sample) = (*reference_sample as f64, *sample as f64);
+
+				signal_energy += reference_sample * reference_sample;
+				noise_energy += (reference_sample - sample) * (reference_sample - sample);
+			}
+		}
+
+		position += block_length;
+	}
+
+	Ok((signal_energy > 0.0).then(|| 10.0 * (signal_energy / noise_energy).log10()))
+}
+
 /// Constructs and executes a dasp pipeline to process the audio frames returned by a dasp
-/// signal, applying resampling, channel mixing and pitch shifting as specified. The raw,
-/// processed samples are then yielded in blocks to the specified consumer. The returned
-/// boolean indicates whether the input audio signal was full of silence samples.
+/// signal, applying pitch shifting, channel mixing, filtering and resampling as specified.
+/// The raw, processed samples are then yielded in blocks to the specified consumer. The
+/// returned boolean indicates whether the input audio signal was full of silence samples.
 fn execute_dasp_pipeline<
 	const INPUT_CHANNELS: usize,
 	const OUTPUT_CHANNELS: usize,
@@ -171,6 +287,7 @@ fn execute_dasp_pipeline<
 	input_signal: S,
 	target_pitch: f32,
 	resampler: Option<FftFixedIn<f32>>,
+	mut filter_chain: Option<FilterChain>,
 	mut processed_sample_block_consumer: impl FnMut(&[Vec<f32>]) -> Result<(), OptimizationError>
 ) -> Result<bool, OptimizationError>
 where
@@ -253,6 +370,10 @@ where
 				}
 
 				if output_frame_buf[0].len() == FRAME_BLOCK_SIZE {
+					if let Some(filter_chain) = &mut filter_chain {
+						filter_chain.process(&mut output_frame_buf);
+					}
+
 					processed_sample_block_consumer(&output_frame_buf)?;
 
 					// Discard this block of samples
@@ -264,6 +385,10 @@ where
 
 			// Consume the frames that didn't make it to a block
 			if !output_frame_buf[0].is_empty() {
+				if let Some(filter_chain) = &mut filter_chain {
+					filter_chain.process(&mut output_frame_buf);
+				}
+
 				processed_sample_block_consumer(&output_frame_buf)?;
 			}
 		}
@@ -309,6 +434,10 @@ where
 				}
 
 				if resample_input_buf[0].len() == FRAME_BLOCK_SIZE {
+					if let Some(filter_chain) = &mut filter_chain {
+						filter_chain.process(&mut resample_input_buf);
+					}
+
 					resample_buffer(
 						&resample_input_buf,
 						&mut resampled_samples_buf,
@@ -346,6 +475,10 @@ where
 
 			// Resample and consume the frames that didn't make it to a block
 			if !resample_input_buf[0].is_empty() {
+				if let Some(filter_chain) = &mut filter_chain {
+					filter_chain.process(&mut resample_input_buf);
+				}
+
 				// The resampler requires input blocks of fixed size, so pad
 				// the buffer. Note that, as Rubato's resamplers work on fixed
 				// size input or output blocks, we can only move the padding
@@ -422,6 +555,15 @@ impl<R: Read> SignalDecoder<R> {
 			Self::Symphonia(decoder) => decoder.sampling_frequency()
 		}
 	}
+
+	/// Returns the channels of the audio signal decoded by this decoder, in sample order,
+	/// or `None` if they are unknown.
+	fn channel_layout(&mut self) -> Option<Vec<Channels>> {
+		match self {
+			Self::Vorbis(decoder) => vorbis_channel_layout(decoder.channels()).map(Vec::from),
+			Self::Symphonia(decoder) => Some(decoder.channel_layout.iter().collect())
+		}
+	}
 }
 
 /// A convenience wrapper for Symphonia decoder data types that probes an
@@ -432,6 +574,7 @@ struct SymphoniaDecoder {
 	decoder: Box<dyn Decoder>,
 	main_track_id: u32,
 	channels: NonZeroU8,
+	channel_layout: Channels,
 	sampling_frequency: NonZeroU32
 }
 
@@ -457,22 +600,19 @@ impl SymphoniaDecoder {
 		let main_track = format_reader
 			.tracks()
 			.iter()
-			// Find the first track with a codec Symphonia can decode
-			.find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+			// Find the first track with a codec we can decode, skipping non-audio tracks
+			.find(|track| CODEC_REGISTRY.get_codec(track.codec_params.codec).is_some())
 			.ok_or(OptimizationError::NoAudioTrack)?;
 		let main_track_id = main_track.id;
 
-		let decoder = symphonia::default::get_codecs()
-			.make(&main_track.codec_params, &DecoderOptions::default())?;
+		let decoder = CODEC_REGISTRY.make(&main_track.codec_params, &DecoderOptions::default())?;
 
-		let channels = match main_track
+		let channel_layout = main_track
 			.codec_params
 			.channels
-			.map(|channels| channels.count())
-		{
-			None | Some(0) => return Err(OptimizationError::UnsupportedChannelCount),
-			Some(channels) => NonZeroU8::new(channels as u8).unwrap()
-		};
+			.ok_or(OptimizationError::UnsupportedChannelCount)?;
+		let channels = NonZeroU8::new(channel_layout.count() as u8)
+			.ok_or(OptimizationError::UnsupportedChannelCount)?;
 
 		let sampling_frequency = NonZeroU32::new(
 			main_track
@@ -489,6 +629,7 @@ impl SymphoniaDecoder {
 			decoder,
 			main_track_id,
 			channels,
+			channel_layout,
 			sampling_frequency
 		})
 	}
@@ -506,9 +647,11 @@ impl SymphoniaDecoder {
 
 /// Wraps a [VorbisDecoder] of a signal of a statically-known number of channels into
 /// a dasp [Signal], allowing signal processing operations on the audio samples
-/// contained in the decoded Vorbis file.
+/// contained in the decoded Vorbis file. Surround signals are downmixed to stereo
+/// with the specified downmix matrix, if any.
 struct VorbisSignal<'out, R: Read, const CHANNELS: usize> {
 	vorbis_decoder: VorbisDecoder<R>,
+	downmix_matrix: Option<&'out StereoDownmixMatrix>,
 	last_error: &'out mut Option<VorbisError>,
 	hole_in_data_found: &'out mut bool,
 	sample_buffer: Option<Box<[Box<[f32]>]>>,
@@ -519,11 +662,13 @@ struct VorbisSignal<'out, R: Read, const CHANNELS: usize> {
 impl<'out, R: Read, const CHANNELS: usize> VorbisSignal<'out, R, CHANNELS> {
 	fn new(
 		vorbis_decoder: VorbisDecoder<R>,
+		downmix_matrix: Option<&'out StereoDownmixMatrix>,
 		last_error: &'out mut Option<VorbisError>,
 		hole_in_data_found: &'out mut bool
 	) -> Self {
 		Self {
 			vorbis_decoder,
+			downmix_matrix,
 			last_error,
 			hole_in_data_found,
 			sample_buffer: None,
@@ -546,8 +691,17 @@ where
 					// We read an audio packet before and have pending frames to yield
 
 					let mut frame = Self::Frame::EQUILIBRIUM;
-					for (channel, sample) in frame.iter_mut().enumerate() {
-						*sample = sample_buffer[channel][self.sample_buffer_position];
+					match self.downmix_matrix {
+						Some(downmix_matrix) => {
+							frame.copy_from_slice(&downmix_matrix.downmix(sample_buffer.iter().map(
+								|channel_samples| channel_samples[self.sample_buffer_position]
+							)));
+						}
+						None => {
+							for (channel, sample) in frame.iter_mut().enumerate() {
+								*sample = sample_buffer[channel][self.sample_buffer_position];
+							}
+						}
 					}
 					self.sample_buffer_position += 1;
 
@@ -615,22 +769,26 @@ where
 
 /// Wraps a [SymphoniaDecoder] of a signal of a statically-known number of channels
 /// into a dasp [Signal], allowing signal processing operations on the audio samples
-/// contained in the decoded file.
-struct SymphoniaSignal<'error, const CHANNELS: usize> {
+/// contained in the decoded file. Surround signals are downmixed to stereo with the
+/// specified downmix matrix, if any.
+struct SymphoniaSignal<'out, const CHANNELS: usize> {
 	symphonia_decoder: SymphoniaDecoder,
-	last_error: &'error mut Option<symphonia::core::errors::Error>,
+	downmix_matrix: Option<&'out StereoDownmixMatrix>,
+	last_error: &'out mut Option<symphonia::core::errors::Error>,
 	current_sample_buffer: Option<SampleBuffer<f32>>,
 	current_sample_buffer_position: usize,
 	end_of_stream: bool
 }
 
-impl<'error, const CHANNELS: usize> SymphoniaSignal<'error, CHANNELS> {
+impl<'out, const CHANNELS: usize> SymphoniaSignal<'out, CHANNELS> {
 	fn new(
 		symphonia_decoder: SymphoniaDecoder,
-		last_error: &'error mut Option<symphonia::core::errors::Error>
+		downmix_matrix: Option<&'out StereoDownmixMatrix>,
+		last_error: &'out mut Option<symphonia::core::errors::Error>
 	) -> Self {
 		Self {
 			symphonia_decoder,
+			downmix_matrix,
 			last_error,
 			current_sample_buffer: None,
 			current_sample_buffer_position: 0,
@@ -651,12 +809,18 @@ where
 				Some(sample_buffer) if self.current_sample_buffer_position < sample_buffer.len() => {
 					// We read an audio packet before and have pending frames to yield
 
-					let frame = sample_buffer.samples()[self.current_sample_buffer_position
-						..self.current_sample_buffer_position + CHANNELS]
-						.try_into()
-						.unwrap();
+					let input_channels = self.symphonia_decoder.channels.get() as usize;
+					let input_frame = &sample_buffer.samples()[self.current_sample_buffer_position
+						..self.current_sample_buffer_position + input_channels];
+
+					let mut frame = Self::Frame::EQUILIBRIUM;
+					match self.downmix_matrix {
+						Some(downmix_matrix) => frame
+							.copy_from_slice(&downmix_matrix.downmix(input_frame.iter().copied())),
+						None => frame.copy_from_slice(input_frame)
+					}
 
-					self.current_sample_buffer_position += CHANNELS;
+					self.current_sample_buffer_position += input_channels;
 
 					return frame;
 				}
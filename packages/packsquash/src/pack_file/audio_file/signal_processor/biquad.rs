@@ -0,0 +1,95 @@
+//! Contains a biquadratic IIR filter implementation, which is the building block of the
+//! audio filters used by the signal processing code.
+
+use std::f64::consts::PI;
+
+/// The quality factors of the second-order sections that make up a fourth-order Butterworth
+/// filter, which has a maximally flat passband and a 24 dB/octave slope.
+pub const BUTTERWORTH_QUALITY_FACTORS: [f64; 2] = [0.541_196_100_146_197, 1.306_562_964_876_376_6];
+
+/// A biquadratic IIR filter, in transposed direct form II.
+#[derive(Clone, Copy)]
+pub struct Biquad {
+	b: [f64; 3],
+	a: [f64; 2],
+	state: [f64; 2]
+}
+
+impl Biquad {
+	/// Creates a new biquadratic filter with the specified numerator coefficients and
+	/// denominator coefficients, normalized so that the first denominator coefficient is 1.
+	pub const fn new(b: [f64; 3], a: [f64; 2]) -> Self {
+		Self {
+			b,
+			a,
+			state: [0.0; 2]
+		}
+	}
+
+	/// Creates a new second-order high-pass filter with the specified cutoff frequency and
+	/// quality factor, for a signal with the specified sampling frequency. The coefficients
+	/// are computed as described in the Audio EQ Cookbook by Robert Bristow-Johnson.
+	pub fn high_pass(sampling_frequency: f64, cutoff_frequency: f64, quality_factor: f64) -> Self {
+		let (cos_w0, alpha) = cos_w0_and_alpha(sampling_frequency, cutoff_frequency, quality_factor);
+		let a0 = 1.0 + alpha;
+
+		Self::new(
+			[
+				(1.0 + cos_w0) / 2.0 / a0,
+				-(1.0 + cos_w0) / a0,
+				(1.0 + cos_w0) / 2.0 / a0
+			],
+			[-2.0 * cos_w0 / a0, (1.0 - alpha) / a0]
+		)
+	}
+
+	/// Creates a new second-order low-pass filter with the specified cutoff frequency and
+	/// quality factor, for a signal with the specified sampling frequency. The coefficients
+	/// are computed as described in the Audio EQ Cookbook by Robert Bristow-Johnson.
+	pub fn low_pass(sampling_frequency: f64, cutoff_frequency: f64, quality_factor: f64) -> Self {
+		let (cos_w0, alpha) = cos_w0_and_alpha(sampling_frequency, cutoff_frequency, quality_factor);
+		let a0 = 1.0 + alpha;
+
+		Self::new(
+			[
+				(1.0 - cos_w0) / 2.0 / a0,
+				(1.0 - cos_w0) / a0,
+				(1.0 - cos_w0) / 2.0 / a0
+			],
+			[-2.0 * cos_w0 / a0, (1.0 - alpha) / a0]
+		)
+	}
+
+	/// Creates a new second-order band-pass filter with the specified center frequency and
+	/// quality factor, for a signal with the specified sampling frequency, whose peak gain is
+	/// 0 dB. The coefficients are computed as described in the Audio EQ Cookbook by Robert
+	/// Bristow-Johnson.
+	pub fn band_pass(sampling_frequency: f64, center_frequency: f64, quality_factor: f64) -> Self {
+		let (cos_w0, alpha) = cos_w0_and_alpha(sampling_frequency, center_frequency, quality_factor);
+		let a0 = 1.0 + alpha;
+
+		Self::new(
+			[alpha / a0, 0.0, -alpha / a0],
+			[-2.0 * cos_w0 / a0, (1.0 - alpha) / a0]
+		)
+	}
+
+	/// Filters the specified sample, returning the output sample.
+	pub fn process(&mut self, sample: f64) -> f64 {
+		let output = self.b[0] * sample + self.state[0];
+		self.state[0] = self.b[1] * sample - self.a[0] * output + self.state[1];
+		self.state[1] = self.b[2] * sample - self.a[1] * output;
+		output
+	}
+}
+
+/// Computes the cosine of the angular cutoff frequency and the alpha intermediate value used
+/// in the Audio EQ Cookbook formulas.
+fn cos_w0_and_alpha(
+	sampling_frequency: f64,
+	cutoff_frequency: f64,
+	quality_factor: f64
+) -> (f64, f64) {
+	let w0 = 2.0 * PI * cutoff_frequency / sampling_frequency;
+	(w0.cos(), w0.sin() / (2.0 * quality_factor))
+}
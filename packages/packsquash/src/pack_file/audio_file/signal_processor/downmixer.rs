@@ -0,0 +1,137 @@
+//! Contains routines to downmix surround audio signals to stereo.
+
+use std::f32::consts::FRAC_1_SQRT_2;
+use std::num::NonZeroU8;
+
+use symphonia::core::audio::Channels;
+
+#[cfg(test)]
+mod tests;
+
+/// A matrix of coefficients that downmixes a surround audio signal with some channel layout to
+/// stereo, as recommended by ITU-R BS.775. Low-frequency effects channels are discarded, and
+/// the coefficients are normalized so that the downmixed signal does not clip.
+pub struct StereoDownmixMatrix {
+	coefficients: Box<[[f32; 2]]>
+}
+
+impl StereoDownmixMatrix {
+	/// Creates a new downmix matrix for a signal with the specified channels, in the same order
+	/// as their samples.
+	pub fn new(channel_layout: impl IntoIterator<Item = Channels>) -> Self {
+		let mut coefficients = channel_layout
+			.into_iter()
+			.map(|channel| match channel {
+				Channels::FRONT_LEFT => [1.0, 0.0],
+				Channels::FRONT_RIGHT => [0.0, 1.0],
+				Channels::FRONT_CENTRE | Channels::FRONT_CENTRE_HIGH | Channels::TOP_FRONT_CENTRE => {
+					[FRAC_1_SQRT_2, FRAC_1_SQRT_2]
+				}
+				Channels::LFE1 | Channels::LFE2 => [0.0, 0.0],
+				Channels::FRONT_LEFT_CENTRE
+				| Channels::FRONT_LEFT_WIDE
+				| Channels::FRONT_LEFT_HIGH
+				| Channels::SIDE_LEFT
+				| Channels::REAR_LEFT
+				| Channels::REAR_LEFT_CENTRE
+				| Channels::TOP_FRONT_LEFT
+				| Channels::TOP_REAR_LEFT => [FRAC_1_SQRT_2, 0.0],
+				Channels::FRONT_RIGHT_CENTRE
+				| Channels::FRONT_RIGHT_WIDE
+				| Channels::FRONT_RIGHT_HIGH
+				| Channels::SIDE_RIGHT
+				| Channels::REAR_RIGHT
+				| Channels::REAR_RIGHT_CENTRE
+				| Channels::TOP_FRONT_RIGHT
+				| Channels::TOP_REAR_RIGHT => [0.0, FRAC_1_SQRT_2],
+				// Rear and top center channels
+				_ => [0.5, 0.5]
+			})
+			.collect::<Box<[_]>>();
+
+		for output_channel in 0..2 {
+			let coefficient_sum = coefficients
+				.iter()
+				.map(|channel_coefficients| channel_coefficients[output_channel])
+				.sum::<f32>();
+
+			if coefficient_sum > 0.0 {
+				for channel_coefficients in coefficients.iter_mut() {
+					channel_coefficients[output_channel] /= coefficient_sum;
+				}
+			}
+		}
+
+		Self { coefficients }
+	}
+
+	/// Downmixes the specified surround frame, whose samples are in the channel order this
+	/// matrix was created for, to a stereo frame.
+	pub fn downmix(&self, frame: impl IntoIterator<Item = f32>) -> [f32; 2] {
+		frame.into_iter().zip(self.coefficients.iter()).fold(
+			[0.0; 2],
+			|[left, right], (sample, [left_coefficient, right_coefficient])| {
+				[
+					left + sample * left_coefficient,
+					right + sample * right_coefficient
+				]
+			}
+		)
+	}
+}
+
+/// Returns the channel layout of Vorbis streams with the specified number of channels, in
+/// sample order, as defined in the Vorbis I specification. `None` is returned if the
+/// specification does not define a layout for that number of channels.
+pub fn vorbis_channel_layout(channels: NonZeroU8) -> Option<&'static [Channels]> {
+	Some(match channels.get() {
+		1 => &[Channels::FRONT_CENTRE],
+		2 => &[Channels::FRONT_LEFT, Channels::FRONT_RIGHT],
+		3 => &[
+			Channels::FRONT_LEFT,
+			Channels::FRONT_CENTRE,
+			Channels::FRONT_RIGHT
+		],
+		4 => &[
+			Channels::FRONT_LEFT,
+			Channels::FRONT_RIGHT,
+			Channels::REAR_LEFT,
+			Channels::REAR_RIGHT
+		],
+		5 => &[
+			Channels::FRONT_LEFT,
+			Channels::FRONT_CENTRE,
+			Channels::FRONT_RIGHT,
+			Channels::REAR_LEFT,
+			Channels::REAR_RIGHT
+		],
+		6 => &[
+			Channels::FRONT_LEFT,
+			Channels::FRONT_CENTRE,
+			Channels::FRONT_RIGHT,
+			Channels::REAR_LEFT,
+			Channels::REAR_RIGHT,
+			Channels::LFE1
+		],
+		7 => &[
+			Channels::FRONT_LEFT,
+			Channels::FRONT_CENTRE,
+			Channels::FRONT_RIGHT,
+			Channels::SIDE_LEFT,
+			Channels::SIDE_RIGHT,
+			Channels::REAR_CENTRE,
+			Channels::LFE1
+		],
+		8 => &[
+			Channels::FRONT_LEFT,
+			Channels::FRONT_CENTRE,
+			Channels::FRONT_RIGHT,
+			Channels::SIDE_LEFT,
+			Channels::SIDE_RIGHT,
+			Channels::REAR_LEFT,
+			Channels::REAR_RIGHT,
+			Channels::LFE1
+		],
+		_ => return None
+	})
+}
@@ -0,0 +1,67 @@
+use std::num::NonZeroU8;
+
+use super::*;
+
+/// The channel layout of 5.1 surround audio, in Symphonia channel order.
+const SURROUND_5_1_LAYOUT: [Channels; 6] = [
+	Channels::FRONT_LEFT,
+	Channels::FRONT_RIGHT,
+	Channels::FRONT_CENTRE,
+	Channels::LFE1,
+	Channels::SIDE_LEFT,
+	Channels::SIDE_RIGHT
+];
+
+#[test]
+fn surround_5_1_downmix_works() {
+	let downmix_matrix = StereoDownmixMatrix::new(SURROUND_5_1_LAYOUT);
+
+	let [left, right] = downmix_matrix.downmix([1.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
+	assert!(
+		left > 0.0,
+		"The front left channel should be mixed into the left channel"
+	);
+	assert_eq!(
+		right, 0.0,
+		"The front left channel should not be mixed into the right channel"
+	);
+
+	let [left, right] = downmix_matrix.downmix([0.0, 0.0, 1.0, 0.0, 0.0, 0.0]);
+	assert_eq!(
+		left, right,
+		"The center channel should be mixed equally into both channels"
+	);
+
+	let [left, right] = downmix_matrix.downmix([0.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+	assert_eq!(
+		[left, right],
+		[0.0, 0.0],
+		"The LFE channel should be discarded"
+	);
+}
+
+#[test]
+fn downmixed_full_scale_signal_does_not_clip() {
+	let downmix_matrix = StereoDownmixMatrix::new(SURROUND_5_1_LAYOUT);
+
+	let [left, right] = downmix_matrix.downmix([1.0; 6]);
+	assert!((left - 1.0).abs() < 1e-6, "Unexpected left sample: {left}");
+	assert!(
+		(right - 1.0).abs() < 1e-6,
+		"Unexpected right sample: {right}"
+	);
+}
+
+#[test]
+fn vorbis_channel_layouts_are_defined_up_to_eight_channels() {
+	for channels in 1..=8 {
+		let channels = NonZeroU8::new(channels).unwrap();
+
+		assert_eq!(
+			vorbis_channel_layout(channels).map(<[_]>::len),
+			Some(channels.get() as usize)
+		);
+	}
+
+	assert!(vorbis_channel_layout(NonZeroU8::new(9).unwrap()).is_none());
+}
@@ -0,0 +1,143 @@
+//! Contains a configurable chain of audio filters that remove content which is inaudible or
+//! otherwise undesirable, making audio signals easier to compress.
+
+use std::num::NonZeroU32;
+
+use super::biquad::{Biquad, BUTTERWORTH_QUALITY_FACTORS};
+
+#[cfg(test)]
+mod tests;
+
+/// The time it takes for the limiter gain to recover 63% of the way back to unity gain after
+/// the signal falls below the threshold, in seconds.
+const LIMITER_RELEASE_TIME: f64 = 0.05;
+
+/// The parameters of a [FilterChain].
+#[derive(Clone, Copy, Default)]
+pub struct FilterChainParameters {
+	/// The cutoff frequency of the high-pass filter, in Hz, if any.
+	pub high_pass_cutoff_frequency: Option<NonZeroU32>,
+	/// The cutoff frequency of the low-pass filter, in Hz, if any.
+	pub low_pass_cutoff_frequency: Option<NonZeroU32>,
+	/// The amplitude threshold of the limiter, in dBFS, if any.
+	pub limiter_threshold: Option<f32>
+}
+
+impl FilterChainParameters {
+	/// Checks whether these parameters request any filter to be applied.
+	pub fn has_filters(&self) -> bool {
+		self.high_pass_cutoff_frequency.is_some()
+			|| self.low_pass_cutoff_frequency.is_some()
+			|| self.limiter_threshold.is_some()
+	}
+}
+
+/// A chain of a high-pass filter, a low-pass filter and a peak limiter, applied in that order
+/// to blocks of planar audio samples. The state of each filter is kept between blocks.
+pub struct FilterChain {
+	filters: Vec<Vec<Biquad>>,
+	limiter: Option<Limiter>
+}
+
+impl FilterChain {
+	/// Creates a new filter chain with the specified parameters, for a signal with the specified
+	/// sampling frequency and number of channels. Filters whose cutoff frequency is not below
+	/// the Nyquist frequency of the signal are left out, as they would not filter anything out
+	/// or everything, respectively. `None` is returned if the chain would not have any filter.
+	pub fn new(
+		parameters: FilterChainParameters,
+		sampling_frequency: NonZeroU32,
+		channels: usize
+	) -> Option<Self> {
+		let nyquist_frequency = sampling_frequency.get() / 2;
+		let sampling_frequency = sampling_frequency.get() as f64;
+
+		let mut channel_filters = vec![];
+		if let Some(cutoff_frequency) = parameters
+			.high_pass_cutoff_frequency
+			.filter(|cutoff_frequency| cutoff_frequency.get() < nyquist_frequency)
+		{
+			channel_filters.extend(BUTTERWORTH_QUALITY_FACTORS.map(|quality_factor| {
+				Biquad::high_pass(
+					sampling_frequency,
+					cutoff_frequency.get() as f64,
+					quality_factor
+				)
+			}));
+		}
+		if let Some(cutoff_frequency) = parameters
+			.low_pass_cutoff_frequency
+			.filter(|cutoff_frequency| cutoff_frequency.get() < nyquist_frequency)
+		{
+			channel_filters.extend(BUTTERWORTH_QUALITY_FACTORS.map(|quality_factor| {
+				Biquad::low_pass(
+					sampling_frequency,
+					cutoff_frequency.get() as f64,
+					quality_factor
+				)
+			}));
+		}
+
+		let limiter = parameters.limiter_threshold.map(|threshold| Limiter {
+			threshold: 10f32.powf(threshold / 20.0),
+			release_coefficient: (1.0 - (-1.0 / (LIMITER_RELEASE_TIME * sampling_frequency)).exp())
+				as f32,
+			gain: 1.0
+		});
+
+		(!channel_filters.is_empty() || limiter.is_some()).then(|| Self {
+			filters: vec![channel_filters; channels],
+			limiter
+		})
+	}
+
+	/// Filters the specified block of planar samples in place.
+	pub fn process(&mut self, block: &mut [Vec<f32>]) {
+		for (channel_filters, channel_samples) in self.filters.iter_mut().zip(block.iter_mut()) {
+			for sample in channel_samples {
+				*sample = channel_filters
+					.iter_mut()
+					.fold(*sample as f64, |sample, filter| filter.process(sample)) as f32;
+			}
+		}
+
+		if let Some(limiter) = &mut self.limiter {
+			limiter.process(block);
+		}
+	}
+}
+
+/// A peak limiter with instantaneous attack and exponential release, which applies the same
+/// gain to every channel to keep the stereo image.
+struct Limiter {
+	threshold: f32,
+	release_coefficient: f32,
+	gain: f32
+}
+
+impl Limiter {
+	/// Limits the peaks of the specified block of planar samples in place.
+	fn process(&mut self, block: &mut [Vec<f32>]) {
+		for frame in 0..block.first().map_or(0, Vec::len) {
+			let peak = block
+				.iter()
+				.map(|channel_samples| channel_samples[frame].abs())
+				.fold(0.0, f32::max);
+			let target_gain = if peak > self.threshold {
+				self.threshold / peak
+			} else {
+				1.0
+			};
+
+			if target_gain < self.gain {
+				self.gain = target_gain;
+			} else {
+				self.gain += (target_gain - self.gain) * self.release_coefficient;
+			}
+
+			for channel_samples in block.iter_mut() {
+				channel_samples[frame] *= self.gain;
+			}
+		}
+	}
+}
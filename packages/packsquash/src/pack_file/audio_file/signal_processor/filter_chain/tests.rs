@@ -0,0 +1,96 @@
+use std::f32::consts::PI;
+use std::num::NonZeroU32;
+
+use super::*;
+
+const SAMPLING_FREQUENCY: NonZeroU32 = NonZeroU32::new(48_000).unwrap();
+
+/// Generates a block of samples of a sine wave with the specified frequency, amplitude and
+/// offset, lasting for one second.
+fn sine_wave(frequency: f32, amplitude: f32, offset: f32) -> Vec<Vec<f32>> {
+	vec![(0..SAMPLING_FREQUENCY.get())
+		.map(|i| {
+			offset
+				+ amplitude
+					* (2.0 * PI * frequency * i as f32 / SAMPLING_FREQUENCY.get() as f32).sin()
+		})
+		.collect()]
+}
+
+/// Returns the peak amplitude of the second half of the specified block of samples, after
+/// the filters have settled.
+fn settled_peak(block: &[Vec<f32>]) -> f32 {
+	block[0][block[0].len() / 2..]
+		.iter()
+		.fold(0.0, |peak, sample| f32::max(peak, sample.abs()))
+}
+
+#[test]
+fn high_pass_filter_removes_dc_offset() {
+	let mut filter_chain = FilterChain::new(
+		FilterChainParameters {
+			high_pass_cutoff_frequency: NonZeroU32::new(40),
+			..Default::default()
+		},
+		SAMPLING_FREQUENCY,
+		1
+	)
+	.expect("A filter was requested");
+
+	let mut block = sine_wave(1000.0, 0.5, 0.25);
+	filter_chain.process(&mut block);
+
+	let peak = settled_peak(&block);
+	assert!((peak - 0.5).abs() < 0.01, "Unexpected peak: {peak}");
+}
+
+#[test]
+fn low_pass_filter_attenuates_high_frequencies() {
+	let mut filter_chain = FilterChain::new(
+		FilterChainParameters {
+			low_pass_cutoff_frequency: NonZeroU32::new(4000),
+			..Default::default()
+		},
+		SAMPLING_FREQUENCY,
+		1
+	)
+	.expect("A filter was requested");
+
+	let mut block = sine_wave(16000.0, 0.5, 0.0);
+	filter_chain.process(&mut block);
+
+	let peak = settled_peak(&block);
+	assert!(peak < 0.005, "Unexpected peak: {peak}");
+}
+
+#[test]
+fn limiter_keeps_peaks_below_threshold() {
+	let mut filter_chain = FilterChain::new(
+		FilterChainParameters {
+			limiter_threshold: Some(-6.0),
+			..Default::default()
+		},
+		SAMPLING_FREQUENCY,
+		1
+	)
+	.expect("A filter was requested");
+
+	let mut block = sine_wave(440.0, 1.0, 0.0);
+	filter_chain.process(&mut block);
+
+	let peak = settled_peak(&block);
+	assert!(peak <= 10f32.powf(-6.0 / 20.0), "Unexpected peak: {peak}");
+}
+
+#[test]
+fn filters_above_nyquist_frequency_are_left_out() {
+	assert!(FilterChain::new(
+		FilterChainParameters {
+			low_pass_cutoff_frequency: NonZeroU32::new(SAMPLING_FREQUENCY.get()),
+			..Default::default()
+		},
+		SAMPLING_FREQUENCY,
+		1
+	)
+	.is_none());
+}
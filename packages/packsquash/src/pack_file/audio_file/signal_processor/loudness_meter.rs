@@ -0,0 +1,197 @@
+//! Contains a loudness meter that measures the integrated loudness and true peak of audio
+//! signals, as defined by the ITU-R BS.1770-4 recommendation and the EBU R128 standard.
+
+use std::f64::consts::PI;
+use std::num::NonZeroU32;
+
+use super::biquad::Biquad;
+
+#[cfg(test)]
+mod tests;
+
+/// The absolute loudness threshold, in LUFS, below which gating blocks are ignored when
+/// computing the integrated loudness of a signal.
+const ABSOLUTE_GATE_THRESHOLD: f64 = -70.0;
+/// The loudness threshold relative to the loudness of the gating blocks above the absolute
+/// threshold, in LU, below which gating blocks are ignored when computing the integrated
+/// loudness of a signal.
+const RELATIVE_GATE_THRESHOLD: f64 = -10.0;
+/// The number of 100 ms steps that make up a 400 ms gating block. Consecutive gating blocks
+/// overlap by 75%, so they are one step apart.
+const STEPS_PER_GATING_BLOCK: usize = 4;
+/// The oversampling factor used to estimate the true peak of a signal.
+const TRUE_PEAK_OVERSAMPLING_FACTOR: usize = 4;
+/// The number of samples the true peak interpolation filter takes into account.
+const TRUE_PEAK_FILTER_TAPS: usize = 16;
+
+/// Measures the integrated loudness and true peak of an audio signal, whose samples are fed
+/// to it in blocks of planar samples.
+pub struct LoudnessMeter {
+	k_weighting_filters: Vec<[Biquad; 2]>,
+	step_length: usize,
+	step_energies: Vec<f64>,
+	current_step_energy: f64,
+	current_step_length: usize,
+	true_peak_interpolation_weights:
+		[[f64; TRUE_PEAK_FILTER_TAPS]; TRUE_PEAK_OVERSAMPLING_FACTOR - 1],
+	true_peak_histories: Vec<[f64; TRUE_PEAK_FILTER_TAPS]>,
+	true_peak: f64
+}
+
+impl LoudnessMeter {
+	/// Creates a new loudness meter for a signal with the specified sampling frequency and
+	/// number of channels. Every channel is given the same weight, which is the correct
+	/// behavior for mono and stereo signals.
+	pub fn new(sampling_frequency: NonZeroU32, channels: usize) -> Self {
+		let sampling_frequency = sampling_frequency.get() as f64;
+
+		// Coefficients of the K-weighting filter stages: a high shelf filter that models the
+		// acoustic effects of the head, and a high pass filter
+		let k = (PI * 1681.974450955533 / sampling_frequency).tan();
+		let (shelf_gain, shelf_q) = (10f64.powf(3.999843853973347 / 20.0), 0.7071752369554196);
+		let shelf_band_gain = shelf_gain.powf(0.4996667741545416);
+		let shelf_a0 = 1.0 + k / shelf_q + k * k;
+		let high_shelf = Biquad::new(
+			[
+				(shelf_gain + shelf_band_gain * k / shelf_q + k * k) / shelf_a0,
+				2.0 * (k * k - shelf_gain) / shelf_a0,
+				(shelf_gain - shelf_band_gain * k / shelf_q + k * k) / shelf_a0
+			],
+			[
+				2.0 * (k * k - 1.0) / shelf_a0,
+				(1.0 - k / shelf_q + k * k) / shelf_a0
+			]
+		);
+
+		let k = (PI * 38.13547087602444 / sampling_frequency).tan();
+		let high_pass_q = 0.5003270373238773;
+		let high_pass_a0 = 1.0 + k / high_pass_q + k * k;
+		let high_pass = Biquad::new(
+			[1.0, -2.0, 1.0],
+			[
+				2.0 * (k * k - 1.0) / high_pass_a0,
+				(1.0 - k / high_pass_q + k * k) / high_pass_a0
+			]
+		);
+
+		// Interpolate the samples between the two central filter taps with a Hann-windowed
+		// sinc kernel
+		let mut true_peak_interpolation_weights =
+			[[0.0; TRUE_PEAK_FILTER_TAPS]; TRUE_PEAK_OVERSAMPLING_FACTOR - 1];
+		for (phase, weights) in true_peak_interpolation_weights.iter_mut().enumerate() {
+			let position = (TRUE_PEAK_FILTER_TAPS / 2 - 1) as f64
+				+ (phase + 1) as f64 / TRUE_PEAK_OVERSAMPLING_FACTOR as f64;
+
+			for (tap, weight) in weights.iter_mut().enumerate() {
+				let x = position - tap as f64;
+				let half_width = (TRUE_PEAK_FILTER_TAPS / 2) as f64;
+				let window = if x.abs() < half_width {
+					0.5 + 0.5 * (PI * x / half_width).cos()
+				} else {
+					0.0
+				};
+				*weight = (PI * x).sin() / (PI * x) * window;
+			}
+		}
+
+		Self {
+			k_weighting_filters: vec![[high_shelf, high_pass]; channels],
+			step_length: ((sampling_frequency / 10.0).round() as usize).max(1),
+			step_energies: vec![],
+			current_step_energy: 0.0,
+			current_step_length: 0,
+			true_peak_interpolation_weights,
+			true_peak_histories: vec![[0.0; TRUE_PEAK_FILTER_TAPS]; channels],
+			true_peak: 0.0
+		}
+	}
+
+	/// Feeds the specified block of planar samples, which must have the channel count this
+	/// meter was created for, to this meter.
+	pub fn process(&mut self, block: &[Vec<f32>]) {
+		for i in 0..block.first().map_or(0, Vec::len) {
+			for (channel, channel_samples) in block.iter().enumerate() {
+				let sample = channel_samples[i] as f64;
+
+				let [high_shelf, high_pass] = &mut self.k_weighting_filters[channel];
+				let weighted_sample = high_pass.process(high_shelf.process(sample));
+				self.current_step_energy += weighted_sample * weighted_sample;
+
+				let history = &mut self.true_peak_histories[channel];
+				history.copy_within(1.., 0);
+				history[TRUE_PEAK_FILTER_TAPS - 1] = sample;
+
+				self.true_peak = self.true_peak.max(sample.abs());
+				for weights in &self.true_peak_interpolation_weights {
+					let interpolated_sample = weights
+						.iter()
+						.zip(history.iter())
+						.map(|(w, s)| w * s)
+						.sum::<f64>();
+
+					self.true_peak = self.true_peak.max(interpolated_sample.abs());
+				}
+			}
+
+			self.current_step_length += 1;
+			if self.current_step_length == self.step_length {
+				self.step_energies.push(self.current_step_energy);
+				self.current_step_energy = 0.0;
+				self.current_step_length = 0;
+			}
+		}
+	}
+
+	/// Returns the integrated loudness of the signal fed to this meter, in LUFS. Signals
+	/// shorter than a gating block are measured as a single block.
+	///
+	/// `None` is returned if the signal is silent, i.e. below the absolute gate threshold.
+	pub fn integrated_loudness(&self) -> Option<f64> {
+		let block_length = (self.step_length * STEPS_PER_GATING_BLOCK) as f64;
+
+		let block_mean_squares = if self.step_energies.len() >= STEPS_PER_GATING_BLOCK {
+			self.step_energies
+				.windows(STEPS_PER_GATING_BLOCK)
+				.map(|steps| steps.iter().sum::<f64>() / block_length)
+				.collect::<Vec<_>>()
+		} else {
+			let length = self.step_energies.len() * self.step_length + self.current_step_length;
+			if length == 0 {
+				return None;
+			}
+
+			vec![(self.step_energies.iter().sum::<f64>() + self.current_step_energy) / length as f64]
+		};
+
+		let absolutely_gated_blocks = block_mean_squares
+			.into_iter()
+			.filter(|mean_square| loudness(*mean_square) > ABSOLUTE_GATE_THRESHOLD)
+			.collect::<Vec<_>>();
+		if absolutely_gated_blocks.is_empty() {
+			return None;
+		}
+
+		let relative_threshold = loudness(mean(&absolutely_gated_blocks)) + RELATIVE_GATE_THRESHOLD;
+		let gated_blocks = absolutely_gated_blocks
+			.into_iter()
+			.filter(|mean_square| loudness(*mean_square) > relative_threshold)
+			.collect::<Vec<_>>();
+
+		Some(loudness(mean(&gated_blocks)))
+	}
+
+	/// Returns the estimated true peak of the signal fed to this meter, in dBTP.
+	pub fn true_peak(&self) -> f64 {
+		20.0 * self.true_peak.log10()
+	}
+}
+
+/// Converts the specified mean square of K-weighted samples to loudness, in LUFS.
+fn loudness(mean_square: f64) -> f64 {
+	-0.691 + 10.0 * mean_square.log10()
+}
+
+/// Computes the arithmetic mean of the specified values.
+fn mean(values: &[f64]) -> f64 {
+	values.iter().sum::<f64>() / values.len() as f64
+}
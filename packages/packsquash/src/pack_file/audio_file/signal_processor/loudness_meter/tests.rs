@@ -0,0 +1,76 @@
+use std::num::NonZeroU32;
+
+use super::*;
+
+const SAMPLING_FREQUENCY: NonZeroU32 = NonZeroU32::new(48_000).unwrap();
+
+/// Generates a block of samples of a 997 Hz sine wave with the specified amplitude and
+/// duration, in seconds, for the specified number of channels.
+fn sine_wave(amplitude: f32, duration: f32, channels: usize) -> Vec<Vec<f32>> {
+	let sample_count = (SAMPLING_FREQUENCY.get() as f32 * duration) as usize;
+	let samples = (0..sample_count)
+		.map(|i| {
+			amplitude
+				* (2.0 * std::f32::consts::PI * 997.0 * i as f32 / SAMPLING_FREQUENCY.get() as f32)
+					.sin()
+		})
+		.collect::<Vec<_>>();
+
+	vec![samples; channels]
+}
+
+#[test]
+fn sine_wave_loudness_is_measured() {
+	let mut meter = LoudnessMeter::new(SAMPLING_FREQUENCY, 1);
+	meter.process(&sine_wave(0.1, 3.0, 1));
+
+	let loudness = meter
+		.integrated_loudness()
+		.expect("The signal is not silent");
+	assert!(
+		(loudness - -23.01).abs() < 0.1,
+		"Unexpected loudness: {loudness}"
+	);
+}
+
+#[test]
+fn channel_loudness_is_added() {
+	let mut meter = LoudnessMeter::new(SAMPLING_FREQUENCY, 2);
+	meter.process(&sine_wave(0.1, 3.0, 2));
+
+	let loudness = meter
+		.integrated_loudness()
+		.expect("The signal is not silent");
+	assert!(
+		(loudness - -20.0).abs() < 0.1,
+		"Unexpected loudness: {loudness}"
+	);
+}
+
+#[test]
+fn short_signal_loudness_is_measured() {
+	let mut meter = LoudnessMeter::new(SAMPLING_FREQUENCY, 1);
+	meter.process(&sine_wave(0.1, 0.2, 1));
+
+	assert!(meter.integrated_loudness().is_some());
+}
+
+#[test]
+fn silence_has_no_loudness() {
+	let mut meter = LoudnessMeter::new(SAMPLING_FREQUENCY, 1);
+	meter.process(&[vec![0.0; 48_000]]);
+
+	assert_eq!(meter.integrated_loudness(), None);
+}
+
+#[test]
+fn sine_wave_true_peak_is_measured() {
+	let mut meter = LoudnessMeter::new(SAMPLING_FREQUENCY, 1);
+	meter.process(&sine_wave(0.5, 1.0, 1));
+
+	let true_peak = meter.true_peak();
+	assert!(
+		(true_peak - -6.02).abs() < 0.1,
+		"Unexpected true peak: {true_peak}"
+	);
+}
@@ -0,0 +1,146 @@
+//! Contains a reader adapter that filters out the logical streams of an Ogg file that are not
+//! Vorbis audio, such as video, cover art or secondary audio streams.
+//!
+//! References:
+//! - <https://www.xiph.org/ogg/doc/framing.html>
+//! - <https://www.xiph.org/vorbis/doc/Vorbis_I_spec.html#x1-620004.2.1>
+
+use std::io::{self, Read};
+
+#[cfg(test)]
+mod tests;
+
+/// The capture pattern that every Ogg page starts with.
+const CAPTURE_PATTERN: &[u8; 4] = b"OggS";
+/// The length of the fixed-size part of an Ogg page header, which is followed by the segment
+/// table.
+const PAGE_HEADER_LENGTH: usize = 27;
+/// The header type flag that marks the first page of a logical stream.
+const BEGINNING_OF_STREAM_FLAG: u8 = 0x02;
+/// The header type flag that marks the last page of a logical stream.
+const END_OF_STREAM_FLAG: u8 = 0x04;
+/// The bytes that the Vorbis identification header packet starts with.
+const VORBIS_IDENTIFICATION_HEADER_SIGNATURE: &[u8; 7] = b"\x01vorbis";
+
+/// A reader of an Ogg file that only yields the pages of its first Vorbis logical stream,
+/// and any Vorbis logical streams chained after it, dropping the rest. The resulting Ogg
+/// file is suitable for decoders that only support Ogg files with a single Vorbis logical
+/// stream at a time, even if the read file multiplexes several logical streams.
+pub struct OggVorbisStreamFilter<R: Read> {
+	source: R,
+	/// The serial of the Vorbis logical stream whose pages are being yielded, if any.
+	vorbis_stream_serial: Option<u32>,
+	/// Whether the end of the Vorbis logical stream being yielded was reached.
+	vorbis_stream_ended: bool,
+	/// The last read page to yield, and the position of the next byte of it to yield.
+	page: Vec<u8>,
+	page_position: usize
+}
+
+impl<R: Read> OggVorbisStreamFilter<R> {
+	/// Creates a new [OggVorbisStreamFilter] that reads pages from the specified source.
+	pub fn new(source: R) -> Self {
+		Self {
+			source,
+			vorbis_stream_serial: None,
+			vorbis_stream_ended: false,
+			page: vec![],
+			page_position: 0
+		}
+	}
+
+	/// Reads the next page of the Vorbis logical stream to yield into the page buffer.
+	/// Returns `false` if the end of the source was reached before such a page could be read.
+	/// Truncated pages at the end of the source are treated like the end of the source.
+	fn read_next_page(&mut self) -> io::Result<bool> {
+		loop {
+			self.page.clear();
+			self.page_position = 0;
+
+			if !self.read_page_bytes(PAGE_HEADER_LENGTH)? {
+				return Ok(false);
+			}
+
+			// Skip any garbage before the next page, like Ogg demuxers usually do
+			while !self.page.starts_with(CAPTURE_PATTERN) {
+				self.page.remove(0);
+
+				if !self.read_page_bytes(1)? {
+					return Ok(false);
+				}
+			}
+
+			let header_type = self.page[5];
+			let serial = u32::from_le_bytes(self.page[14..18].try_into().unwrap());
+			let segment_count = self.page[26] as usize;
+
+			if !self.read_page_bytes(segment_count)? {
+				return Ok(false);
+			}
+
+			let body_length = self.page[PAGE_HEADER_LENGTH..]
+				.iter()
+				.map(|&lacing_value| lacing_value as usize)
+				.sum();
+
+			if !self.read_page_bytes(body_length)? {
+				return Ok(false);
+			}
+
+			// Logical streams begin with a page that contains their identification header packet
+			// only. Multiplexed streams begin at the same time, while chained streams begin after
+			// the previous stream ends. Therefore, when no Vorbis stream is being yielded, any new
+			// Vorbis stream must be the first one or chained after the last yielded one
+			if header_type & BEGINNING_OF_STREAM_FLAG != 0
+				&& (self.vorbis_stream_serial.is_none() || self.vorbis_stream_ended)
+				&& self.page[PAGE_HEADER_LENGTH + segment_count..]
+					.starts_with(VORBIS_IDENTIFICATION_HEADER_SIGNATURE)
+			{
+				self.vorbis_stream_serial = Some(serial);
+				self.vorbis_stream_ended = false;
+			}
+
+			if self.vorbis_stream_serial == Some(serial) && !self.vorbis_stream_ended {
+				self.vorbis_stream_ended = header_type & END_OF_STREAM_FLAG != 0;
+				return Ok(true);
+			}
+		}
+	}
+
+	/// Appends the specified number of bytes from the source to the page buffer. Returns
+	/// `false` if the end of the source was reached before reading them.
+	fn read_page_bytes(&mut self, length: usize) -> io::Result<bool> {
+		let read_length = (&mut self.source)
+			.take(length as u64)
+			.read_to_end(&mut self.page)?;
+
+		Ok(read_length == length)
+	}
+}
+
+impl<R: Read> Read for OggVorbisStreamFilter<R> {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		if self.page_position == self.page.len() && !self.read_next_page()? {
+			return Ok(0);
+		}
+
+		let read_length = (&self.page[self.page_position..]).read(buf)?;
+		self.page_position += read_length;
+
+		Ok(read_length)
+	}
+}
+
+/// Checks whether [OggVorbisStreamFilter] would drop any data of the specified Ogg file, such
+/// as logical streams that are not Vorbis audio or trailing garbage. Files without Vorbis
+/// streams are deemed to not contain extraneous data, as they can't be meaningfully filtered.
+pub fn has_extraneous_data(ogg_file: &[u8]) -> bool {
+	let mut filter = OggVorbisStreamFilter::new(ogg_file);
+	let mut filtered_file_length = 0;
+
+	while let Ok(true) = filter.read_next_page() {
+		filtered_file_length += filter.page.len();
+	}
+
+	filter.vorbis_stream_serial.is_some() && filtered_file_length < ogg_file.len()
+}
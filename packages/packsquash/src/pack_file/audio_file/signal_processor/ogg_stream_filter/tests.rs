@@ -0,0 +1,92 @@
+use super::*;
+
+static OGG_AUDIO_DATA: &[u8] = include_bytes!("../../dtmf_tone.ogg");
+
+/// Splits the specified well-formed Ogg file into its pages.
+fn ogg_pages(mut ogg_file: &[u8]) -> Vec<&[u8]> {
+	let mut pages = vec![];
+
+	while !ogg_file.is_empty() {
+		let segment_count = ogg_file[26] as usize;
+		let body_length = ogg_file[PAGE_HEADER_LENGTH..PAGE_HEADER_LENGTH + segment_count]
+			.iter()
+			.map(|&lacing_value| lacing_value as usize)
+			.sum::<usize>();
+
+		let (page, remaining_file) =
+			ogg_file.split_at(PAGE_HEADER_LENGTH + segment_count + body_length);
+		pages.push(page);
+		ogg_file = remaining_file;
+	}
+
+	pages
+}
+
+/// Generates an Ogg page of a logical stream with the specified serial, containing a single
+/// packet with the specified data.
+fn ogg_page(header_type: u8, serial: u32, packet: &[u8]) -> Vec<u8> {
+	let mut page = vec![];
+	page.extend_from_slice(CAPTURE_PATTERN);
+	page.push(0); // Version
+	page.push(header_type);
+	page.extend_from_slice(&0u64.to_le_bytes()); // Granule position
+	page.extend_from_slice(&serial.to_le_bytes());
+	page.extend_from_slice(&0u32.to_le_bytes()); // Sequence number
+	page.extend_from_slice(&0u32.to_le_bytes()); // Checksum
+	page.push(1);
+	page.push(packet.len() as u8);
+	page.extend_from_slice(packet);
+
+	page
+}
+
+fn filter(ogg_file: &[u8]) -> Vec<u8> {
+	let mut filtered_file = vec![];
+	OggVorbisStreamFilter::new(ogg_file)
+		.read_to_end(&mut filtered_file)
+		.expect("Filtering in-memory data should not fail");
+
+	filtered_file
+}
+
+#[test]
+fn single_vorbis_stream_is_kept() {
+	assert_eq!(filter(OGG_AUDIO_DATA), OGG_AUDIO_DATA);
+	assert!(!has_extraneous_data(OGG_AUDIO_DATA));
+}
+
+#[test]
+fn multiplexed_streams_are_filtered_out() {
+	let pages = ogg_pages(OGG_AUDIO_DATA);
+	let (first_page, remaining_pages) = pages.split_first().unwrap();
+
+	// Multiplex a Vorbis stream with a Theora-like video stream and a secondary Vorbis
+	// stream, whose beginning pages must come before any other page
+	let mut multiplexed_file = first_page.to_vec();
+	multiplexed_file.extend(ogg_page(BEGINNING_OF_STREAM_FLAG, 0xCAFE, b"\x80theora"));
+	multiplexed_file.extend(ogg_page(BEGINNING_OF_STREAM_FLAG, 0xBEEF, b"\x01vorbis"));
+	for page in remaining_pages {
+		multiplexed_file.extend_from_slice(page);
+		multiplexed_file.extend(ogg_page(0, 0xCAFE, b"frame"));
+	}
+	multiplexed_file.extend(ogg_page(END_OF_STREAM_FLAG, 0xBEEF, b"end"));
+
+	assert_eq!(filter(&multiplexed_file), OGG_AUDIO_DATA);
+	assert!(has_extraneous_data(&multiplexed_file));
+}
+
+#[test]
+fn chained_vorbis_streams_are_kept() {
+	let chained_file = [OGG_AUDIO_DATA, OGG_AUDIO_DATA].concat();
+
+	assert_eq!(filter(&chained_file), chained_file);
+	assert!(!has_extraneous_data(&chained_file));
+}
+
+#[test]
+fn garbage_is_skipped() {
+	let file_with_garbage = [&b"ID3 tag"[..], OGG_AUDIO_DATA, b"OggS trailing garbage"].concat();
+
+	assert_eq!(filter(&file_with_garbage), OGG_AUDIO_DATA);
+	assert!(has_extraneous_data(&file_with_garbage));
+}
@@ -0,0 +1,155 @@
+//! Contains a Symphonia decoder for Opus audio, backed by libopus, as Symphonia does not
+//! support decoding Opus on its own.
+//!
+//! References:
+//! - <https://datatracker.ietf.org/doc/html/rfc6716>
+//! - <https://datatracker.ietf.org/doc/html/rfc7845#section-5.1>
+
+use std::sync::{Mutex, PoisonError};
+
+use symphonia::core::audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef, Signal, SignalSpec};
+use symphonia::core::codecs::{
+	CodecDescriptor, CodecParameters, Decoder, DecoderOptions, FinalizeResult, CODEC_TYPE_OPUS
+};
+use symphonia::core::errors::{decode_error, unsupported_error, Result};
+use symphonia::core::formats::Packet;
+use symphonia::core::support_codec;
+
+/// The sampling frequency Opus audio is decoded at. Opus streams are always decoded at this
+/// frequency by Symphonia demuxers, no matter what their input sampling frequency was.
+const SAMPLING_FREQUENCY: u32 = 48_000;
+/// The maximum number of frames of audio an Opus packet may contain, which corresponds to
+/// 120 ms of audio at the decoding sampling frequency.
+const MAXIMUM_PACKET_FRAMES: usize = 5760;
+
+/// A Symphonia [Decoder] for mono and stereo Opus audio.
+pub struct OpusDecoder {
+	// libopus decoders can be sent between threads, but not shared between them. Symphonia
+	// decoders must be shareable, but they are only used through mutable references, so
+	// wrapping the libopus decoder in a mutex that is never locked is enough
+	decoder: Mutex<opus::Decoder>,
+	params: CodecParameters,
+	interleaved_samples: Vec<f32>,
+	buf: AudioBuffer<f32>
+}
+
+impl OpusDecoder {
+	fn decode_inner(&mut self, packet: &Packet) -> Result<()> {
+		let channels = self.buf.spec().channels.count();
+
+		let frames = self
+			.decoder
+			.get_mut()
+			.unwrap_or_else(PoisonError::into_inner)
+			.decode_float(packet.buf(), &mut self.interleaved_samples, false)
+			.or_else(|_| decode_error("opus: invalid packet"))?;
+
+		self.buf.clear();
+		self.buf.render_reserved(Some(frames));
+
+		for channel in 0..channels {
+			for (sample, interleaved_sample) in self
+				.buf
+				.chan_mut(channel)
+				.iter_mut()
+				.zip(self.interleaved_samples[channel..].iter().step_by(channels))
+			{
+				*sample = *interleaved_sample;
+			}
+		}
+
+		self.buf
+			.trim(packet.trim_start() as usize, packet.trim_end() as usize);
+
+		Ok(())
+	}
+}
+
+impl Decoder for OpusDecoder {
+	fn try_new(params: &CodecParameters, _: &DecoderOptions) -> Result<Self> {
+		if params.codec != CODEC_TYPE_OPUS {
+			return unsupported_error("opus: invalid codec type");
+		}
+
+		let Some(channel_layout) = params.channels else {
+			return unsupported_error("opus: unknown channel count");
+		};
+		let channels = match channel_layout.count() {
+			1 => opus::Channels::Mono,
+			2 => opus::Channels::Stereo,
+			_ => return unsupported_error("opus: more than two channels")
+		};
+
+		// The identification header specifies how channels are split into Opus streams for
+		// mapping families other than zero, which can only be decoded by a libopus decoder
+		// when they contain a single stream with every channel
+		if let Some(&[_, stream_count, coupled_stream_count, ..]) = params
+			.extra_data
+			.as_deref()
+			.and_then(|header| header.get(18..))
+		{
+			if stream_count != 1 || coupled_stream_count as usize != channel_layout.count() - 1 {
+				return unsupported_error("opus: multiple streams");
+			}
+		}
+
+		let mut decoder = opus::Decoder::new(SAMPLING_FREQUENCY, channels)
+			.or_else(|_| unsupported_error("opus: could not create decoder"))?;
+
+		// The identification header may specify a gain to apply to the decoded samples, in
+		// Q7.8 dB units, which libopus applies while decoding
+		if let Some(&[low_byte, high_byte]) = params
+			.extra_data
+			.as_deref()
+			.and_then(|header| header.get(16..18))
+		{
+			decoder
+				.set_gain(i16::from_le_bytes([low_byte, high_byte]).into())
+				.or_else(|_| unsupported_error("opus: invalid output gain"))?;
+		}
+
+		Ok(Self {
+			decoder: Mutex::new(decoder),
+			params: params.clone(),
+			interleaved_samples: vec![0.0; MAXIMUM_PACKET_FRAMES * channel_layout.count()],
+			buf: AudioBuffer::new(
+				MAXIMUM_PACKET_FRAMES as u64,
+				SignalSpec::new(SAMPLING_FREQUENCY, channel_layout)
+			)
+		})
+	}
+
+	fn supported_codecs() -> &'static [CodecDescriptor] {
+		&[support_codec!(CODEC_TYPE_OPUS, "opus", "Opus")]
+	}
+
+	fn reset(&mut self) {
+		// Resetting a valid decoder state never fails
+		self.decoder
+			.get_mut()
+			.unwrap_or_else(PoisonError::into_inner)
+			.reset_state()
+			.ok();
+	}
+
+	fn codec_params(&self) -> &CodecParameters {
+		&self.params
+	}
+
+	fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
+		if let Err(err) = self.decode_inner(packet) {
+			self.buf.clear();
+			Err(err)
+		} else {
+			Ok(self.buf.as_audio_buffer_ref())
+		}
+	}
+
+	fn finalize(&mut self) -> FinalizeResult {
+		Default::default()
+	}
+
+	fn last_decoded(&self) -> AudioBufferRef<'_> {
+		self.buf.as_audio_buffer_ref()
+	}
+}
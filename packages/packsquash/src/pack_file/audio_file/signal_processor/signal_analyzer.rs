@@ -0,0 +1,131 @@
+//! Contains a signal analyzer that measures properties of audio signals which are useful to
+//! automatically select how to process them.
+
+use std::num::NonZeroU32;
+
+use super::biquad::{Biquad, BUTTERWORTH_QUALITY_FACTORS};
+
+#[cfg(test)]
+mod tests;
+
+/// The standard sampling frequencies that may be automatically selected for audio signals,
+/// in ascending order.
+const STANDARD_SAMPLING_FREQUENCIES: [u32; 8] =
+	[8000, 11_025, 16_000, 22_050, 24_000, 32_000, 44_100, 48_000];
+/// The fraction of the Nyquist frequency of a sampling frequency that is considered to be
+/// preserved when resampling to it, accounting for the transition band of the resampler
+/// anti-aliasing filter.
+const PRESERVED_BANDWIDTH_FRACTION: f64 = 0.9;
+
+/// Analyzes the stereo similarity and bandwidth of an audio signal, whose samples are fed to
+/// it in blocks of planar samples.
+pub struct SignalAnalyzer {
+	sampling_frequency: NonZeroU32,
+	is_stereo: bool,
+	energy: f64,
+	mid_energy: f64,
+	side_energy: f64,
+	candidate_sampling_frequencies: Vec<CandidateSamplingFrequency>
+}
+
+/// A sampling frequency lower than the one of the analyzed signal, with the high-pass filters
+/// that isolate the part of the signal which would be lost by resampling to it.
+struct CandidateSamplingFrequency {
+	sampling_frequency: NonZeroU32,
+	channel_filters: Vec<[Biquad; 2]>,
+	lost_energy: f64
+}
+
+impl SignalAnalyzer {
+	/// Creates a new signal analyzer for a signal with the specified sampling frequency and
+	/// number of channels.
+	pub fn new(sampling_frequency: NonZeroU32, channels: usize) -> Self {
+		let candidate_sampling_frequencies = STANDARD_SAMPLING_FREQUENCIES
+			.into_iter()
+			.filter(|candidate_sampling_frequency| {
+				*candidate_sampling_frequency < sampling_frequency.get()
+			})
+			.map(|candidate_sampling_frequency| {
+				let cutoff_frequency =
+					candidate_sampling_frequency as f64 / 2.0 * PRESERVED_BANDWIDTH_FRACTION;
+
+				CandidateSamplingFrequency {
+					sampling_frequency: NonZeroU32::new(candidate_sampling_frequency).unwrap(),
+					channel_filters: vec![
+						BUTTERWORTH_QUALITY_FACTORS.map(|quality_factor| {
+							Biquad::high_pass(
+								sampling_frequency.get() as f64,
+								cutoff_frequency,
+								quality_factor
+							)
+						});
+						channels
+					],
+					lost_energy: 0.0
+				}
+			})
+			.collect();
+
+		Self {
+			sampling_frequency,
+			is_stereo: channels == 2,
+			energy: 0.0,
+			mid_energy: 0.0,
+			side_energy: 0.0,
+			candidate_sampling_frequencies
+		}
+	}
+
+	/// Feeds the specified block of planar samples to this analyzer.
+	pub fn process(&mut self, block: &[Vec<f32>]) {
+		for (channel, channel_samples) in block.iter().enumerate() {
+			self.energy += channel_samples
+				.iter()
+				.map(|sample| *sample as f64 * *sample as f64)
+				.sum::<f64>();
+
+			for candidate in &mut self.candidate_sampling_frequencies {
+				let [first_filter, second_filter] = &mut candidate.channel_filters[channel];
+
+				for sample in channel_samples {
+					let lost_sample = second_filter.process(first_filter.process(*sample as f64));
+					candidate.lost_energy += lost_sample * lost_sample;
+				}
+			}
+		}
+
+		if let (true, [left_samples, right_samples]) = (self.is_stereo, block) {
+			for (left_sample, right_sample) in left_samples.iter().zip(right_samples) {
+				let mid_sample = (*left_sample as f64 + *right_sample as f64) / 2.0;
+				let side_sample = (*left_sample as f64 - *right_sample as f64) / 2.0;
+
+				self.mid_energy += mid_sample * mid_sample;
+				self.side_energy += side_sample * side_sample;
+			}
+		}
+	}
+
+	/// Returns the ratio between the energy of the side (i.e., difference) and mid (i.e., sum)
+	/// channels of the stereo signal fed to this analyzer, in decibels. The lower the ratio, the
+	/// more similar the stereo channels are. `None` is returned for non-stereo or silent signals.
+	pub fn side_to_mid_energy_ratio(&self) -> Option<f64> {
+		(self.is_stereo && self.mid_energy > 0.0)
+			.then(|| 10.0 * (self.side_energy / self.mid_energy).log10())
+	}
+
+	/// Returns the lowest standard sampling frequency that preserves the bandwidth of the signal
+	/// fed to this analyzer, i.e., the lowest one that would lose a part of the signal whose
+	/// energy relative to the whole signal, in decibels, is below the specified threshold. If
+	/// no standard sampling frequency lower than the one of the signal qualifies, the sampling
+	/// frequency of the signal is returned.
+	pub fn bandwidth_preserving_sampling_frequency(&self, lost_energy_threshold: f64) -> NonZeroU32 {
+		let maximum_lost_energy = self.energy * 10f64.powf(lost_energy_threshold / 10.0);
+
+		self.candidate_sampling_frequencies
+			.iter()
+			.find(|candidate| candidate.lost_energy <= maximum_lost_energy)
+			.map_or(self.sampling_frequency, |candidate| {
+				candidate.sampling_frequency
+			})
+	}
+}
@@ -0,0 +1,57 @@
+use std::f32::consts::PI;
+use std::num::NonZeroU32;
+
+use super::*;
+
+const SAMPLING_FREQUENCY: NonZeroU32 = NonZeroU32::new(48_000).unwrap();
+
+/// Generates one second of samples of a sine wave with the specified frequency.
+fn sine_wave(frequency: f32) -> Vec<f32> {
+	(0..SAMPLING_FREQUENCY.get())
+		.map(|i| 0.5 * (2.0 * PI * frequency * i as f32 / SAMPLING_FREQUENCY.get() as f32).sin())
+		.collect()
+}
+
+#[test]
+fn identical_stereo_channels_are_detected() {
+	let mut analyzer = SignalAnalyzer::new(SAMPLING_FREQUENCY, 2);
+	analyzer.process(&[sine_wave(440.0), sine_wave(440.0)]);
+
+	assert_eq!(analyzer.side_to_mid_energy_ratio(), Some(f64::NEG_INFINITY));
+}
+
+#[test]
+fn different_stereo_channels_are_detected() {
+	let mut analyzer = SignalAnalyzer::new(SAMPLING_FREQUENCY, 2);
+	analyzer.process(&[sine_wave(440.0), sine_wave(1000.0)]);
+
+	let ratio = analyzer
+		.side_to_mid_energy_ratio()
+		.expect("The signal is stereo and not silent");
+	assert!(ratio > -3.0, "Unexpected side to mid energy ratio: {ratio}");
+}
+
+#[test]
+fn narrowband_signal_gets_low_sampling_frequency() {
+	let mut analyzer = SignalAnalyzer::new(SAMPLING_FREQUENCY, 1);
+	analyzer.process(&[sine_wave(1000.0)]);
+
+	let sampling_frequency = analyzer.bandwidth_preserving_sampling_frequency(-50.0);
+	assert!(
+		sampling_frequency.get() <= 16_000,
+		"Unexpected sampling frequency: {sampling_frequency}"
+	);
+}
+
+#[test]
+fn wideband_signal_keeps_its_sampling_frequency() {
+	let mut analyzer = SignalAnalyzer::new(SAMPLING_FREQUENCY, 1);
+	analyzer.process(&[(0..SAMPLING_FREQUENCY.get())
+		.map(|i| if i % 2 == 0 { 0.5 } else { -0.5 })
+		.collect()]);
+
+	assert_eq!(
+		analyzer.bandwidth_preserving_sampling_frequency(-50.0),
+		SAMPLING_FREQUENCY
+	);
+}
@@ -0,0 +1,52 @@
+//! Contains a routine to trim the leading and trailing silence of audio signals.
+
+#[cfg(test)]
+mod tests;
+
+/// Trims the leading and trailing frames of the specified planar audio samples whose samples
+/// are all quieter than the threshold amplitude, and then linearly fades in and out the edges
+/// that were trimmed over the specified number of frames, to avoid clicks. Signals which are
+/// quieter than the threshold everywhere are left as is. Returns the number of trimmed frames.
+pub fn trim_silence(samples: &mut [Vec<f32>], threshold_amplitude: f32, fade_length: usize) -> usize {
+	let frame_count = samples.first().map_or(0, Vec::len);
+	let is_audible_frame = |frame: usize| {
+		samples
+			.iter()
+			.any(|channel_samples| channel_samples[frame].abs() >= threshold_amplitude)
+	};
+
+	let Some(first_audible_frame) = (0..frame_count).find(|&frame| is_audible_frame(frame)) else {
+		return 0;
+	};
+	// There is at least an audible frame, so this search always succeeds
+	let last_audible_frame = (0..frame_count)
+		.rfind(|&frame| is_audible_frame(frame))
+		.unwrap();
+
+	let trimmed_frame_count = last_audible_frame + 1 - first_audible_frame;
+	// Do not let the fades overlap for very short signals
+	let fade_length = fade_length.min(trimmed_frame_count / 2);
+
+	for channel_samples in samples.iter_mut() {
+		channel_samples.truncate(last_audible_frame + 1);
+		channel_samples.drain(..first_audible_frame);
+
+		if first_audible_frame > 0 {
+			for (i, sample) in channel_samples[..fade_length].iter_mut().enumerate() {
+				*sample *= (i + 1) as f32 / (fade_length + 1) as f32;
+			}
+		}
+
+		if last_audible_frame + 1 < frame_count {
+			for (i, sample) in channel_samples[trimmed_frame_count - fade_length..]
+				.iter_mut()
+				.rev()
+				.enumerate()
+			{
+				*sample *= (i + 1) as f32 / (fade_length + 1) as f32;
+			}
+		}
+	}
+
+	frame_count - trimmed_frame_count
+}
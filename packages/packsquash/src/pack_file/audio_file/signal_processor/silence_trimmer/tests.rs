@@ -0,0 +1,32 @@
+use super::*;
+
+#[test]
+fn leading_and_trailing_silence_is_trimmed() {
+	let mut samples = vec![
+		vec![0.0, 0.001, 0.5, 0.0, -0.5, 0.001, 0.0],
+		vec![0.0, 0.0, 0.0, 0.25, 0.0, 0.0, 0.0],
+	];
+
+	assert_eq!(trim_silence(&mut samples, 0.01, 0), 4);
+	assert_eq!(samples, [vec![0.5, 0.0, -0.5], vec![0.0, 0.25, 0.0]]);
+}
+
+#[test]
+fn trimmed_edges_are_faded() {
+	let mut samples = vec![vec![0.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0]];
+
+	assert_eq!(trim_silence(&mut samples, 0.01, 2), 1);
+	assert_eq!(
+		samples,
+		[vec![1.0 / 3.0, 2.0 / 3.0, 1.0, 1.0, 1.0, 1.0]],
+		"Only the trimmed edge should be faded"
+	);
+}
+
+#[test]
+fn silent_signal_is_not_trimmed() {
+	let mut samples = vec![vec![0.0, 0.001, -0.001, 0.0]];
+
+	assert_eq!(trim_silence(&mut samples, 0.01, 2), 0);
+	assert_eq!(samples, [vec![0.0, 0.001, -0.001, 0.0]]);
+}
@@ -0,0 +1,168 @@
+//! Contains a sound fingerprinter that computes perceptual fingerprints of audio signals,
+//! which can be compared to find similar-sounding signals.
+//!
+//! References:
+//! - Haitsma, J., & Kalker, T. (2002). A Highly Robust Audio Fingerprinting System. In
+//!   Proceedings of the 3rd International Conference on Music Information Retrieval.
+
+use std::collections::VecDeque;
+use std::num::NonZeroU32;
+
+use crate::pack_index::SoundFingerprint;
+
+use super::biquad::Biquad;
+
+#[cfg(test)]
+mod tests;
+
+/// The number of frequency bands whose energies are compared to compute each sub-fingerprint,
+/// which is one more than the number of bits of a sub-fingerprint.
+const BANDS: usize = u16::BITS as usize + 1;
+/// The center frequency of the lowest frequency band, in Hz.
+const LOWEST_BAND_FREQUENCY: f64 = 250.0;
+/// The width of each frequency band, in octaves. The bands span four octaves in total, so the
+/// highest one is below the Nyquist frequency of the lowest sampling frequency supported.
+const BAND_WIDTH: f64 = 4.0 / BANDS as f64;
+/// The lowest sampling frequency of the signals that can be fingerprinted, in Hz.
+const MINIMUM_SAMPLING_FREQUENCY: u32 = 8000;
+/// The duration of the hops between sub-fingerprints, in seconds.
+const HOP_DURATION: f64 = 0.01;
+/// The number of hops whose energies are added to compute each sub-fingerprint. Using
+/// overlapping frames of several hops makes fingerprints robust to small time offsets.
+const FRAME_HOPS: usize = 16;
+/// The minimum number of sub-fingerprints of a meaningful fingerprint. Shorter fingerprints
+/// have too few bits to reliably tell sounds apart.
+const MINIMUM_SUB_FINGERPRINTS: usize = 8;
+/// The sample magnitude below which a sample is considered to be silence. This corresponds
+/// to -60 dBFS.
+const SILENCE_THRESHOLD: f32 = 0.001;
+
+/// Computes a perceptual fingerprint of an audio signal, whose samples are fed to it in blocks
+/// of planar samples. Leading and trailing silence is ignored, and the channels are averaged,
+/// so that sounds that only differ in these aspects or their gain have the same fingerprint.
+pub struct SoundFingerprinter {
+	band_filters: [Biquad; BANDS],
+	hop_length: usize,
+	hop_position: usize,
+	hop_energies: [f64; BANDS],
+	hop_is_silence: bool,
+	frame_hop_energies: VecDeque<[f64; BANDS]>,
+	previous_frame_energies: Option<[f64; BANDS]>,
+	sub_fingerprints: Vec<u16>,
+	audible_sub_fingerprints: usize,
+	sound_started: bool
+}
+
+impl SoundFingerprinter {
+	/// Creates a new sound fingerprinter for a signal with the specified sampling frequency.
+	/// Returns `None` if that sampling frequency is too low to fingerprint the signal.
+	pub fn new(sampling_frequency: NonZeroU32) -> Option<Self> {
+		if sampling_frequency.get() < MINIMUM_SAMPLING_FREQUENCY {
+			return None;
+		}
+
+		let quality_factor = 2f64.powf(BAND_WIDTH / 2.0) / (2f64.powf(BAND_WIDTH) - 1.0);
+
+		Some(Self {
+			band_filters: std::array::from_fn(|band| {
+				Biquad::band_pass(
+					sampling_frequency.get() as f64,
+					LOWEST_BAND_FREQUENCY * 2f64.powf(band as f64 * BAND_WIDTH),
+					quality_factor
+				)
+			}),
+			hop_length: (sampling_frequency.get() as f64 * HOP_DURATION) as usize,
+			hop_position: 0,
+			hop_energies: [0.0; BANDS],
+			hop_is_silence: true,
+			frame_hop_energies: VecDeque::with_capacity(FRAME_HOPS),
+			previous_frame_energies: None,
+			sub_fingerprints: vec![],
+			audible_sub_fingerprints: 0,
+			sound_started: false
+		})
+	}
+
+	/// Feeds the specified block of planar samples to this fingerprinter.
+	pub fn process(&mut self, block: &[Vec<f32>]) {
+		let frames = block.first().map_or(0, Vec::len);
+
+		for i in 0..frames {
+			let sample = block.iter().map(|channel| channel[i]).sum::<f32>() / block.len() as f32;
+			let is_silence = sample.abs() < SILENCE_THRESHOLD;
+
+			// Skip leading silence, so that the first hop starts with the sound
+			self.sound_started |= !is_silence;
+			if !self.sound_started {
+				continue;
+			}
+
+			for (band_filter, band_energy) in self.band_filters.iter_mut().zip(&mut self.hop_energies)
+			{
+				*band_energy += band_filter.process(sample as f64).powi(2);
+			}
+			self.hop_is_silence &= is_silence;
+
+			self.hop_position += 1;
+			if self.hop_position == self.hop_length {
+				self.finish_hop();
+			}
+		}
+	}
+
+	/// Returns the fingerprint of the signal fed to this fingerprinter, or `None` if the
+	/// signal is too short or quiet to be meaningfully fingerprinted.
+	pub fn fingerprint(mut self) -> Option<SoundFingerprint> {
+		// Ignore trailing silence
+		self.sub_fingerprints
+			.truncate(self.audible_sub_fingerprints);
+
+		(self.sub_fingerprints.len() >= MINIMUM_SUB_FINGERPRINTS)
+			.then(|| SoundFingerprint::new(self.sub_fingerprints))
+	}
+
+	/// Adds the energies of the current hop to the current frame, computing a new
+	/// sub-fingerprint when the frame is complete, and starts a new hop.
+	fn finish_hop(&mut self) {
+		if self.frame_hop_energies.len() == FRAME_HOPS {
+			self.frame_hop_energies.pop_front();
+		}
+		self.frame_hop_energies.push_back(self.hop_energies);
+
+		if self.frame_hop_energies.len() == FRAME_HOPS {
+			let mut frame_energies = [0.0; BANDS];
+			for hop_energies in &self.frame_hop_energies {
+				for (frame_energy, hop_energy) in frame_energies.iter_mut().zip(hop_energies) {
+					*frame_energy += hop_energy;
+				}
+			}
+
+			// Each bit tells whether the energy difference between two adjacent bands increased
+			// since the previous frame. These differences are independent of the signal gain
+			if let Some(previous_frame_energies) = self.previous_frame_energies {
+				let mut sub_fingerprint = 0;
+				for band in 0..BANDS - 1 {
+					let energy_difference = frame_energies[band] - frame_energies[band + 1];
+					let previous_energy_difference =
+						previous_frame_energies[band] - previous_frame_energies[band + 1];
+
+					if energy_difference > previous_energy_difference {
+						sub_fingerprint |= 1 << band;
+					}
+				}
+
+				self.sub_fingerprints.push(sub_fingerprint);
+			}
+
+			self.previous_frame_energies = Some(frame_energies);
+		}
+
+		if !self.hop_is_silence {
+			self.audible_sub_fingerprints = self.sub_fingerprints.len();
+		}
+
+		self.hop_position = 0;
+		self.hop_energies = [0.0; BANDS];
+		self.hop_is_silence = true;
+	}
+}
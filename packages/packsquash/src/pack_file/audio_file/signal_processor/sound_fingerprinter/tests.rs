@@ -0,0 +1,65 @@
+use std::num::NonZeroU32;
+
+use pretty_assertions::assert_eq;
+
+use super::*;
+
+const SAMPLING_FREQUENCY: NonZeroU32 = NonZeroU32::new(48_000).unwrap();
+
+/// Generates the specified duration of pseudorandom white noise, determined by the specified
+/// seed.
+fn noise(seed: u32, duration: f32) -> Vec<f32> {
+	let mut state = seed;
+
+	(0..(SAMPLING_FREQUENCY.get() as f32 * duration) as usize)
+		.map(|_| {
+			state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+			(state >> 8) as f32 / (1 << 24) as f32 - 0.5
+		})
+		.collect()
+}
+
+fn fingerprint(channels: &[Vec<f32>]) -> Option<SoundFingerprint> {
+	let mut fingerprinter = SoundFingerprinter::new(SAMPLING_FREQUENCY).unwrap();
+	fingerprinter.process(channels);
+	fingerprinter.fingerprint()
+}
+
+#[test]
+fn gain_does_not_change_fingerprint() {
+	let samples = noise(1, 1.0);
+	let quieter_samples = samples.iter().map(|sample| sample * 0.5).collect();
+
+	assert_eq!(fingerprint(&[samples]), fingerprint(&[quieter_samples]));
+}
+
+#[test]
+fn silence_and_channels_do_not_change_fingerprint() {
+	let samples = noise(1, 1.0);
+	let padded_samples = [vec![0.0; 12_000], samples.clone(), vec![0.0; 24_000]].concat();
+
+	let expected_fingerprint = fingerprint(&[samples]);
+	assert!(expected_fingerprint.is_some());
+	assert_eq!(
+		fingerprint(&[padded_samples.clone(), padded_samples]),
+		expected_fingerprint
+	);
+}
+
+#[test]
+fn different_sounds_have_different_fingerprints() {
+	let bit_error_rate = fingerprint(&[noise(1, 1.0)])
+		.unwrap()
+		.bit_error_rate(&fingerprint(&[noise(2, 1.0)]).unwrap())
+		.expect("The sounds have the same duration");
+	assert!(
+		bit_error_rate > 0.3,
+		"Unexpectedly low bit error rate: {bit_error_rate}"
+	);
+}
+
+#[test]
+fn short_or_low_sampling_frequency_sounds_are_not_fingerprinted() {
+	assert_eq!(fingerprint(&[noise(1, 0.1)]), None);
+	assert!(SoundFingerprinter::new(NonZeroU32::new(4000).unwrap()).is_none());
+}
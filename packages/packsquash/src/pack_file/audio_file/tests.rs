@@ -14,6 +14,82 @@ static OGG_AUDIO_DATA: &[u8] = include_bytes!("dtmf_tone.ogg");
 static EMPTY_OGG_AUDIO_DATA: &[u8] = include_bytes!("empty.ogg");
 static OGG_AUDIO_DATA_UNUSUAL_SAMPLE_RATE: &[u8] = include_bytes!("araquanid_ambient.ogg");
 
+/// Generates a WAV file with a sine wave tone of the specified frequency in each channel, using
+/// the specified channel mask of its extensible format.
+fn tone_wav_data(channel_mask: u32, tone_frequencies: &[f32]) -> Vec<u8> {
+	const SAMPLING_FREQUENCY: u32 = 48_000;
+	let channels = tone_frequencies.len() as u16;
+
+	let mut samples = vec![];
+	for i in 0..SAMPLING_FREQUENCY / 2 {
+		for frequency in tone_frequencies {
+			let sample = 0.25
+				* (2.0 * std::f32::consts::PI * frequency * i as f32 / SAMPLING_FREQUENCY as f32)
+					.sin();
+
+			samples.extend_from_slice(&((sample * i16::MAX as f32) as i16).to_le_bytes());
+		}
+	}
+
+	let mut data = vec![];
+	data.extend_from_slice(b"RIFF");
+	data.extend_from_slice(&(4 + 8 + 40 + 8 + samples.len() as u32).to_le_bytes());
+	data.extend_from_slice(b"WAVEfmt ");
+	data.extend_from_slice(&40u32.to_le_bytes());
+	data.extend_from_slice(&0xFFFEu16.to_le_bytes()); // WAVE_FORMAT_EXTENSIBLE
+	data.extend_from_slice(&channels.to_le_bytes());
+	data.extend_from_slice(&SAMPLING_FREQUENCY.to_le_bytes());
+	data.extend_from_slice(&(SAMPLING_FREQUENCY * channels as u32 * 2).to_le_bytes());
+	data.extend_from_slice(&(channels * 2).to_le_bytes());
+	data.extend_from_slice(&16u16.to_le_bytes()); // Bits per sample
+	data.extend_from_slice(&22u16.to_le_bytes()); // Extension size
+	data.extend_from_slice(&16u16.to_le_bytes()); // Valid bits per sample
+	data.extend_from_slice(&channel_mask.to_le_bytes());
+	data.extend_from_slice(&[
+		0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xAA, 0x00, 0x38, 0x9B,
+		0x71
+	]); // PCM subformat GUID
+	data.extend_from_slice(b"data");
+	data.extend_from_slice(&(samples.len() as u32).to_le_bytes());
+	data.extend_from_slice(&samples);
+
+	data
+}
+
+/// Generates a 5.1 surround WAV file with a different sine wave tone in each channel.
+fn surround_wav_data() -> Vec<u8> {
+	// FL, FR, FC, LFE, BL and BR channels
+	tone_wav_data(0x3F, &[220.0, 440.0, 660.0, 880.0, 1100.0, 1320.0])
+}
+
+/// Generates a mono AIFF file with a sine wave tone of the specified frequency.
+fn tone_aiff_data(tone_frequency: f32) -> Vec<u8> {
+	const SAMPLING_FREQUENCY: u32 = 48_000;
+	const FRAMES: u32 = SAMPLING_FREQUENCY / 2;
+
+	let mut data = vec![];
+	data.extend_from_slice(b"FORM");
+	data.extend_from_slice(&(4 + 8 + 18 + 8 + 8 + FRAMES * 2).to_be_bytes());
+	data.extend_from_slice(b"AIFFCOMM");
+	data.extend_from_slice(&18u32.to_be_bytes());
+	data.extend_from_slice(&1u16.to_be_bytes()); // Channels
+	data.extend_from_slice(&FRAMES.to_be_bytes());
+	data.extend_from_slice(&16u16.to_be_bytes()); // Bits per sample
+	data.extend_from_slice(&[0x40, 0x0E, 0xBB, 0x80, 0, 0, 0, 0, 0, 0]); // 48 kHz, as an 80-bit float
+	data.extend_from_slice(b"SSND");
+	data.extend_from_slice(&(8 + FRAMES * 2).to_be_bytes());
+	data.extend_from_slice(&[0; 8]); // Offset and block size
+	for i in 0..FRAMES {
+		let sample = 0.25
+			* (2.0 * std::f32::consts::PI * tone_frequency * i as f32 / SAMPLING_FREQUENCY as f32)
+				.sin();
+
+		data.extend_from_slice(&((sample * i16::MAX as f32) as i16).to_be_bytes());
+	}
+
+	data
+}
+
 /// Processes the given input data as a [AudioFile], using the provided settings,
 /// expecting a successful result.
 async fn successful_process_test(
@@ -28,6 +104,9 @@ async fn successful_process_test(
 		read: Builder::new().read(input_data).build(),
 		file_length_hint: input_data.len(),
 		is_ogg,
+		is_music_stream: false,
+		sound_fingerprints: None,
+		depends_on_other_pack_files: false,
 		optimization_settings: settings
 	}
 	.process();
@@ -108,6 +187,9 @@ async fn error_process_test<T: AsyncRead + Unpin + Send + 'static>(
 		read,
 		file_length_hint: 0,
 		is_ogg,
+		is_music_stream: false,
+		sound_fingerprints: None,
+		depends_on_other_pack_files: false,
 		optimization_settings: settings
 	}
 	.process();
@@ -147,6 +229,19 @@ async fn transcoding_unusual_sample_rate_works() {
 	.await
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn aiff_transcoding_works() {
+	successful_process_test(
+		&tone_aiff_data(440.0),
+		false, // Is not Ogg
+		Default::default(),
+		false,                               // Smaller file size
+		1,                                   // One channel (mono)
+		POSITIONAL_AUDIO_SAMPLING_FREQUENCY  // Default sampling frequency
+	)
+	.await
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
 async fn passthrough_works() {
 	successful_process_test(
@@ -197,6 +292,52 @@ async fn channel_mixing_works() {
 	.await
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn surround_downmixing_works() {
+	successful_process_test(
+		&surround_wav_data(),
+		false, // Is not Ogg
+		Default::default(),
+		false,                                   // Smaller file size
+		2,                                       // Two channels (stereo)
+		NON_POSITIONAL_AUDIO_SAMPLING_FREQUENCY  // Default sampling frequency
+	)
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn surround_downmixing_to_mono_works() {
+	successful_process_test(
+		&surround_wav_data(),
+		false, // Is not Ogg
+		AudioFileOptions {
+			channels: ChannelMixingOption::ToChannels(NonZeroU8::new(1).unwrap().try_into().unwrap()),
+			..Default::default()
+		},
+		false,                               // Smaller file size
+		1,                                   // One channel (mono)
+		POSITIONAL_AUDIO_SAMPLING_FREQUENCY  // Default sampling frequency
+	)
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn automatic_channel_mixing_and_sampling_frequency_work() {
+	successful_process_test(
+		&tone_wav_data(0x3, &[440.0, 440.0]), // FL and FR channels with the same tone
+		false,                                // Is not Ogg
+		AudioFileOptions {
+			channels: ChannelMixingOption::Auto,
+			sampling_frequency: Some(SamplingFrequencyOption::Auto),
+			..Default::default()
+		},
+		false,                          // Smaller file size
+		1,                              // One channel (mono)
+		NonZeroU32::new(8000).unwrap()  // Lowest sampling frequency that preserves the tone
+	)
+	.await
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
 async fn channel_mixing_and_pitch_shifting_work() {
 	successful_process_test(
@@ -214,6 +355,24 @@ async fn channel_mixing_and_pitch_shifting_work() {
 	.await
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn filtering_works() {
+	successful_process_test(
+		FLAC_AUDIO_DATA,
+		false, // Is not Ogg
+		AudioFileOptions {
+			high_pass_filter_cutoff_frequency: NonZeroU32::new(30),
+			low_pass_filter_cutoff_frequency: NonZeroU32::new(12_000),
+			limiter_threshold: Some(-3.0),
+			..Default::default()
+		},
+		false,                               // Smaller file size
+		1,                                   // One channel (mono)
+		POSITIONAL_AUDIO_SAMPLING_FREQUENCY  // Default sampling frequency
+	)
+	.await
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
 async fn transcoded_audio_is_not_upsampled() {
 	successful_process_test(
@@ -227,6 +386,162 @@ async fn transcoded_audio_is_not_upsampled() {
 	.await
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn signal_to_noise_ratio_check_works() {
+	successful_process_test(
+		FLAC_AUDIO_DATA,
+		false, // Is not Ogg
+		AudioFileOptions {
+			minimum_signal_to_noise_ratio: Some(-100.0),
+			..Default::default()
+		},
+		false,                               // Smaller file size
+		1,                                   // One channel (mono)
+		POSITIONAL_AUDIO_SAMPLING_FREQUENCY  // Default sampling frequency
+	)
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn insufficient_signal_to_noise_ratio_is_handled() {
+	error_process_test(
+		Builder::new().read(FLAC_AUDIO_DATA).build(),
+		false, // Is not Ogg
+		AudioFileOptions {
+			// Lossy encoding can't possibly be this good
+			minimum_signal_to_noise_ratio: Some(1000.0),
+			..Default::default()
+		}
+	)
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn target_file_size_works() {
+	successful_process_test(
+		FLAC_AUDIO_DATA,
+		false, // Is not Ogg
+		AudioFileOptions {
+			bitrate_control_mode: AudioBitrateControlMode::TargetFileSize,
+			target_bitrate_control_metric: Some(16.0),
+			..Default::default()
+		},
+		false,                               // Smaller file size
+		1,                                   // One channel (mono)
+		POSITIONAL_AUDIO_SAMPLING_FREQUENCY  // Default sampling frequency
+	)
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn unreachable_target_file_size_is_handled() {
+	error_process_test(
+		Builder::new().read(FLAC_AUDIO_DATA).build(),
+		false, // Is not Ogg
+		AudioFileOptions {
+			bitrate_control_mode: AudioBitrateControlMode::TargetFileSize,
+			// Vorbis headers alone take more space than this
+			target_bitrate_control_metric: Some(0.25),
+			..Default::default()
+		}
+	)
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn loudness_normalization_works() {
+	successful_process_test(
+		OGG_AUDIO_DATA,
+		true, // Is Ogg
+		AudioFileOptions {
+			loudness_normalization_target: Some(-16.0),
+			..Default::default()
+		},
+		false,                               // Smaller file size
+		1,                                   // One channel (mono)
+		POSITIONAL_AUDIO_SAMPLING_FREQUENCY  // Default sampling frequency
+	)
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn silence_trimming_works() {
+	successful_process_test(
+		FLAC_AUDIO_DATA,
+		false, // Is not Ogg
+		AudioFileOptions {
+			silence_trimming_threshold: Some(-60.0),
+			silence_trimming_fade_duration: 5,
+			..Default::default()
+		},
+		false,                               // Smaller file size
+		1,                                   // One channel (mono)
+		POSITIONAL_AUDIO_SAMPLING_FREQUENCY  // Default sampling frequency
+	)
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn near_duplicate_sounds_are_detected() {
+	let sound_fingerprints = Arc::<SoundFingerprints>::default();
+
+	for (input_data, is_ogg, sound_file_path) in [
+		(
+			FLAC_AUDIO_DATA,
+			false,
+			"assets/mypack/sounds/dtmf_tone.flac"
+		),
+		(OGG_AUDIO_DATA, true, "assets/mypack/sounds/dtmf_tone.ogg"),
+		(
+			FLAC_AUDIO_DATA_8KHZ,
+			false,
+			"assets/mypack/sounds/dtmf_tone_8khz.flac"
+		),
+		(
+			OGG_AUDIO_DATA_UNUSUAL_SAMPLE_RATE,
+			true,
+			"assets/mypack/sounds/araquanid_ambient.ogg"
+		)
+	] {
+		AudioFile {
+			read: Builder::new().read(input_data).build(),
+			file_length_hint: input_data.len(),
+			is_ogg,
+			is_music_stream: false,
+			sound_fingerprints: Some((Arc::clone(&sound_fingerprints), sound_file_path.into())),
+			depends_on_other_pack_files: false,
+			optimization_settings: AudioFileOptions::default()
+		}
+		.process()
+		.map(|result| result.expect("No error should happen while processing"))
+		.collect::<Vec<_>>()
+		.await;
+	}
+
+	assert_eq!(
+		sound_fingerprints.near_duplicate_clusters(),
+		vec![vec![
+			"assets/mypack/sounds/dtmf_tone.flac".to_string(),
+			"assets/mypack/sounds/dtmf_tone.ogg".to_string(),
+			"assets/mypack/sounds/dtmf_tone_8khz.flac".to_string()
+		]]
+	);
+}
+
+#[test]
+fn music_streams_and_loop_markers_are_detected() {
+	assert!(is_music_stream(
+		"assets/minecraft/sounds/music/game/calm1.ogg"
+	));
+	assert!(is_music_stream("assets/mypack/sounds/records/cat.ogg"));
+	assert!(!is_music_stream(
+		"assets/minecraft/sounds/mob/cat/meow1.ogg"
+	));
+
+	assert!(has_loop_markers(b"OggS\x03vorbis\x0e\0\0\0loopstart=4410"));
+	assert!(!has_loop_markers(OGG_AUDIO_DATA));
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
 async fn valid_empty_input_works() {
 	successful_process_test(
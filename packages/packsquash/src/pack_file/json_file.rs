@@ -1,6 +1,7 @@
 //! Contains code to optimize JSON files.
 
 use std::borrow::Cow;
+use std::sync::Arc;
 
 use bytes::{BufMut, BytesMut};
 use json_comments::StripComments;
@@ -12,12 +13,18 @@ use tokio_util::codec::{Decoder, FramedRead};
 use crate::config::JsonFileOptions;
 use crate::pack_file::asset_type::PackFileAssetType;
 use crate::pack_file::AsyncReadAndSizeHint;
+use crate::pack_index::{
+	language_file_namespace_and_locale, texture_path_and_metadata_asset_type,
+	AnimationFrameDeduplication, TextureDownscaling, Translations, FALLBACK_LOCALE
+};
 
-use super::{util::strip_utf8_bom, PackFile, PackFileConstructor};
+use super::{util::strip_utf8_bom, PackFile, PackFileConstructor, PackFileContext};
 
 use self::debloater::Debloater;
 
+mod blockstate;
 mod debloater;
+mod json5_parser;
 
 #[cfg(test)]
 mod tests;
@@ -31,13 +38,23 @@ pub struct JsonFile<T: AsyncRead + Send + Unpin + 'static> {
 	read: T,
 	file_length_hint: usize,
 	asset_type: PackFileAssetType,
-	optimization_settings: JsonFileOptions
+	optimization_settings: JsonFileOptions,
+	fallback_translations: Option<Arc<Translations>>,
+	block_state_properties: Option<Vec<String>>,
+	animation_frame_deduplication: Option<Arc<AnimationFrameDeduplication>>,
+	texture_downscaling: Option<TextureDownscaling>,
+	may_be_directory_listed_atlas_texture_sprite: bool,
+	depends_on_other_pack_files: bool
 }
 
 /// Optimizer decoder that transforms JSON files to an optimized representation.
 pub struct OptimizerDecoder {
 	asset_type: PackFileAssetType,
 	optimization_settings: JsonFileOptions,
+	fallback_translations: Option<Arc<Translations>>,
+	block_state_properties: Option<Vec<String>>,
+	animation_frame_deduplication: Option<Arc<AnimationFrameDeduplication>>,
+	texture_downscaling: Option<TextureDownscaling>,
 	reached_eof: bool
 }
 
@@ -47,8 +64,12 @@ pub struct OptimizerDecoder {
 pub enum OptimizationError {
 	#[error("JSON error: {0}")]
 	JsonSerde(#[from] serde_json::Error),
+	#[error("JSON5 syntax error: {0}")]
+	Json5Syntax(#[from] json5_parser::SyntaxError),
 	#[error("Unexpected JSON value: {0}")]
 	UnexpectedValue(&'static str),
+	#[error("Unknown block state property \"{property}\" referenced in {location}")]
+	UnknownBlockStateProperty { property: String, location: String },
 	#[error("I/O error: {0}")]
 	Io(#[from] std::io::Error)
 }
@@ -75,14 +96,12 @@ impl Decoder for OptimizerDecoder {
 		self.reached_eof = true;
 
 		// Parse the JSON so we know how to serialize it again in a compact manner, and whether
-		// it's valid. Check whether we should parse and discard comments, too
-		let mut json_value: Value = if self.optimization_settings.always_allow_comments
-			|| asset_type_has_comments_extension(self.asset_type)
-		{
-			serde_json::from_reader(StripComments::new(strip_utf8_bom(src)))?
-		} else {
-			serde_json::from_slice(strip_utf8_bom(src))?
-		};
+		// it's valid
+		let mut json_value = parse_json(
+			src,
+			self.asset_type,
+			self.optimization_settings.always_allow_comments
+		)?;
 
 		// All concrete asset types start with a JSON object (aka struct, map)
 		if self.asset_type != PackFileAssetType::GenericJson
@@ -98,13 +117,58 @@ impl Decoder for OptimizerDecoder {
 		// the optimized JSON serialization
 		src.clear();
 
+		// Validate and simplify blockstates, which is part of debloating them
+		let blockstate_simplified = if self.optimization_settings.delete_bloat
+			&& matches!(
+				self.asset_type,
+				PackFileAssetType::MinecraftBlockState
+					| PackFileAssetType::MinecraftBlockStateWithComments
+			) {
+			blockstate::validate_and_simplify(
+				&mut json_value,
+				self.block_state_properties.as_deref()
+			)?
+		} else {
+			false
+		};
+
+		// Make animations play the frames their texture has after removing duplicate ones
+		if let Some(animation_frame_deduplication) = &self.animation_frame_deduplication {
+			animation_frame_deduplication.remap_frames(&mut json_value);
+		}
+
+		// Make animations expect the frame size their texture has after downscaling it
+		if let Some(texture_downscaling) = &self.texture_downscaling {
+			texture_downscaling.scale_frame_dimensions(&mut json_value);
+		}
+
 		// Debloat the read value
 		let debloated = if self.optimization_settings.delete_bloat {
 			DEBLOATER.with(|debloater| debloater.debloat(&mut json_value, self.asset_type))
+				|| blockstate_simplified
 		} else {
 			false
 		};
 
+		// Delete translations that Minecraft would fall back to anyway
+		let deleted_translations = self
+			.fallback_translations
+			.as_ref()
+			.zip(json_value.as_object_mut())
+			.map(|(fallback_translations, translations)| {
+				let translation_count = translations.len();
+
+				translations.retain(|key, translation| {
+					!matches!(
+						(fallback_translations.get(key), translation),
+						(Some(fallback_translation), Value::String(translation))
+							if fallback_translation == translation
+					)
+				});
+
+				translation_count - translations.len()
+			});
+
 		let mut json_writer = src.split_off(0).writer();
 
 		macro_rules! concat_debloated_suffix {
@@ -126,11 +190,21 @@ impl Decoder for OptimizerDecoder {
 			concat_debloated_suffix!("Prettified")
 		};
 
+		let description = match deleted_translations {
+			Some(deleted_translations) => Cow::Owned(format!(
+				"{description}, deleted {deleted_translations} redundant translations"
+			)),
+			None => Cow::Borrowed(description)
+		};
+
+		let description = if self.animation_frame_deduplication.is_some() {
+			Cow::Owned(format!("{description}, remapped animation frames"))
+		} else {
+			description
+		};
+
 		// Cheaply get an owned BytesMut with the serialized JSON data
-		Ok(Some((
-			Cow::Borrowed(description),
-			json_writer.get_mut().split_off(0)
-		)))
+		Ok(Some((description, json_writer.get_mut().split_off(0))))
 	}
 }
 
@@ -145,6 +219,10 @@ impl<T: AsyncRead + Send + Unpin + 'static> PackFile for JsonFile<T> {
 			OptimizerDecoder {
 				asset_type: self.asset_type,
 				optimization_settings: self.optimization_settings,
+				fallback_translations: self.fallback_translations,
+				block_state_properties: self.block_state_properties,
+				animation_frame_deduplication: self.animation_frame_deduplication,
+				texture_downscaling: self.texture_downscaling,
 				reached_eof: false
 			},
 			// FIXME consider refactoring this when we have a global memory budget
@@ -157,15 +235,11 @@ impl<T: AsyncRead + Send + Unpin + 'static> PackFile for JsonFile<T> {
 	}
 
 	fn may_be_directory_listed_atlas_texture_sprite(&self) -> bool {
-		// FIXME only return true if both the asset types match and the
-		//       corresponding texture has the may_be_directory_listed_atlas_texture
-		//       option set to true. This cannot be done elegantly with the current
-		//       iterator design though
-		matches!(
-			self.asset_type,
-			PackFileAssetType::MinecraftTextureMetadata
-				| PackFileAssetType::MinecraftTextureMetadataWithComments
-		)
+		self.may_be_directory_listed_atlas_texture_sprite
+	}
+
+	fn depends_on_other_pack_files(&self) -> bool {
+		self.depends_on_other_pack_files
 	}
 }
 
@@ -175,20 +249,122 @@ impl<T: AsyncRead + Send + Unpin + 'static> PackFileConstructor<T> for JsonFile<
 	fn new(
 		file_read_producer: impl FnOnce() -> Option<AsyncReadAndSizeHint<T>>,
 		asset_type: PackFileAssetType,
-		optimization_settings: Self::OptimizationSettings
+		optimization_settings: Self::OptimizationSettings,
+		pack_file_context: &PackFileContext<'_>
 	) -> Option<Self> {
+		// Translations of the fallback locale itself are never redundant
+		let redundant_translations_namespace =
+			language_file_namespace_and_locale(pack_file_context.relative_path)
+				.filter(|(_, locale, _)| {
+					optimization_settings.delete_redundant_translations
+						&& *locale != FALLBACK_LOCALE
+						&& matches!(
+							asset_type,
+							PackFileAssetType::MinecraftLanguage
+								| PackFileAssetType::MinecraftLanguageWithComments
+						)
+				})
+				.map(|(namespace, _, _)| namespace);
+		let fallback_translations = redundant_translations_namespace.and_then(|namespace| {
+			pack_file_context
+				.pack_index
+				.fallback_translations(namespace)
+				.cloned()
+		});
+
+		let block_state_properties = if matches!(
+			asset_type,
+			PackFileAssetType::MinecraftBlockState
+				| PackFileAssetType::MinecraftBlockStateWithComments
+		) {
+			blockstate::block_identifier(pack_file_context.relative_path).and_then(|block| {
+				pack_file_context
+					.global_options
+					.block_state_properties
+					.get(&block)
+					.cloned()
+			})
+		} else {
+			None
+		};
+
+		// Texture metadata files are listed alongside their textures, and describe how
+		// their frames are played
+		let is_texture_metadata = matches!(
+			asset_type,
+			PackFileAssetType::MinecraftTextureMetadata
+				| PackFileAssetType::MinecraftTextureMetadataWithComments
+		);
+		let texture_path = texture_path_and_metadata_asset_type(pack_file_context.relative_path)
+			.map(|(texture_path, _)| texture_path)
+			.filter(|_| is_texture_metadata);
+
+		let may_be_directory_listed_atlas_texture_sprite = is_texture_metadata
+			&& texture_path.map_or(true, |texture_path| {
+				pack_file_context
+					.pack_index
+					.may_be_directory_listed_atlas_sprite(texture_path)
+			});
+		let animation_frame_deduplication = texture_path
+			.and_then(|texture_path| {
+				pack_file_context
+					.pack_index
+					.animation_frame_deduplication(texture_path)
+			})
+			.cloned();
+		// Only downscalings that change frame dimensions need the metadata to be rewritten
+		let texture_downscaling = texture_path
+			.and_then(|texture_path| {
+				pack_file_context
+					.pack_index
+					.texture_downscaling(texture_path)
+			})
+			.filter(TextureDownscaling::downscales);
+
+		// Previous runs may have deleted translations even if there are no fallback ones now
+		let depends_on_other_pack_files = redundant_translations_namespace.is_some()
+			|| texture_path.is_some_and(|texture_path| {
+				pack_file_context
+					.pack_index
+					.is_interdependent_with_metadata(texture_path)
+			});
+
 		file_read_producer().map(|(read, file_length_hint)| Self {
 			read,
 			// The file is too big to fit in memory if this conversion fails anyway
 			file_length_hint: file_length_hint.try_into().unwrap_or(usize::MAX),
 			asset_type,
-			optimization_settings
+			optimization_settings,
+			fallback_translations,
+			block_state_properties,
+			animation_frame_deduplication,
+			texture_downscaling,
+			may_be_directory_listed_atlas_texture_sprite,
+			depends_on_other_pack_files
 		})
 	}
 }
 
+/// Parses the JSON value contained in the specified bytes, which may start with a UTF-8 BOM.
+/// Files of asset types with an extension that allows comments are parsed as JSON5, which is
+/// a superset of JSON with comments that pack authors find handy to write by hand. Otherwise,
+/// comments are only discarded if `always_allow_comments` is `true`.
+pub fn parse_json(
+	src: &[u8],
+	asset_type: PackFileAssetType,
+	always_allow_comments: bool
+) -> Result<Value, OptimizationError> {
+	Ok(if asset_type_has_comments_extension(asset_type) {
+		json5_parser::from_slice(strip_utf8_bom(src))?
+	} else if always_allow_comments {
+		serde_json::from_reader(StripComments::new(strip_utf8_bom(src)))?
+	} else {
+		serde_json::from_slice(strip_utf8_bom(src))?
+	})
+}
+
 /// Checks whether the specified asset type is an extension type whose file extension
-/// signals that its JSON data might have comments.
+/// signals that its JSON data might have comments, or any other JSON5 syntax extension.
 #[cfg_attr(
 	not(any(feature = "optifine-support", feature = "mtr3-support")),
 	allow(clippy::match_like_matches_macro)
@@ -198,6 +374,8 @@ const fn asset_type_has_comments_extension(asset_type: PackFileAssetType) -> boo
 		PackFileAssetType::MinecraftTextureMetadataWithComments
 		| PackFileAssetType::MinecraftMetadataWithComments
 		| PackFileAssetType::MinecraftModelWithComments
+		| PackFileAssetType::MinecraftLanguageWithComments
+		| PackFileAssetType::MinecraftBlockStateWithComments
 		| PackFileAssetType::GenericJsonWithComments => true,
 		#[cfg(feature = "optifine-support")]
 		PackFileAssetType::OptifineCustomEntityModelWithComments
@@ -0,0 +1,312 @@
+//! Contains code to validate and simplify Minecraft blockstate files, which map the states
+//! of a block to the models it is rendered with.
+//!
+//! References:
+//! - <https://minecraft.wiki/w/Tutorials/Models#Block_states>
+//! - Minecraft classes `net.minecraft.client.renderer.block.model.BlockModelDefinition`,
+//!   `net.minecraft.client.renderer.block.model.Variant` and
+//!   `net.minecraft.client.renderer.block.model.multipart.Selector`
+
+use serde_json::{Map, Value};
+
+use crate::RelativePath;
+
+use super::OptimizationError;
+
+/// The rotation values, in degrees, that Minecraft accepts for the `x` and `y` variant
+/// properties.
+const VALID_ROTATIONS: [i64; 4] = [0, 90, 180, 270];
+
+/// Returns the identifier of the block described by the blockstate file at the specified
+/// relative path, or `None` if the path does not belong to a blockstate file.
+pub fn block_identifier(relative_path: &RelativePath<'_>) -> Option<String> {
+	let relative_path = relative_path
+		.as_str()
+		.strip_suffix(".json")
+		.or_else(|| relative_path.as_str().strip_suffix(".jsonc"))?;
+	let (namespace, block_path) = relative_path
+		.strip_prefix("assets/")?
+		.split_once("/blockstates/")?;
+
+	Some(format!("{namespace}:{block_path}"))
+}
+
+/// Validates the structure of the specified blockstate JSON value, whose root is known to be
+/// an object, merging identical variants in weighted variant lists and removing variant
+/// properties with default values. If `known_properties` is `Some`, references to properties
+/// not in that list are considered errors.
+///
+/// Blockstates in the Forge format, marked by a `forge_marker` key, have a different structure
+/// that modded clients understand, so they are left as-is. The returned boolean is `true` if
+/// a simplification attempt was made.
+pub fn validate_and_simplify(
+	blockstate: &mut Value,
+	known_properties: Option<&[String]>
+) -> Result<bool, OptimizationError> {
+	let blockstate = blockstate.as_object_mut().unwrap();
+
+	if blockstate.contains_key("forge_marker") {
+		return Ok(false);
+	}
+
+	if !blockstate.contains_key("variants") && !blockstate.contains_key("multipart") {
+		return Err(OptimizationError::UnexpectedValue(
+			"Blockstates must have a \"variants\" or \"multipart\" key"
+		));
+	}
+
+	if let Some(variants) = blockstate.get_mut("variants") {
+		let variants = variants
+			.as_object_mut()
+			.ok_or(OptimizationError::UnexpectedValue(
+				"The \"variants\" blockstate key must contain an object"
+			))?;
+
+		for (variant_key, variant) in variants {
+			if let Some(known_properties) = known_properties {
+				// Legacy variant keys such as "normal" do not contain any property
+				for property in variant_key
+					.split(',')
+					.filter_map(|property_value| property_value.split_once('=').map(|(name, _)| name))
+				{
+					check_known_property(property, variant_key, known_properties)?;
+				}
+			}
+
+			validate_and_simplify_variants(variant)?;
+		}
+	}
+
+	if let Some(multipart) = blockstate.get_mut("multipart") {
+		let cases = multipart
+			.as_array_mut()
+			.ok_or(OptimizationError::UnexpectedValue(
+				"The \"multipart\" blockstate key must contain an array"
+			))?;
+
+		for case in cases {
+			let case = case
+				.as_object_mut()
+				.ok_or(OptimizationError::UnexpectedValue(
+					"Multipart blockstate cases must be objects"
+				))?;
+
+			if let Some(condition) = case.get("when") {
+				validate_condition(condition, known_properties)?;
+			}
+
+			validate_and_simplify_variants(case.get_mut("apply").ok_or(
+				OptimizationError::UnexpectedValue(
+					"Multipart blockstate cases must have an \"apply\" key"
+				)
+			)?)?;
+		}
+	}
+
+	Ok(true)
+}
+
+/// Validates and simplifies a single variant or a weighted list of variants.
+fn validate_and_simplify_variants(variants: &mut Value) -> Result<(), OptimizationError> {
+	match variants {
+		Value::Object(variant) => {
+			validate_and_simplify_variant(variant)?;
+
+			// The weight of a single variant does not matter, as it is always chosen
+			variant.remove("weight");
+		}
+		Value::Array(variant_list) => {
+			if variant_list.is_empty() {
+				return Err(OptimizationError::UnexpectedValue(
+					"Weighted variant lists must not be empty"
+				));
+			}
+
+			for variant in &mut *variant_list {
+				validate_and_simplify_variant(variant.as_object_mut().ok_or(
+					OptimizationError::UnexpectedValue("Variants must be objects")
+				)?)?;
+			}
+
+			merge_identical_variants(variant_list);
+
+			if variant_list.len() == 1 {
+				*variants = variant_list.pop().unwrap();
+				variants.as_object_mut().unwrap().remove("weight");
+			}
+		}
+		_ => {
+			return Err(OptimizationError::UnexpectedValue(
+				"Variants must be objects or arrays of objects"
+			));
+		}
+	}
+
+	Ok(())
+}
+
+/// Validates a single variant object, removing properties with default values. Rotations and
+/// weights written as integral floating-point numbers, which Minecraft accepts, are rewritten
+/// as integers.
+fn validate_and_simplify_variant(variant: &mut Map<String, Value>) -> Result<(), OptimizationError> {
+	if !variant.get("model").is_some_and(Value::is_string) {
+		return Err(OptimizationError::UnexpectedValue(
+			"Variants must have a \"model\" key with a string value"
+		));
+	}
+
+	for rotation_key in ["x", "y"] {
+		match variant.get(rotation_key).map(as_integer) {
+			Some(Some(0)) => {
+				variant.remove(rotation_key);
+			}
+			Some(Some(rotation)) if VALID_ROTATIONS.contains(&rotation) => {
+				variant.insert(rotation_key.into(), rotation.into());
+			}
+			Some(_) => {
+				return Err(OptimizationError::UnexpectedValue(
+					"Variant rotations must be 0, 90, 180 or 270 degrees"
+				));
+			}
+			None => {}
+		}
+	}
+
+	match variant.get("uvlock").map(Value::as_bool) {
+		Some(Some(false)) => {
+			variant.remove("uvlock");
+		}
+		Some(Some(_)) | None => {}
+		Some(None) => {
+			return Err(OptimizationError::UnexpectedValue(
+				"The \"uvlock\" variant key must contain a boolean"
+			));
+		}
+	}
+
+	match variant.get("weight").map(as_integer) {
+		Some(Some(1)) => {
+			variant.remove("weight");
+		}
+		Some(Some(weight)) if weight > 0 && weight <= i32::MAX as i64 => {
+			variant.insert("weight".into(), weight.into());
+		}
+		Some(_) => {
+			return Err(OptimizationError::UnexpectedValue(
+				"Variant weights must be positive integers"
+			));
+		}
+		None => {}
+	}
+
+	Ok(())
+}
+
+/// Merges variants of a weighted list that have exactly the same keys and values, other than
+/// their weight, into a single variant whose weight is the sum of the weights of the merged
+/// variants. Minecraft chooses a variant with a probability proportional to its weight, so
+/// this does not change how blocks look. The variants are expected to be validated and
+/// simplified objects, so that default values and number formatting don't tell them apart.
+/// Variants that reference the same model through different but equivalent resource
+/// locations, such as `stone` and `minecraft:block/stone`, are not merged.
+fn merge_identical_variants(variant_list: &mut Vec<Value>) {
+	let mut merged_variants: Vec<(Map<String, Value>, i64)> = Vec::with_capacity(variant_list.len());
+
+	for variant in variant_list.drain(..) {
+		let Value::Object(mut variant) = variant else {
+			unreachable!()
+		};
+
+		let weight = variant
+			.remove("weight")
+			.and_then(|weight| as_integer(&weight))
+			.unwrap_or(1);
+
+		match merged_variants
+			.iter_mut()
+			.find(|(merged_variant, _)| *merged_variant == variant)
+		{
+			Some((_, merged_weight)) => *merged_weight += weight,
+			None => merged_variants.push((variant, weight))
+		}
+	}
+
+	variant_list.extend(merged_variants.into_iter().map(|(mut variant, weight)| {
+		if weight != 1 {
+			variant.insert("weight".into(), weight.into());
+		}
+
+		Value::Object(variant)
+	}));
+}
+
+/// Validates a multipart case condition, which may be a combination of other conditions.
+fn validate_condition(
+	condition: &Value,
+	known_properties: Option<&[String]>
+) -> Result<(), OptimizationError> {
+	let condition = condition
+		.as_object()
+		.ok_or(OptimizationError::UnexpectedValue(
+			"Multipart blockstate conditions must be objects"
+		))?;
+
+	match condition.iter().next() {
+		Some((operator, operands))
+			if condition.len() == 1 && (operator == "OR" || operator == "AND") =>
+		{
+			for operand in operands
+				.as_array()
+				.ok_or(OptimizationError::UnexpectedValue(
+					"Multipart blockstate condition combinations must contain an array"
+				))? {
+				validate_condition(operand, known_properties)?;
+			}
+		}
+		_ => {
+			for (property, values) in condition {
+				if !matches!(values, Value::String(_) | Value::Number(_) | Value::Bool(_)) {
+					return Err(OptimizationError::UnexpectedValue(
+						"Multipart blockstate property conditions must contain primitive values"
+					));
+				}
+
+				if let Some(known_properties) = known_properties {
+					check_known_property(property, "multipart condition", known_properties)?;
+				}
+			}
+		}
+	}
+
+	Ok(())
+}
+
+/// Returns the integer value of the specified JSON value, if it is a number without a
+/// fractional part. Minecraft parses rotations and weights as integers, but accepts them
+/// written as floating-point numbers too.
+fn as_integer(value: &Value) -> Option<i64> {
+	value.as_i64().or_else(|| {
+		value
+			.as_f64()
+			.filter(|value| value.fract() == 0.0 && value.abs() <= i32::MAX as f64)
+			.map(|value| value as i64)
+	})
+}
+
+fn check_known_property(
+	property: &str,
+	location: &str,
+	known_properties: &[String]
+) -> Result<(), OptimizationError> {
+	if known_properties
+		.iter()
+		.any(|known_property| known_property == property)
+	{
+		Ok(())
+	} else {
+		Err(OptimizationError::UnknownBlockStateProperty {
+			property: property.into(),
+			location: location.into()
+		})
+	}
+}
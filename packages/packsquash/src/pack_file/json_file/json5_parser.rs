@@ -0,0 +1,465 @@
+//! Contains a parser for the [JSON5](https://spec.json5.org/) data interchange format, a
+//! superset of JSON that is more convenient to write by hand. Pack authors commonly rely
+//! on some of its extensions (trailing commas, single-quoted strings, unquoted object
+//! keys...) when editing JSON files that may contain comments.
+//!
+//! The parsed values are regular [`serde_json`] values, so they are always serialized
+//! back to strict JSON that Minecraft can read.
+
+use std::borrow::Cow;
+
+use serde_json::{Map, Number, Value};
+use thiserror::Error;
+
+/// The maximum nesting depth of arrays and objects that the parser accepts. This matches
+/// the default `serde_json` recursion limit, and protects against stack overflows.
+const MAX_NESTING_DEPTH: u8 = 128;
+
+/// Represents a JSON5 syntax error, located at some position of the parsed text.
+#[derive(Error, Debug)]
+#[error("{reason} at line {line} column {column}")]
+pub struct SyntaxError {
+	reason: Cow<'static, str>,
+	line: usize,
+	column: usize
+}
+
+impl SyntaxError {
+	/// Returns the one-based line number where this error happened.
+	#[cfg(test)]
+	pub fn line(&self) -> usize {
+		self.line
+	}
+
+	/// Returns the one-based column number, in characters, where this error happened.
+	#[cfg(test)]
+	pub fn column(&self) -> usize {
+		self.column
+	}
+}
+
+/// Parses the specified UTF-8 encoded JSON5 text to a JSON value.
+pub fn from_slice(src: &[u8]) -> Result<Value, SyntaxError> {
+	let src = match std::str::from_utf8(src) {
+		Ok(src) => src,
+		Err(err) => {
+			// Report the error at the position of the first invalid byte, using the
+			// valid prefix to count lines and columns
+			let valid_src = std::str::from_utf8(&src[..err.valid_up_to()]).unwrap();
+			return Err(Parser {
+				src: valid_src,
+				pos: valid_src.len()
+			}
+			.error("Invalid UTF-8 byte sequence"));
+		}
+	};
+
+	let mut parser = Parser { src, pos: 0 };
+
+	parser.skip_whitespace_and_comments()?;
+	let value = parser.parse_value(0)?;
+	parser.skip_whitespace_and_comments()?;
+
+	if parser.pos < src.len() {
+		Err(parser.error("Trailing characters after the root value"))
+	} else {
+		Ok(value)
+	}
+}
+
+/// A recursive descent JSON5 parser.
+struct Parser<'src> {
+	src: &'src str,
+	pos: usize
+}
+
+impl<'src> Parser<'src> {
+	fn peek(&self) -> Option<char> {
+		self.src[self.pos..].chars().next()
+	}
+
+	fn peek_nth(&self, n: usize) -> Option<char> {
+		self.src[self.pos..].chars().nth(n)
+	}
+
+	fn next_char(&mut self) -> Option<char> {
+		let c = self.peek()?;
+		self.pos += c.len_utf8();
+		Some(c)
+	}
+
+	fn consume_if(&mut self, expected: char) -> bool {
+		let matches = self.peek() == Some(expected);
+		if matches {
+			self.pos += expected.len_utf8();
+		}
+		matches
+	}
+
+	fn consume_literal(&mut self, literal: &str) -> bool {
+		let matches = self.src[self.pos..].starts_with(literal);
+		if matches {
+			self.pos += literal.len();
+		}
+		matches
+	}
+
+	/// Creates a syntax error located at the current position of the parser.
+	fn error(&self, reason: impl Into<Cow<'static, str>>) -> SyntaxError {
+		self.error_at(self.pos, reason)
+	}
+
+	/// Creates a syntax error located at the specified byte offset of the source text.
+	fn error_at(&self, pos: usize, reason: impl Into<Cow<'static, str>>) -> SyntaxError {
+		let preceding_text = &self.src[..pos];
+		let line_start = preceding_text.rfind('\n').map_or(0, |i| i + 1);
+
+		SyntaxError {
+			reason: reason.into(),
+			line: preceding_text.matches('\n').count() + 1,
+			column: preceding_text[line_start..].chars().count() + 1
+		}
+	}
+
+	fn skip_whitespace_and_comments(&mut self) -> Result<(), SyntaxError> {
+		loop {
+			match self.peek() {
+				Some(c) if is_whitespace(c) => {
+					self.pos += c.len_utf8();
+				}
+				Some('/') if self.peek_nth(1) == Some('/') => {
+					while let Some(c) = self.peek() {
+						if is_line_terminator(c) {
+							break;
+						}
+						self.pos += c.len_utf8();
+					}
+				}
+				Some('/') if self.peek_nth(1) == Some('*') => {
+					let comment_start = self.pos;
+					match self.src[self.pos + 2..].find("*/") {
+						Some(comment_length) => self.pos += comment_length + 4,
+						None => {
+							return Err(self.error_at(comment_start, "Unterminated block comment"));
+						}
+					}
+				}
+				_ => return Ok(())
+			}
+		}
+	}
+
+	fn parse_value(&mut self, depth: u8) -> Result<Value, SyntaxError> {
+		match self.peek() {
+			Some('{') => self.parse_object(depth),
+			Some('[') => self.parse_array(depth),
+			Some(quote @ ('"' | '\'')) => {
+				self.pos += 1;
+				self.parse_string(quote).map(Value::String)
+			}
+			Some('0'..='9' | '-' | '+' | '.') => self.parse_number().map(Value::Number),
+			Some(_) if self.consume_literal("true") => Ok(Value::Bool(true)),
+			Some(_) if self.consume_literal("false") => Ok(Value::Bool(false)),
+			Some(_) if self.consume_literal("null") => Ok(Value::Null),
+			Some('I' | 'N') => self.parse_number().map(Value::Number),
+			Some(_) => Err(self.error("Expected a value")),
+			None => Err(self.error("Unexpected end of file while expecting a value"))
+		}
+	}
+
+	fn parse_object(&mut self, depth: u8) -> Result<Value, SyntaxError> {
+		let depth = self.enter_nested_value(depth)?;
+		let mut object = Map::new();
+
+		loop {
+			self.skip_whitespace_and_comments()?;
+			if self.consume_if('}') {
+				return Ok(Value::Object(object));
+			}
+
+			let key = match self.peek() {
+				Some(quote @ ('"' | '\'')) => {
+					self.pos += 1;
+					self.parse_string(quote)?
+				}
+				Some(_) => self.parse_identifier_name()?,
+				None => return Err(self.error("Unexpected end of file while parsing an object"))
+			};
+
+			self.skip_whitespace_and_comments()?;
+			if !self.consume_if(':') {
+				return Err(self.error("Expected ':' after an object key"));
+			}
+
+			self.skip_whitespace_and_comments()?;
+			object.insert(key, self.parse_value(depth)?);
+
+			// Either a comma, which may be trailing, or the end of the object must follow
+			self.skip_whitespace_and_comments()?;
+			if !self.consume_if(',') {
+				return if self.consume_if('}') {
+					Ok(Value::Object(object))
+				} else {
+					Err(self.error("Expected ',' or '}' after an object member"))
+				};
+			}
+		}
+	}
+
+	fn parse_array(&mut self, depth: u8) -> Result<Value, SyntaxError> {
+		let depth = self.enter_nested_value(depth)?;
+		let mut array = vec![];
+
+		loop {
+			self.skip_whitespace_and_comments()?;
+			if self.consume_if(']') {
+				return Ok(Value::Array(array));
+			}
+
+			array.push(self.parse_value(depth)?);
+
+			// Either a comma, which may be trailing, or the end of the array must follow
+			self.skip_whitespace_and_comments()?;
+			if !self.consume_if(',') {
+				return if self.consume_if(']') {
+					Ok(Value::Array(array))
+				} else {
+					Err(self.error("Expected ',' or ']' after an array element"))
+				};
+			}
+		}
+	}
+
+	/// Consumes the opening character of an array or object, checking that the maximum
+	/// nesting depth is not exceeded, and returns the depth of its elements.
+	fn enter_nested_value(&mut self, depth: u8) -> Result<u8, SyntaxError> {
+		if depth >= MAX_NESTING_DEPTH {
+			return Err(self.error("Arrays and objects are nested too deeply"));
+		}
+
+		self.pos += 1;
+		Ok(depth + 1)
+	}
+
+	/// Parses a string whose opening quote was already consumed, up to and including
+	/// its closing quote.
+	fn parse_string(&mut self, quote: char) -> Result<String, SyntaxError> {
+		let mut string = String::new();
+
+		loop {
+			match self.next_char() {
+				Some(c) if c == quote => return Ok(string),
+				Some('\\') => {
+					if let Some(c) = self.parse_escape_sequence()? {
+						string.push(c);
+					}
+				}
+				Some('\n' | '\r') => {
+					return Err(self.error_at(self.pos - 1, "Unterminated string"));
+				}
+				None => return Err(self.error("Unterminated string")),
+				Some(c) => string.push(c)
+			}
+		}
+	}
+
+	/// Parses the escape sequence that follows an already consumed backslash, returning
+	/// the character it represents. Line continuations do not represent any character.
+	fn parse_escape_sequence(&mut self) -> Result<Option<char>, SyntaxError> {
+		let escape_start = self.pos - 1;
+
+		Ok(Some(match self.next_char() {
+			Some('b') => '\u{8}',
+			Some('f') => '\u{c}',
+			Some('n') => '\n',
+			Some('r') => '\r',
+			Some('t') => '\t',
+			Some('v') => '\u{b}',
+			Some('0') if !matches!(self.peek(), Some('0'..='9')) => '\0',
+			Some('x') => {
+				let code_point = self.parse_hex_digits(2, escape_start)?;
+				char::from_u32(code_point).unwrap()
+			}
+			Some('u') => self.parse_unicode_escape_sequence(escape_start)?,
+			Some('\r') => {
+				self.consume_if('\n');
+				return Ok(None);
+			}
+			Some('\n' | '\u{2028}' | '\u{2029}') => return Ok(None),
+			Some('1'..='9' | '0') => {
+				return Err(self.error_at(escape_start, "Octal escape sequences are not allowed"));
+			}
+			Some(c) => c,
+			None => return Err(self.error("Unterminated string"))
+		}))
+	}
+
+	/// Parses the four hexadecimal digits of a `\u` escape sequence whose backslash starts
+	/// at `escape_start`, combining UTF-16 surrogate pairs.
+	fn parse_unicode_escape_sequence(&mut self, escape_start: usize) -> Result<char, SyntaxError> {
+		let code_unit = self.parse_hex_digits(4, escape_start)?;
+
+		if (0xD800..0xDC00).contains(&code_unit) && self.consume_literal("\\u") {
+			let low_surrogate = self.parse_hex_digits(4, escape_start)?;
+			if (0xDC00..0xE000).contains(&low_surrogate) {
+				return Ok(char::from_u32(
+					0x10000 + ((code_unit - 0xD800) << 10) + (low_surrogate - 0xDC00)
+				)
+				.unwrap());
+			}
+		}
+
+		char::from_u32(code_unit).ok_or_else(|| {
+			self.error_at(escape_start, "Unpaired UTF-16 surrogate in escape sequence")
+		})
+	}
+
+	fn parse_hex_digits(&mut self, count: usize, escape_start: usize) -> Result<u32, SyntaxError> {
+		let mut value = 0;
+
+		for _ in 0..count {
+			match self.peek().and_then(|c| c.to_digit(16)) {
+				Some(digit) => {
+					value = value * 16 + digit;
+					self.pos += 1;
+				}
+				None => return Err(self.error_at(escape_start, "Invalid escape sequence"))
+			}
+		}
+
+		Ok(value)
+	}
+
+	/// Parses an ECMAScript identifier name, used as an unquoted object key.
+	fn parse_identifier_name(&mut self) -> Result<String, SyntaxError> {
+		let mut identifier = String::new();
+
+		loop {
+			let c = match self.peek() {
+				Some('\\') => {
+					let escape_start = self.pos;
+					self.pos += 1;
+					if !self.consume_if('u') {
+						return Err(self.error_at(escape_start, "Invalid escape sequence"));
+					}
+					self.parse_unicode_escape_sequence(escape_start)?
+				}
+				Some(c) if is_identifier_part(c, identifier.is_empty()) => {
+					self.pos += c.len_utf8();
+					c
+				}
+				_ if identifier.is_empty() => return Err(self.error("Expected an object key")),
+				_ => return Ok(identifier)
+			};
+
+			identifier.push(c);
+		}
+	}
+
+	fn parse_number(&mut self) -> Result<Number, SyntaxError> {
+		let number_start = self.pos;
+		let mut normalized_number = String::new();
+
+		// JSON does not allow explicit plus signs
+		if !self.consume_if('+') && self.consume_if('-') {
+			normalized_number.push('-');
+		}
+
+		if self.consume_literal("Infinity") || self.consume_literal("NaN") {
+			return Err(self.error_at(
+				number_start,
+				"Infinity and NaN can't be represented in strict JSON"
+			));
+		}
+
+		if matches!(self.peek(), Some('0')) && matches!(self.peek_nth(1), Some('x' | 'X')) {
+			self.pos += 2;
+
+			let digits_start = self.pos;
+			while matches!(self.peek(), Some(c) if c.is_ascii_hexdigit()) {
+				self.pos += 1;
+			}
+
+			let magnitude = u64::from_str_radix(&self.src[digits_start..self.pos], 16)
+				.map_err(|_| self.error_at(number_start, "Invalid hexadecimal number"))?;
+			normalized_number.push_str(&magnitude.to_string());
+		} else {
+			let integer_digits = self.consume_decimal_digits();
+			if integer_digits.is_empty() {
+				// JSON5 allows a leading decimal point, but JSON requires an integer part
+				normalized_number.push('0');
+			} else if integer_digits.len() > 1 && integer_digits.starts_with('0') {
+				return Err(self.error_at(number_start, "Numbers can't have leading zeros"));
+			}
+			normalized_number.push_str(integer_digits);
+
+			if self.consume_if('.') {
+				// JSON5 allows a trailing decimal point, but JSON requires fractional digits
+				// after it
+				let fractional_digits = self.consume_decimal_digits();
+				if integer_digits.is_empty() && fractional_digits.is_empty() {
+					return Err(self.error_at(number_start, "Invalid number"));
+				}
+				if !fractional_digits.is_empty() {
+					normalized_number.push('.');
+					normalized_number.push_str(fractional_digits);
+				}
+			} else if integer_digits.is_empty() {
+				return Err(self.error_at(number_start, "Invalid number"));
+			}
+
+			if let Some(c @ ('e' | 'E')) = self.peek() {
+				self.pos += 1;
+				normalized_number.push(c);
+
+				if let Some(c @ ('+' | '-')) = self.peek() {
+					self.pos += 1;
+					normalized_number.push(c);
+				}
+
+				let exponent_digits = self.consume_decimal_digits();
+				if exponent_digits.is_empty() {
+					return Err(self.error_at(number_start, "Invalid number exponent"));
+				}
+				normalized_number.push_str(exponent_digits);
+			}
+		}
+
+		// Numbers must be delimited from whatever comes after them
+		if matches!(self.peek(), Some(c) if is_identifier_part(c, false)) {
+			return Err(self.error_at(number_start, "Invalid number"));
+		}
+
+		// Let serde_json convert the now strict JSON number literal to its number representation
+		serde_json::from_str(&normalized_number)
+			.map_err(|_| self.error_at(number_start, "Number out of range"))
+	}
+
+	fn consume_decimal_digits(&mut self) -> &'src str {
+		let digits_start = self.pos;
+		while matches!(self.peek(), Some('0'..='9')) {
+			self.pos += 1;
+		}
+		let src = self.src;
+		&src[digits_start..self.pos]
+	}
+}
+
+/// Checks whether a character is considered white space by JSON5. Any Unicode space
+/// separator is white space, and `char::is_whitespace` covers all of them.
+fn is_whitespace(c: char) -> bool {
+	c.is_whitespace() || c == '\u{feff}'
+}
+
+fn is_line_terminator(c: char) -> bool {
+	matches!(c, '\n' | '\r' | '\u{2028}' | '\u{2029}')
+}
+
+/// Checks whether a character may appear in an ECMAScript identifier name. This is a
+/// practical approximation of the Unicode identifier classes referenced by the spec.
+fn is_identifier_part(c: char, is_start: bool) -> bool {
+	c == '$'
+		|| c == '_'
+		|| c.is_alphabetic()
+		|| (!is_start && (c.is_alphanumeric() || c == '\u{200c}' || c == '\u{200d}'))
+}
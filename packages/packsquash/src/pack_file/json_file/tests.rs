@@ -1 +1,255 @@
+use pretty_assertions::assert_eq;
+use tokio_stream::StreamExt;
+use tokio_test::io::Builder;
 
+use super::*;
+
+/// Processes the given input data as a [JsonFile], using the provided settings,
+/// and returns the result.
+async fn process_test(
+	input_data: &[u8],
+	settings: JsonFileOptions,
+	asset_type: PackFileAssetType
+) -> Result<Vec<u8>, OptimizationError> {
+	process_test_with_fallback_translations(input_data, settings, asset_type, None).await
+}
+
+/// Like [process_test], but also using the specified fallback translations.
+async fn process_test_with_fallback_translations(
+	input_data: &[u8],
+	settings: JsonFileOptions,
+	asset_type: PackFileAssetType,
+	fallback_translations: Option<Arc<Translations>>
+) -> Result<Vec<u8>, OptimizationError> {
+	let data_stream = JsonFile {
+		read: Builder::new().read(input_data).build(),
+		file_length_hint: input_data.len(),
+		asset_type,
+		optimization_settings: settings,
+		fallback_translations,
+		block_state_properties: None,
+		animation_frame_deduplication: None,
+		texture_downscaling: None,
+		may_be_directory_listed_atlas_texture_sprite: false,
+		depends_on_other_pack_files: false
+	}
+	.process();
+
+	let mut data = Vec::with_capacity(input_data.len());
+	for result in data_stream.collect::<Vec<_>>().await {
+		data.extend_from_slice(&result?.1);
+	}
+
+	Ok(data)
+}
+
+#[tokio::test]
+async fn json5_syntax_is_converted_to_strict_json() {
+	let processed_data = process_test(
+		b"// Hand-written model\n\
+		{\n\
+			parent: 'block/cube_all',\n\
+			textures: { all: \"block/stone\", },\n\
+			'elements': [ { from: [+0, .5, 0x10], to: [16., 16, 16e0] }, ],\n\
+			/* A \"quoted\" note */ comment: 'It\\'s \\x41 \\\n multi-line string',\n\
+		}",
+		JsonFileOptions {
+			delete_bloat: false,
+			..Default::default()
+		},
+		PackFileAssetType::MinecraftModelWithComments
+	)
+	.await
+	.expect("No error should happen while processing valid JSON5");
+
+	assert_eq!(
+		serde_json::from_slice::<Value>(&processed_data)
+			.expect("The processed file should be strict JSON"),
+		serde_json::json!({
+			"parent": "block/cube_all",
+			"textures": { "all": "block/stone" },
+			"elements": [ { "from": [0, 0.5, 16], "to": [16, 16, 16.0] } ],
+			"comment": "It's A  multi-line string"
+		})
+	);
+}
+
+#[tokio::test]
+async fn json5_syntax_is_rejected_in_files_without_comments_extension() {
+	process_test(
+		b"{ parent: 'block/cube_all', }",
+		JsonFileOptions::default(),
+		PackFileAssetType::MinecraftModel
+	)
+	.await
+	.expect_err("JSON5 syntax should not be accepted in strict JSON files");
+}
+
+#[tokio::test]
+async fn json5_syntax_errors_are_located() {
+	let error = process_test(
+		b"{\n  a: 1,\n  b: 'c' 'd'\n}",
+		JsonFileOptions::default(),
+		PackFileAssetType::GenericJsonWithComments
+	)
+	.await
+	.expect_err("Invalid JSON5 should not be accepted");
+
+	match error {
+		OptimizationError::Json5Syntax(error) => {
+			assert_eq!((error.line(), error.column()), (3, 10));
+		}
+		error => panic!("Unexpected error: {error}")
+	}
+}
+
+#[tokio::test]
+async fn json5_non_finite_numbers_are_rejected() {
+	process_test(
+		b"[1, -Infinity, NaN]",
+		JsonFileOptions::default(),
+		PackFileAssetType::GenericJsonWithComments
+	)
+	.await
+	.expect_err("Non-finite numbers can't be represented in strict JSON");
+}
+
+#[tokio::test]
+async fn translations_equal_to_fallback_ones_are_deleted() {
+	let fallback_translations = Translations::from_iter([
+		("item.mypack.gem".into(), "Gem".into()),
+		("item.mypack.ruby".into(), "Ruby".into()),
+		("item.mypack.amount".into(), "1".into())
+	]);
+
+	let processed_data = process_test_with_fallback_translations(
+		r#"{
+			"item.mypack.gem": "Gem",
+			"item.mypack.ruby": "Rubí",
+			"item.mypack.amount": 1,
+			"item.mypack.pearl": "Perla"
+		}"#
+		.as_bytes(),
+		JsonFileOptions {
+			delete_redundant_translations: true,
+			..Default::default()
+		},
+		PackFileAssetType::MinecraftLanguage,
+		Some(Arc::new(fallback_translations))
+	)
+	.await
+	.expect("No error should happen while processing a valid language file");
+
+	assert_eq!(
+		serde_json::from_slice::<Value>(&processed_data)
+			.expect("The processed file should be strict JSON"),
+		serde_json::json!({
+			"item.mypack.ruby": "Rubí",
+			"item.mypack.amount": 1,
+			"item.mypack.pearl": "Perla"
+		})
+	);
+}
+
+#[tokio::test]
+async fn blockstate_variants_are_simplified() {
+	let processed_data = process_test(
+		br#"{
+			"variants": {
+				"facing=north": { "model": "block/furnace", "y": 0, "uvlock": false, "weight": 1 },
+				"facing=east": [
+					{ "model": "block/furnace", "y": 90 },
+					{ "model": "block/furnace_alt", "y": 90, "weight": 2 },
+					{ "model": "block/furnace", "y": 90.0, "weight": 3.0 }
+				],
+				"facing=south": [
+					{ "model": "block/furnace", "x": 0, "y": 180 },
+					{ "model": "block/furnace", "y": 180, "weight": 1 }
+				]
+			}
+		}"#,
+		JsonFileOptions::default(),
+		PackFileAssetType::MinecraftBlockState
+	)
+	.await
+	.expect("No error should happen while processing a valid blockstate");
+
+	assert_eq!(
+		serde_json::from_slice::<Value>(&processed_data)
+			.expect("The processed file should be strict JSON"),
+		serde_json::json!({
+			"variants": {
+				"facing=north": { "model": "block/furnace" },
+				"facing=east": [
+					{ "model": "block/furnace", "y": 90, "weight": 4 },
+					{ "model": "block/furnace_alt", "y": 90, "weight": 2 }
+				],
+				"facing=south": { "model": "block/furnace", "y": 180 }
+			}
+		})
+	);
+}
+
+#[tokio::test]
+async fn invalid_blockstates_are_rejected() {
+	process_test(
+		br#"{ "variants": { "": { "model": "block/stone", "y": 45 } } }"#,
+		JsonFileOptions::default(),
+		PackFileAssetType::MinecraftBlockState
+	)
+	.await
+	.expect_err("Invalid variant rotations should be rejected");
+}
+
+#[tokio::test]
+async fn blockstates_are_only_validated_when_debloated() {
+	process_test(
+		br#"{ "variants": { "": { "model": "block/stone", "y": 45 } } }"#,
+		JsonFileOptions {
+			delete_bloat: false,
+			..Default::default()
+		},
+		PackFileAssetType::MinecraftBlockState
+	)
+	.await
+	.expect("Blockstates should not be validated when not debloating them");
+}
+
+#[tokio::test]
+async fn forge_blockstates_are_not_validated() {
+	let input_data = br#"{
+		"forge_marker": 1,
+		"defaults": { "model": "mymod:block/machine", "textures": { "all": "mymod:block/machine" } },
+		"variants": { "facing": { "north": {}, "east": { "y": 90 } }, "inventory": [{}] }
+	}"#;
+
+	let processed_data = process_test(
+		input_data,
+		JsonFileOptions::default(),
+		PackFileAssetType::MinecraftBlockState
+	)
+	.await
+	.expect("Forge blockstates should be accepted");
+
+	assert_eq!(
+		serde_json::from_slice::<Value>(&processed_data)
+			.expect("The processed file should be strict JSON"),
+		serde_json::from_slice::<Value>(input_data).unwrap()
+	);
+}
+
+#[test]
+fn unknown_blockstate_properties_are_rejected() {
+	let mut blockstate = serde_json::json!({
+		"multipart": [
+			{ "when": { "OR": [ { "north": "true" }, { "up": "true" } ] }, "apply": { "model": "block/wall_side" } }
+		]
+	});
+
+	match blockstate::validate_and_simplify(&mut blockstate, Some(&["north".into(), "east".into()])) {
+		Err(OptimizationError::UnknownBlockStateProperty { property, .. }) => {
+			assert_eq!(property, "up");
+		}
+		result => panic!("Unexpected result: {result:?}")
+	}
+}
@@ -13,7 +13,7 @@ use crate::pack_file::asset_type::PackFileAssetType;
 use crate::pack_file::util::{prepare_line_for_output, LineNumber, MarkLastDecorator, BOM};
 use crate::pack_file::AsyncReadAndSizeHint;
 
-use super::{OptimizedBytesChunk, PackFile, PackFileConstructor};
+use super::{OptimizedBytesChunk, PackFile, PackFileConstructor, PackFileContext};
 
 #[cfg(test)]
 mod tests;
@@ -125,7 +125,8 @@ impl<T: AsyncRead + Send + Unpin + 'static> PackFileConstructor<T> for LegacyLan
 	fn new(
 		file_read_producer: impl FnOnce() -> Option<AsyncReadAndSizeHint<T>>,
 		_: PackFileAssetType,
-		optimization_settings: Self::OptimizationSettings
+		optimization_settings: Self::OptimizationSettings,
+		_: &PackFileContext<'_>
 	) -> Option<Self> {
 		file_read_producer().map(|(read, _)| Self {
 			read,
@@ -6,7 +6,9 @@ use bytes::BytesMut;
 use tokio::io::AsyncRead;
 use tokio_util::codec::{Decoder, FramedRead};
 
-use super::{AsyncReadAndSizeHint, PackFile, PackFileAssetType, PackFileConstructor};
+use super::{
+	AsyncReadAndSizeHint, PackFile, PackFileAssetType, PackFileConstructor, PackFileContext
+};
 
 #[cfg(test)]
 mod tests;
@@ -69,7 +71,8 @@ impl<T: AsyncRead + Send + Unpin + 'static> PackFileConstructor<T> for Passthrou
 	fn new(
 		file_read_producer: impl FnOnce() -> Option<AsyncReadAndSizeHint<T>>,
 		asset_type: PackFileAssetType,
-		_: Self::OptimizationSettings
+		_: Self::OptimizationSettings,
+		_: &PackFileContext<'_>
 	) -> Option<Self> {
 		match asset_type {
 			PackFileAssetType::TrueTypeFont => file_read_producer().map(|(read, _)| Self {
@@ -1,15 +1,20 @@
 //! Contains code to optimize PNG files.
 
 use std::borrow::Cow;
+use std::num::NonZeroU16;
+use std::sync::Arc;
 
 use bytes::BytesMut;
 use thiserror::Error;
 use tokio::io::AsyncRead;
 use tokio_util::codec::{Decoder, FramedRead};
 
-use crate::config::PngFileOptions;
+use crate::config::{AlphaBinarization, PngFileOptions};
+use crate::pack_index::{AnimationFrameDeduplication, TextureDownscaling};
 
-use super::{AsyncReadAndSizeHint, PackFile, PackFileAssetType, PackFileConstructor};
+use super::{
+	AsyncReadAndSizeHint, PackFile, PackFileAssetType, PackFileConstructor, PackFileContext
+};
 
 use image_processor::{ImageProcessingError, ProcessedImage};
 
@@ -25,13 +30,18 @@ pub struct PngFile<T: AsyncRead + Send + Unpin + 'static> {
 	read: T,
 	file_length_hint: usize,
 	asset_type: PackFileAssetType,
-	optimization_settings: PngFileOptions
+	optimization_settings: PngFileOptions,
+	animation_frame_deduplication: Option<Arc<AnimationFrameDeduplication>>,
+	texture_downscaling: Option<TextureDownscaling>,
+	depends_on_other_pack_files: bool
 }
 
 /// Optimizer decoder that transforms PNG files to an optimized representation.
 pub struct OptimizerDecoder {
 	asset_type: PackFileAssetType,
 	optimization_settings: PngFileOptions,
+	animation_frame_deduplication: Option<Arc<AnimationFrameDeduplication>>,
+	texture_downscaling: Option<TextureDownscaling>,
 	reached_eof: bool
 }
 
@@ -41,6 +51,11 @@ pub struct OptimizerDecoder {
 pub enum OptimizationError {
 	#[error("{0}")]
 	OptimizationError(#[from] ImageProcessingError),
+	#[error("The color quantized texture has a SSIM of {structural_similarity:.4}, below the minimum of {minimum_structural_similarity}. Try tweaking color quantization options")]
+	InsufficientQuality {
+		structural_similarity: f64,
+		minimum_structural_similarity: f32
+	},
 	#[error("I/O error: {0}")]
 	Io(#[from] std::io::Error)
 }
@@ -87,12 +102,23 @@ impl Decoder for OptimizerDecoder {
 			// These textures may be used to pass data to shaders: their RGB values may
 			// be arbitrarily used for computation. Leave them untouched
 			PackFileAssetType::AuxiliaryShaderTargetTexture => false,
+			// The alpha channel of material maps encodes data such as height or emissiveness,
+			// so fully transparent pixels still have meaningful colors
+			PackFileAssetType::PbrNormalMap
+			| PackFileAssetType::PbrSpecularMap
+			| PackFileAssetType::PbrEmissiveMap => false,
 			_ => !self.optimization_settings.skip_alpha_optimizations
 		};
 		let is_auxiliary_shader_target_texture = matches!(
 			self.asset_type,
 			PackFileAssetType::AuxiliaryShaderTargetTexture
 		);
+		let is_material_map = matches!(
+			self.asset_type,
+			PackFileAssetType::PbrNormalMap
+				| PackFileAssetType::PbrSpecularMap
+				| PackFileAssetType::PbrEmissiveMap
+		);
 		let color_quantization_target = self.optimization_settings.color_quantization_target;
 
 		// First pass: strip non-critical PNG chunks we won't use. At worst this does nothing
@@ -106,6 +132,105 @@ impl Decoder for OptimizerDecoder {
 		)?;
 		let mut first_pass_image = ProcessedImage::read(&*first_pass_png)?;
 
+		// Textures that carry exact data must stay bit-exact, so only recompress them losslessly,
+		// without decoding their pixels to RGBA8 and changing their color type
+		if matches!(self.asset_type, PackFileAssetType::DataTexture)
+			|| self.optimization_settings.preserve_exact_pixel_data
+		{
+			return Ok(Some((
+				Cow::Borrowed("Optimized losslessly, keeping exact pixel data"),
+				image_processor::recompress_losslessly(
+					&first_pass_png,
+					first_pass_image.width().get() as u32 * first_pass_image.height().get() as u32,
+					self.optimization_settings.image_data_compression_iterations
+				)?
+			)));
+		}
+
+		// Decode images with 16 bits per channel with full precision, so that their depth is
+		// reduced to the 8 bits per channel Minecraft uses as configured, instead of truncated
+		let sixteen_bit_depth_reduced = first_pass_image.reduce_sixteen_bit_depth(
+			&first_pass_png,
+			self.optimization_settings.sixteen_bit_depth_reduction
+		)?;
+
+		// Remove duplicate animation frames, which the animation metadata of the texture is
+		// rewritten for. The resulting image is used as the first pass image from now on
+		let removed_animation_frame_count = match &self.animation_frame_deduplication {
+			Some(deduplication) => {
+				if let Some(deduplicated_image) =
+					first_pass_image.deduplicate_animation_frames(deduplication)?
+				{
+					first_pass_image = deduplicated_image;
+				}
+
+				deduplication.removed_frame_count()
+			}
+			None => 0
+		};
+
+		// Downscale the texture if it exceeds the target resolution. Textures made up of cells,
+		// such as animation frames or font glyphs, have a downscaling planned with knowledge of
+		// other pack files. The remaining textures are treated as a single cell. Material maps
+		// are not downscaled, because resampling does not preserve the data they encode, and
+		// neither are the textures they belong to, which were excluded beforehand. Pack icons
+		// may be resized to fit in a resolution of their own instead
+		let is_pack_icon = matches!(self.asset_type, PackFileAssetType::PackIcon);
+		let texture_downscaling = match self.optimization_settings.pack_icon_resolution {
+			Some(pack_icon_resolution) if is_pack_icon => Some(TextureDownscaling::new(
+				first_pass_image.width().get() as u32,
+				first_pass_image.height().get() as u32,
+				pack_icon_resolution
+			)),
+			_ => self
+				.optimization_settings
+				.downscaling_target_resolution
+				.filter(|_| !is_auxiliary_shader_target_texture && !is_material_map)
+				.map(|target_resolution| {
+					self.texture_downscaling.unwrap_or_else(|| {
+						TextureDownscaling::new(
+							first_pass_image.width().get() as u32,
+							first_pass_image.height().get() as u32,
+							target_resolution
+						)
+					})
+				})
+		}
+		.filter(TextureDownscaling::downscales);
+		let downscaled = match texture_downscaling {
+			Some(texture_downscaling) => {
+				if let Some(downscaled_image) = first_pass_image.downscale(
+					&texture_downscaling,
+					self.optimization_settings.downscaling_filter
+				)? {
+					first_pass_image = downscaled_image;
+				}
+
+				true
+			}
+			None => false
+		};
+
+		// Make pack icons opaque by compositing them over a background color, if configured
+		let alpha_flattened = match self.optimization_settings.pack_icon_background_color {
+			Some(background_color) if is_pack_icon => {
+				first_pass_image.flatten_alpha(background_color.into())?
+			}
+			_ => false
+		};
+
+		// Snap the alpha of textures that are rendered in a way that ignores partial transparency
+		let binarize_alpha = match self.optimization_settings.alpha_binarization {
+			AlphaBinarization::Never => false,
+			AlphaBinarization::CutoutTextures => {
+				matches!(self.asset_type, PackFileAssetType::CutoutTexture)
+			}
+			AlphaBinarization::Always => !is_material_map
+		};
+		let alpha_binarized = binarize_alpha
+			&& first_pass_image
+				.binarize_alpha(self.optimization_settings.alpha_binarization_threshold)?;
+
 		// Second pass: downsize most textures that consist of a single color to the minimum
 		// size that does not cause side effects in Minecraft. If that can't be done, then
 		// perform quantization if desired and useful (i.e., there are more pixels than
@@ -117,8 +242,10 @@ impl Decoder for OptimizerDecoder {
 		let second_pass_image = match (can_change_color_type
 			&& self.optimization_settings.downsize_if_single_color)
 			.then(|| {
-				first_pass_image
-					.downsize_single_color(can_change_color_type, is_auxiliary_shader_target_texture)
+				first_pass_image.downsize_single_color(
+					can_change_color_type,
+					is_auxiliary_shader_target_texture || is_material_map
+				)
 			})
 			.transpose()?
 			.flatten()
@@ -136,7 +263,9 @@ impl Decoder for OptimizerDecoder {
 					color_quantization_target,
 					self.optimization_settings
 						.color_quantization_dithering_level
-						.into()
+						.into(),
+					self.optimization_settings
+						.color_quantization_minimum_quality
 				)?
 			}
 			// No downsizing or quantization is appropriate
@@ -146,6 +275,11 @@ impl Decoder for OptimizerDecoder {
 		let quantization_quality = second_pass_image
 			.as_ref()
 			.and_then(|image| image.quantization_quality());
+		let quantization_quality_metrics = second_pass_image
+			.as_ref()
+			.map(|image| image.quantization_quality_metrics(&mut first_pass_image))
+			.transpose()?
+			.flatten();
 		let have_second_pass_result = second_pass_image.is_some();
 		let must_use_second_pass_result =
 			color_quantization_target.is_quantization_required() && quantization_quality.is_some();
@@ -195,33 +329,98 @@ impl Decoder for OptimizerDecoder {
 		// the user. While this has the desired properties of never increasing the input PNG file
 		// size when quantization is not forced and executing each pass a single time, explicit
 		// user configuration of the quantization parameters may be needed to achieve the most
-		// optimal PNG we are capable of. Luckily, the points above are fairly rare
-		let (optimized_png, optimization_strategy_message) =
-			if !must_use_second_pass_result && first_pass_png.len() < third_pass_png.len() {
-				(
-					first_pass_png,
-					Cow::Borrowed(
-						"Barely optimized. \
+		// optimal PNG we are capable of. Luckily, the points above are fairly rare.
+		//
+		// The first pass PNG still has any duplicate animation frames, which the rewritten
+		// animation metadata does not expect, so it can't be returned if there were some. The
+		// same goes for downscaled textures, which are expected to be downscaled, and for
+		// pack icons whose alpha was explicitly requested to be flattened
+		let optimized_png_is_first_pass_png = !must_use_second_pass_result
+			&& removed_animation_frame_count == 0
+			&& !downscaled
+			&& !alpha_flattened
+			&& first_pass_png.len() < third_pass_png.len();
+		let (optimized_png, optimization_strategy_message) = if optimized_png_is_first_pass_png {
+			(
+				first_pass_png,
+				Cow::Borrowed(
+					"Barely optimized. \
 						If not optimized externally, try tweaking options for extra savings"
-					)
 				)
-			} else {
-				(
-					third_pass_png,
-					if let Some(quantization_quality) = quantization_quality {
-						Cow::Owned(format!(
-							"Optimized with {quantization_quality}% quality color quantization"
-						))
-					} else if have_second_pass_result {
-						Cow::Borrowed("Downsized and optimized")
-					} else if can_change_transparent_pixel_colors {
-						Cow::Borrowed("Optimized with no visible color loss")
-					} else {
-						Cow::Borrowed("Optimized")
+			)
+		} else {
+			(
+				third_pass_png,
+				if let Some(quantization_quality) = quantization_quality {
+					// Lossy results must meet the configured quality bar
+					if let Some((metrics, minimum_structural_similarity)) =
+						quantization_quality_metrics
+							.zip(self.optimization_settings.minimum_structural_similarity)
+					{
+						if metrics.structural_similarity
+							< f32::from(minimum_structural_similarity) as f64
+						{
+							return Err(OptimizationError::InsufficientQuality {
+								structural_similarity: metrics.structural_similarity,
+								minimum_structural_similarity: minimum_structural_similarity.into()
+							});
+						}
 					}
-				)
+
+					Cow::Owned(match quantization_quality_metrics {
+						Some(metrics) => format!(
+							"Optimized with {quantization_quality}% quality color quantization ({metrics})"
+						),
+						None => format!(
+							"Optimized with {quantization_quality}% quality color quantization"
+						)
+					})
+				} else if have_second_pass_result {
+					Cow::Borrowed("Downsized and optimized")
+				} else if can_change_transparent_pixel_colors {
+					Cow::Borrowed("Optimized with no visible color loss")
+				} else {
+					Cow::Borrowed("Optimized")
+				}
+			)
+		};
+
+		let optimization_strategy_message = if removed_animation_frame_count > 0 {
+			Cow::Owned(format!(
+				"{optimization_strategy_message}, removed {removed_animation_frame_count} \
+				duplicate animation frames"
+			))
+		} else {
+			optimization_strategy_message
+		};
+
+		let optimization_strategy_message = if downscaled {
+			Cow::Owned(format!("{optimization_strategy_message}, downscaled"))
+		} else {
+			optimization_strategy_message
+		};
+
+		let optimization_strategy_message = if alpha_flattened {
+			Cow::Owned(format!("{optimization_strategy_message}, flattened alpha"))
+		} else {
+			optimization_strategy_message
+		};
+
+		let optimization_strategy_message =
+			if sixteen_bit_depth_reduced && !optimized_png_is_first_pass_png {
+				Cow::Owned(format!(
+					"{optimization_strategy_message}, reduced 16-bit depth"
+				))
+			} else {
+				optimization_strategy_message
 			};
 
+		let optimization_strategy_message = if alpha_binarized && !optimized_png_is_first_pass_png {
+			Cow::Owned(format!("{optimization_strategy_message}, binarized alpha"))
+		} else {
+			optimization_strategy_message
+		};
+
 		Ok(Some((optimization_strategy_message, optimized_png)))
 	}
 }
@@ -237,6 +436,8 @@ impl<T: AsyncRead + Send + Unpin + 'static> PackFile for PngFile<T> {
 			OptimizerDecoder {
 				asset_type: self.asset_type,
 				optimization_settings: self.optimization_settings,
+				animation_frame_deduplication: self.animation_frame_deduplication,
+				texture_downscaling: self.texture_downscaling,
 				reached_eof: false
 			},
 			self.file_length_hint
@@ -251,6 +452,10 @@ impl<T: AsyncRead + Send + Unpin + 'static> PackFile for PngFile<T> {
 		self.optimization_settings
 			.may_be_directory_listed_atlas_sprite
 	}
+
+	fn depends_on_other_pack_files(&self) -> bool {
+		self.depends_on_other_pack_files
+	}
 }
 
 impl<T: AsyncRead + Send + Unpin + 'static> PackFileConstructor<T> for PngFile<T> {
@@ -259,14 +464,62 @@ impl<T: AsyncRead + Send + Unpin + 'static> PackFileConstructor<T> for PngFile<T
 	fn new(
 		file_read_producer: impl FnOnce() -> Option<AsyncReadAndSizeHint<T>>,
 		asset_type: PackFileAssetType,
-		optimization_settings: Self::OptimizationSettings
+		mut optimization_settings: Self::OptimizationSettings,
+		pack_file_context: &PackFileContext<'_>
 	) -> Option<Self> {
+		// Atlas definitions may tell us that this texture is not directory-listed after all
+		optimization_settings.may_be_directory_listed_atlas_sprite &= pack_file_context
+			.pack_index
+			.may_be_directory_listed_atlas_sprite(pack_file_context.relative_path.as_str());
+
+		// Whether a texture is downscaled depends on other pack files, such as its material maps
+		let depends_on_other_pack_files = optimization_settings
+			.downscaling_target_resolution
+			.is_some()
+			|| pack_file_context
+				.pack_index
+				.is_interdependent_with_metadata(pack_file_context.relative_path.as_str());
+
+		// Textures must keep the size of their material maps, which are never downscaled
+		if pack_file_context
+			.pack_index
+			.has_material_maps(pack_file_context.relative_path.as_str())
+		{
+			optimization_settings.downscaling_target_resolution = None;
+		}
+
+		let animation_frame_deduplication = pack_file_context
+			.pack_index
+			.animation_frame_deduplication(pack_file_context.relative_path.as_str())
+			.cloned();
+
+		let texture_downscaling = pack_file_context
+			.pack_index
+			.texture_downscaling(pack_file_context.relative_path.as_str());
+
 		file_read_producer().map(|(read, file_length_hint)| Self {
 			read,
 			// The file is too big to fit in memory if this conversion fails anyway
 			file_length_hint: file_length_hint.try_into().unwrap_or(usize::MAX),
 			asset_type,
-			optimization_settings
+			optimization_settings,
+			animation_frame_deduplication,
+			texture_downscaling,
+			depends_on_other_pack_files
 		})
 	}
 }
+
+/// Decodes the pixels of the specified PNG file to RGBA8 format, in the same way they are
+/// decoded when optimizing it, and returns its width, height and pixel data.
+pub fn decode_png_pixels(
+	png_data: BytesMut,
+	maximum_width_and_height: NonZeroU16
+) -> Result<(u32, u32, Vec<u8>), OptimizationError> {
+	let stripped_png = image_processor::strip_unnecessary_chunks(png_data, maximum_width_and_height)?;
+	let image = ProcessedImage::read(&*stripped_png)?;
+	let (width, height) = (image.width().get() as u32, image.height().get() as u32);
+
+	// Images that were just read are never color quantized
+	Ok((width, height, image.into_rgba8_buf()?.unwrap()))
+}
@@ -1,23 +1,32 @@
 //! Contains routines and data types for processing single images.
 
-use crate::config::ColorQuantizationTarget;
+use crate::config::{ColorQuantizationTarget, DownscalingFilter, SixteenBitDepthReduction};
+use crate::pack_index::{AnimationFrameDeduplication, TextureDownscaling};
 use crate::zopfli_iterations_time_model::ZopfliIterationsTimeModel;
 use bytes::BytesMut;
 use imagequant::{liq_error, Attributes};
 use itertools::Itertools;
 use oxipng::{indexset, BitDepth, ColorType, Deflaters, Options, RowFilter, StripChunks};
-use rgb::{AsPixels, RGBA8};
-use spng::{ContextFlags, DecodeFlags, Format};
+use rgb::{AsPixels, RGB8, RGBA8};
+use spng::{BitDepth as PngBitDepth, ContextFlags, DecodeFlags, Format};
 use std::io::Read;
 use std::num::{NonZeroU16, NonZeroU8};
 use std::time::Duration;
 use std::{cmp, iter};
 use thiserror::Error;
 
+mod depth_reducer;
+mod quality_metrics;
+mod resampler;
+
+pub use quality_metrics::ImageQualityMetrics;
+
 #[derive(Error, Debug)]
 pub enum ImageProcessingError {
 	#[error("Invalid PNG: {0}")]
 	StripValidateError(&'static str),
+	#[error("The texture dimensions changed while it was being processed")]
+	ChangedDimensions,
 	#[error("PNG decode error: {0}")]
 	PngDecoding(#[from] spng::Error),
 	#[error("OxiPNG optimization error: {0}")]
@@ -26,6 +35,10 @@ pub enum ImageProcessingError {
 	LiqError(#[from] liq_error)
 }
 
+/// The palette sizes that are tried when quantizing colors with a minimum quality, from
+/// the biggest to the smallest. These match the bit depths indexed PNG images can have.
+const QUANTIZATION_PALETTE_SIZES: &[u32] = &[256, 16, 4, 2];
+
 /// Performs a first fast optimization to an input PNG image: remove non-critical chunks
 /// that will not be parsed by the expected downstream decoders. This can never increase
 /// the input PNG image size, only decrease or maintain it.
@@ -257,6 +270,66 @@ impl<R: Read> ProcessedImage<R> {
 		})
 	}
 
+	/// Consumes this image and returns its pixels, in RGBA8 format and as a byte buffer.
+	///
+	/// `Ok(None)` is returned if this image was quantized to a color palette.
+	pub fn into_rgba8_buf(self) -> Result<Option<Vec<u8>>, ImageProcessingError> {
+		Ok(self.into_pixel_array()?.map(PixelArray::into_byte_buf))
+	}
+
+	/// Removes the duplicate frames of this animated image according to the specified
+	/// frame deduplication, and returns a new image with the result.
+	///
+	/// Returns `Ok(None)` if the image was color quantized.
+	pub fn deduplicate_animation_frames(
+		&mut self,
+		deduplication: &AnimationFrameDeduplication
+	) -> Result<Option<Self>, ImageProcessingError> {
+		let width = self.width();
+
+		self.as_pixel_array()?
+			.map(|pixel_array| {
+				let (height, buf) = deduplication
+					.deduplicate_frames(width.get() as u32, &pixel_array.buf)
+					.ok_or(ImageProcessingError::ChangedDimensions)?;
+
+				Ok(Self::from(PixelArray {
+					width,
+					// Removing frames can't make the image taller
+					height: NonZeroU16::new(height as u16).unwrap(),
+					buf
+				}))
+			})
+			.transpose()
+	}
+
+	/// Downscales this image according to the specified texture downscaling, resampling
+	/// each of its cells with the specified filter, and returns a new image with the result.
+	///
+	/// Returns `Ok(None)` if the image was color quantized.
+	pub fn downscale(
+		&mut self,
+		downscaling: &TextureDownscaling,
+		filter: DownscalingFilter
+	) -> Result<Option<Self>, ImageProcessingError> {
+		let width = self.width();
+
+		self.as_pixel_array()?
+			.map(|pixel_array| {
+				let (width, height, buf) =
+					resampler::downscale(&pixel_array.buf, width.get() as u32, downscaling, filter)
+						.ok_or(ImageProcessingError::ChangedDimensions)?;
+
+				Ok(Self::from(PixelArray {
+					// Downscaling can't make the image bigger, and keeps dimensions above zero
+					width: NonZeroU16::new(width as u16).unwrap(),
+					height: NonZeroU16::new(height as u16).unwrap(),
+					buf
+				}))
+			})
+			.transpose()
+	}
+
 	/// Returns an estimation of the visual image quality after performing color
 	/// quantization, in the `0-100` range.
 	///
@@ -273,25 +346,164 @@ impl<R: Read> ProcessedImage<R> {
 		}
 	}
 
+	/// Snaps the alpha value of every pixel of this image to fully transparent if it is below
+	/// the specified threshold, or to fully opaque otherwise. Returns whether the alpha value
+	/// of some pixel was changed.
+	///
+	/// Returns `Ok(false)` if the image was color quantized.
+	pub fn binarize_alpha(&mut self, threshold: u8) -> Result<bool, ImageProcessingError> {
+		let Some(pixel_array) = self.as_pixel_array()? else {
+			return Ok(false);
+		};
+
+		let mut alpha_changed = false;
+		for pixel in pixel_array.buf.as_pixels_mut() {
+			let binarized_alpha = if pixel.a < threshold { 0 } else { u8::MAX };
+
+			alpha_changed |= pixel.a != binarized_alpha;
+			pixel.a = binarized_alpha;
+		}
+
+		Ok(alpha_changed)
+	}
+
+	/// Composites this image over the specified background color, making every pixel fully
+	/// opaque. Returns whether the color of some pixel was changed.
+	///
+	/// Returns `Ok(false)` if the image was color quantized.
+	pub fn flatten_alpha(&mut self, background_color: RGB8) -> Result<bool, ImageProcessingError> {
+		let Some(pixel_array) = self.as_pixel_array()? else {
+			return Ok(false);
+		};
+
+		let mut pixels_changed = false;
+		for pixel in pixel_array.buf.as_pixels_mut() {
+			let alpha = pixel.a as u32;
+			let composite = |channel: u8, background_channel: u8| {
+				((channel as u32 * alpha + background_channel as u32 * (255 - alpha) + 127) / 255)
+					as u8
+			};
+			let flattened_pixel = RGBA8::new(
+				composite(pixel.r, background_color.r),
+				composite(pixel.g, background_color.g),
+				composite(pixel.b, background_color.b),
+				u8::MAX
+			);
+
+			pixels_changed |= *pixel != flattened_pixel;
+			*pixel = flattened_pixel;
+		}
+
+		Ok(pixels_changed)
+	}
+
+	/// Reduces the depth of this image to 8 bits per channel with the specified method, if
+	/// it has 16 bits per channel and was not decoded yet. The image is decoded again from the
+	/// specified PNG data, which must be the same it was read from, with full precision.
+	/// Returns whether the depth of this image was reduced.
+	///
+	/// Grayscale and RGB images, with or without alpha, are handled the same, because they
+	/// are always expanded to RGBA before reducing their depth.
+	pub fn reduce_sixteen_bit_depth(
+		&mut self,
+		png_data: &[u8],
+		reduction: SixteenBitDepthReduction
+	) -> Result<bool, ImageProcessingError> {
+		let Self::ParsedPng { png_reader } = self else {
+			return Ok(false);
+		};
+
+		if matches!(reduction, SixteenBitDepthReduction::None)
+			|| !matches!(png_reader.info().bit_depth, PngBitDepth::Sixteen)
+		{
+			return Ok(false);
+		}
+
+		let mut png_reader = spng::Decoder::new(png_data)
+			.with_decode_flags(DecodeFlags::GAMMA | DecodeFlags::TRANSPARENCY)
+			.with_context_flags(ContextFlags::IGNORE_ADLER32)
+			.with_output_format(Format::Rgba16) // Samples are in host byte order
+			.read_info()?;
+
+		let mut rgba16_buf = vec![0; png_reader.output_buffer_size()];
+		png_reader.next_frame(&mut rgba16_buf)?;
+
+		let width = NonZeroU16::new(png_reader.info().width as u16).unwrap();
+		let height = NonZeroU16::new(png_reader.info().height as u16).unwrap();
+		let rgba16_pixels = rgba16_buf
+			.chunks_exact(2)
+			.map(|sample| u16::from_ne_bytes([sample[0], sample[1]]))
+			.collect::<Vec<_>>();
+
+		let Some(buf) = depth_reducer::reduce_depth(&rgba16_pixels, width.get() as usize, reduction)
+		else {
+			return Ok(false);
+		};
+
+		*self = PixelArray { width, height, buf }.into();
+
+		Ok(true)
+	}
+
+	/// Computes objective metrics of the visual quality of this color quantized image,
+	/// relative to the specified reference image it was quantized from.
+	///
+	/// Returns `Ok(None)` if this image was not color quantized, or the reference image was.
+	pub fn quantization_quality_metrics(
+		&self,
+		reference: &mut Self
+	) -> Result<Option<ImageQualityMetrics>, ImageProcessingError> {
+		let Self::Indexed {
+			width,
+			height,
+			palette,
+			pixel_palette_indexes,
+			..
+		} = self
+		else {
+			return Ok(None);
+		};
+
+		let Some(reference_pixels) = reference.as_pixel_array()? else {
+			return Ok(None);
+		};
+
+		if (reference_pixels.width(), reference_pixels.height()) != (*width, *height) {
+			return Err(ImageProcessingError::ChangedDimensions);
+		}
+
+		let quantized_pixels = pixel_palette_indexes
+			.iter()
+			.map(|palette_index| palette[*palette_index as usize])
+			.collect::<Vec<_>>();
+
+		Ok(Some(ImageQualityMetrics::compute(
+			reference_pixels.as_slice(),
+			&quantized_pixels,
+			width.get() as usize
+		)))
+	}
+
 	/// Performs color quantization on this image according to the specified parameters,
 	/// and returns a new image with the result. In some edge cases, even in combination
 	/// with another optimizer, this may be a size-increasing operation, depending on the
 	/// dithering pattern compressibility and how optimal the input image already was.
 	///
-	/// Returns `Ok(None)` if the image was already quantized.
+	/// If the minimum quality is greater than zero, progressively smaller palettes are tried,
+	/// down to the number of colors of the quantization target, and the smallest palette that
+	/// reaches the minimum quality is used.
+	///
+	/// Returns `Ok(None)` if the image was already quantized, or if even the palette with
+	/// the most colors does not reach the minimum quality.
 	pub fn quantize_color(
 		&mut self,
 		quantization_target: ColorQuantizationTarget,
-		dithering_level: f32
+		dithering_level: f32,
+		minimum_quality: u8
 	) -> Result<Option<Self>, ImageProcessingError> {
 		let width = self.width();
 		let height = self.height();
-
-		// Set the quantization attributes
-		let mut quantization_attributes = Attributes::new();
-		quantization_attributes.set_max_colors(quantization_target.max_colors())?;
-		quantization_attributes.set_speed(2)?;
-		quantization_attributes.set_quality(0, 100)?;
+		let minimum_quality = cmp::min(minimum_quality, 100);
 
 		let bitmap = if let Some(pixel_array) = self.as_pixel_array()? {
 			pixel_array.as_slice()
@@ -299,16 +511,48 @@ impl<R: Read> ProcessedImage<R> {
 			return Ok(None);
 		};
 
-		// Wrap the pixel data in an ImageQuant image
-		let mut iq_image = quantization_attributes.new_image_borrowed(
-			bitmap,
-			width.get() as usize,
-			height.get() as usize,
-			0.0 // sRGB: spng applies gamma correction
-		)?;
+		// Without a minimum quality, every palette is acceptable, so the smallest palette
+		// would always be chosen. Stick to the palette size of the quantization target then
+		let max_colors = quantization_target.max_colors();
+		let palette_sizes = if minimum_quality > 0 {
+			QUANTIZATION_PALETTE_SIZES
+				.iter()
+				.copied()
+				.filter(|palette_size| *palette_size <= max_colors)
+				.collect()
+		} else {
+			vec![max_colors]
+		};
+
+		// Try each palette size, from the biggest to the smallest, until the minimum quality
+		// is no longer reached. The quality of smaller palettes is never better
+		let mut quantization = None;
+		for palette_size in palette_sizes {
+			// Set the quantization attributes
+			let mut quantization_attributes = Attributes::new();
+			quantization_attributes.set_max_colors(palette_size)?;
+			quantization_attributes.set_speed(2)?;
+			quantization_attributes.set_quality(minimum_quality, 100)?;
+
+			// Wrap the pixel data in an ImageQuant image
+			let mut iq_image = quantization_attributes.new_image_borrowed(
+				bitmap,
+				width.get() as usize,
+				height.get() as usize,
+				0.0 // sRGB: spng applies gamma correction
+			)?;
+
+			// Configure the quantization operation for the image from its attributes
+			match quantization_attributes.quantize(&mut iq_image) {
+				Ok(quantization_result) => quantization = Some((iq_image, quantization_result)),
+				Err(liq_error::QualityTooLow) => break,
+				Err(err) => return Err(err.into())
+			}
+		}
 
-		// Configure the quantization operation for the image from its attributes
-		let mut quantization_result = quantization_attributes.quantize(&mut iq_image)?;
+		let Some((mut iq_image, mut quantization_result)) = quantization else {
+			return Ok(None);
+		};
 		quantization_result.set_dithering_level(dithering_level)?;
 
 		// Quantize the image
@@ -333,7 +577,7 @@ impl<R: Read> ProcessedImage<R> {
 	pub fn downsize_single_color(
 		&mut self,
 		can_change_color_type: bool,
-		is_auxiliary_shader_target_texture: bool
+		is_shader_texture: bool
 	) -> Result<Option<Self>, ImageProcessingError> {
 		let dimension = cmp::min(self.width(), self.height());
 		let minimum_mipmap_level_keeping_dimension =
@@ -355,7 +599,7 @@ impl<R: Read> ProcessedImage<R> {
 		//   of a font that cannot display anything, so this shouldn't matter too much in practice. FIXME
 		if minimum_mipmap_level_keeping_dimension >= dimension
 			|| !can_change_color_type
-			|| is_auxiliary_shader_target_texture
+			|| is_shader_texture
 		{
 			return Ok(None);
 		}
@@ -393,54 +637,13 @@ impl<R: Read> ProcessedImage<R> {
 		can_change_transparent_pixel_colors: bool,
 		can_convert_to_grayscale: bool
 	) -> Result<Vec<u8>, ImageProcessingError> {
-		let zopfli_iterations_model =
-			ZopfliIterationsTimeModel::new(zopfli_compression_iterations, 2.0);
-
-		let pixel_count = self.width().get() as u32 * self.height().get() as u32;
-
-		let filter_strategies = indexset! {
-			// The usually most promising filters. Only a single Zopfli run with
-			// the estimated best filter will be attempted
-			RowFilter::None,
-			RowFilter::Bigrams,
-			RowFilter::BigEnt,
-			RowFilter::MinSum,
-			RowFilter::Brute
-		};
-
-		let optimization_options = Options {
-			optimize_alpha: can_change_transparent_pixel_colors,
-			bit_depth_reduction: can_change_color_type,
-			color_type_reduction: can_change_color_type,
-			// Compute an appropriate number of Zopfli compression iterations using our
-			// model. If the number of iterations drops to zero, switch to the much faster,
-			// but not so space-efficient, libdeflater. Take into account that OxiPNG does
-			// a run per filter with a faster compressor than Zopfli to evaluate the best
-			// filtering strategy per image, but that still takes a significant amount of
-			// time
-			deflate: match zopfli_iterations_model.iterations_for_data_size(pixel_count, 0, 15) {
-				0 => Deflaters::Libdeflater {
-					// Use the maximum compression level for the best compression.
-					// This is still acceptably fast for bigger images of realistic
-					// sizes
-					compression: 12
-				},
-				zopfli_iterations => Deflaters::Zopfli {
-					iterations: NonZeroU8::new(zopfli_iterations).unwrap()
-				}
-			},
-			filter: filter_strategies,
-			fix_errors: true, // Ignore CRC for speed. We assume a reliable data source
-			force: false,
-			idat_recoding: true,
-			interlace: None, // We provide raw pixel data to OxiPNG, so interlacing is not a concern
-			palette_reduction: true,
-			grayscale_reduction: can_convert_to_grayscale && can_change_color_type,
-			scale_16: can_change_color_type,
-			strip: StripChunks::All,
-			timeout: Some(Duration::from_secs(600)), // Bail out if the optimization takes too long
-			fast_evaluation: true
-		};
+		let optimization_options = oxipng_options(
+			self.width().get() as u32 * self.height().get() as u32,
+			zopfli_compression_iterations,
+			can_change_color_type,
+			can_change_transparent_pixel_colors,
+			can_convert_to_grayscale
+		);
 
 		let oxipng_image = match self {
 			parsed_png @ Self::ParsedPng { .. } => oxipng::RawImage::new(
@@ -481,3 +684,79 @@ impl<R: Read> From<PixelArray> for ProcessedImage<R> {
 		Self::RGBA8 { pixels }
 	}
 }
+
+/// Losslessly recompresses the specified PNG image, whose width and height multiplied together
+/// are the specified pixel count, without changing its color type, bit depth or any pixel.
+/// The returned PNG image is never bigger than the input one.
+pub fn recompress_losslessly(
+	png: &[u8],
+	pixel_count: u32,
+	zopfli_compression_iterations: u8
+) -> Result<Vec<u8>, ImageProcessingError> {
+	let mut optimization_options = oxipng_options(
+		pixel_count,
+		zopfli_compression_iterations,
+		false,
+		false,
+		false
+	);
+	// Shaders may depend on palette indexes, so keep them as is
+	optimization_options.palette_reduction = false;
+
+	Ok(oxipng::optimize_from_memory(png, &optimization_options)?)
+}
+
+/// Returns the OxiPNG options to optimize an image with the specified pixel count.
+fn oxipng_options(
+	pixel_count: u32,
+	zopfli_compression_iterations: u8,
+	can_change_color_type: bool,
+	can_change_transparent_pixel_colors: bool,
+	can_convert_to_grayscale: bool
+) -> Options {
+	let zopfli_iterations_model = ZopfliIterationsTimeModel::new(zopfli_compression_iterations, 2.0);
+
+	let filter_strategies = indexset! {
+		// The usually most promising filters. Only a single Zopfli run with
+		// the estimated best filter will be attempted
+		RowFilter::None,
+		RowFilter::Bigrams,
+		RowFilter::BigEnt,
+		RowFilter::MinSum,
+		RowFilter::Brute
+	};
+
+	Options {
+		optimize_alpha: can_change_transparent_pixel_colors,
+		bit_depth_reduction: can_change_color_type,
+		color_type_reduction: can_change_color_type,
+		// Compute an appropriate number of Zopfli compression iterations using our
+		// model. If the number of iterations drops to zero, switch to the much faster,
+		// but not so space-efficient, libdeflater. Take into account that OxiPNG does
+		// a run per filter with a faster compressor than Zopfli to evaluate the best
+		// filtering strategy per image, but that still takes a significant amount of
+		// time
+		deflate: match zopfli_iterations_model.iterations_for_data_size(pixel_count, 0, 15) {
+			0 => Deflaters::Libdeflater {
+				// Use the maximum compression level for the best compression.
+				// This is still acceptably fast for bigger images of realistic
+				// sizes
+				compression: 12
+			},
+			zopfli_iterations => Deflaters::Zopfli {
+				iterations: NonZeroU8::new(zopfli_iterations).unwrap()
+			}
+		},
+		filter: filter_strategies,
+		fix_errors: true, // Ignore CRC for speed. We assume a reliable data source
+		force: false,
+		idat_recoding: true,
+		interlace: None, // We provide raw pixel data to OxiPNG, so interlacing is not a concern
+		palette_reduction: true,
+		grayscale_reduction: can_convert_to_grayscale && can_change_color_type,
+		scale_16: can_change_color_type,
+		strip: StripChunks::All,
+		timeout: Some(Duration::from_secs(600)), // Bail out if the optimization takes too long
+		fast_evaluation: true
+	}
+}
@@ -0,0 +1,47 @@
+//! Contains code to reduce the depth of images with 16 bits per channel to 8 bits per channel.
+
+use crate::config::SixteenBitDepthReduction;
+
+#[cfg(test)]
+mod tests;
+
+/// The 4x4 Bayer matrix used for ordered dithering, whose elements are in the `[0, 16)`
+/// range.
+const BAYER_MATRIX: [[u32; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// Reduces the specified RGBA16 pixels of an image with the specified width, in host byte
+/// order, to RGBA8 pixels, using the specified method. The alpha channel is always rounded,
+/// because dithering it would make transparency noisy. `None` is returned if the method
+/// does not reduce the depth of images.
+pub fn reduce_depth(
+	rgba16_pixels: &[u16],
+	width: usize,
+	reduction: SixteenBitDepthReduction
+) -> Option<Vec<u8>> {
+	let round = |sample: u16| ((sample as u32 * 255 + 32767) / 65535) as u8;
+
+	match reduction {
+		SixteenBitDepthReduction::None => None,
+		SixteenBitDepthReduction::Round => Some(rgba16_pixels.iter().copied().map(round).collect()),
+		SixteenBitDepthReduction::Dither => Some(
+			rgba16_pixels
+				.chunks_exact(4)
+				.enumerate()
+				.flat_map(|(i, pixel)| {
+					let (x, y) = (i % width, i / width);
+					// Offset samples by a threshold in the (0, 1) range, centered around 0.5,
+					// before truncating them. On average, this is equivalent to rounding
+					let threshold = (2 * BAYER_MATRIX[y % 4][x % 4] + 1) * 65535 / 32;
+					let dither = |sample: u16| ((sample as u32 * 255 + threshold) / 65535) as u8;
+
+					[
+						dither(pixel[0]),
+						dither(pixel[1]),
+						dither(pixel[2]),
+						round(pixel[3])
+					]
+				})
+				.collect()
+		)
+	}
+}
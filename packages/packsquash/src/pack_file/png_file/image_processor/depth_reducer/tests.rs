@@ -0,0 +1,48 @@
+use pretty_assertions::assert_eq;
+
+use super::*;
+
+#[test]
+fn samples_are_rounded() {
+	assert_eq!(
+		reduce_depth(
+			&[0, 128, 129, 65535, 32896, 32767, 65150, 65408],
+			2,
+			SixteenBitDepthReduction::Round
+		),
+		Some(vec![0, 0, 1, 255, 128, 127, 254, 255])
+	);
+}
+
+#[test]
+fn dithering_preserves_average_intensity() {
+	// A 4x4 image of a gray level halfway between two 8-bit gray levels
+	let gray_level = 32896 + 128;
+	let rgba16_pixels = [gray_level, gray_level, gray_level, u16::MAX].repeat(16);
+
+	let rgba8_pixels = reduce_depth(&rgba16_pixels, 4, SixteenBitDepthReduction::Dither)
+		.expect("Dithering reduces depth");
+
+	let red_samples = rgba8_pixels.iter().step_by(4).copied().collect::<Vec<_>>();
+	assert_eq!(
+		red_samples.iter().filter(|sample| **sample == 128).count(),
+		8
+	);
+	assert_eq!(
+		red_samples.iter().filter(|sample| **sample == 129).count(),
+		8
+	);
+	assert!(rgba8_pixels
+		.iter()
+		.skip(3)
+		.step_by(4)
+		.all(|alpha| *alpha == u8::MAX));
+}
+
+#[test]
+fn no_reduction_is_done_when_disabled() {
+	assert_eq!(
+		reduce_depth(&[0; 4], 1, SixteenBitDepthReduction::None),
+		None
+	);
+}
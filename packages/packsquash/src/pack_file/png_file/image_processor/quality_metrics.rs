@@ -0,0 +1,150 @@
+//! Contains code to compute objective metrics of the visual quality loss of lossy image
+//! transformations.
+//!
+//! References:
+//! - <https://en.wikipedia.org/wiki/Peak_signal-to-noise_ratio>
+//! - <https://en.wikipedia.org/wiki/Structural_similarity>
+
+use std::fmt::{Display, Formatter};
+
+use rgb::RGBA8;
+
+#[cfg(test)]
+mod tests;
+
+/// The side length of the square windows the structural similarity is computed over.
+const SSIM_WINDOW_SIZE: usize = 8;
+/// The stabilization constants of the structural similarity formula, for 8-bit samples.
+const SSIM_C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+const SSIM_C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+
+/// Objective metrics of how similar a distorted image looks to a reference image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageQualityMetrics {
+	/// The peak signal-to-noise ratio, in decibels. It is infinite for identical images.
+	pub peak_signal_to_noise_ratio: f64,
+	/// The mean structural similarity index, which is `1` for identical images.
+	pub structural_similarity: f64
+}
+
+impl ImageQualityMetrics {
+	/// Computes the quality metrics of the specified distorted pixels, relative to the
+	/// specified reference pixels, which belong to images of the specified width. Colors
+	/// are weighted by their opacity, so changes to the color of transparent pixels, which
+	/// are invisible, do not count as quality loss.
+	pub fn compute(reference: &[RGBA8], distorted: &[RGBA8], width: usize) -> Self {
+		debug_assert_eq!(reference.len(), distorted.len());
+
+		let squared_error_sum = reference
+			.iter()
+			.zip(distorted)
+			.map(|(reference_pixel, distorted_pixel)| {
+				premultiplied_samples(*reference_pixel)
+					.into_iter()
+					.zip(premultiplied_samples(*distorted_pixel))
+					.map(|(reference_sample, distorted_sample)| {
+						(reference_sample - distorted_sample).powi(2)
+					})
+					.sum::<f64>()
+			})
+			.sum::<f64>();
+		let mean_squared_error = squared_error_sum / (reference.len() * 4) as f64;
+
+		Self {
+			peak_signal_to_noise_ratio: 10.0 * (255.0 * 255.0 / mean_squared_error).log10(),
+			structural_similarity: structural_similarity(
+				&reference.iter().copied().map(luma).collect::<Vec<_>>(),
+				&distorted.iter().copied().map(luma).collect::<Vec<_>>(),
+				width
+			)
+		}
+	}
+}
+
+impl Display for ImageQualityMetrics {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		if self.peak_signal_to_noise_ratio.is_finite() {
+			write!(f, "PSNR {:.2} dB", self.peak_signal_to_noise_ratio)?;
+		} else {
+			write!(f, "PSNR ∞ dB")?;
+		}
+
+		write!(f, ", SSIM {:.4}", self.structural_similarity)
+	}
+}
+
+/// Returns the RGBA samples of the specified pixel, with its color premultiplied by its
+/// opacity.
+fn premultiplied_samples(pixel: RGBA8) -> [f64; 4] {
+	let alpha = pixel.a as f64 / 255.0;
+
+	[
+		pixel.r as f64 * alpha,
+		pixel.g as f64 * alpha,
+		pixel.b as f64 * alpha,
+		pixel.a as f64
+	]
+}
+
+/// Returns the luma of the specified pixel, with its color premultiplied by its opacity.
+fn luma(pixel: RGBA8) -> f64 {
+	let [r, g, b, _] = premultiplied_samples(pixel);
+
+	0.299 * r + 0.587 * g + 0.114 * b
+}
+
+/// Computes the mean structural similarity of the specified distorted luma samples relative
+/// to the reference ones, over non-overlapping square windows. Windows at the right and
+/// bottom edges may be smaller if the image dimensions are not a multiple of their size.
+fn structural_similarity(reference: &[f64], distorted: &[f64], width: usize) -> f64 {
+	let height = reference.len() / width;
+	let mut structural_similarity_sum = 0.0;
+	let mut window_count = 0;
+
+	for window_y in (0..height).step_by(SSIM_WINDOW_SIZE) {
+		for window_x in (0..width).step_by(SSIM_WINDOW_SIZE) {
+			let window_samples = || {
+				(window_y..height.min(window_y + SSIM_WINDOW_SIZE)).flat_map(move |y| {
+					(window_x..width.min(window_x + SSIM_WINDOW_SIZE)).map(move |x| {
+						let i = y * width + x;
+						(reference[i], distorted[i])
+					})
+				})
+			};
+
+			let sample_count = window_samples().count() as f64;
+			let (reference_sum, distorted_sum) = window_samples().fold(
+				(0.0, 0.0),
+				|(reference_sum, distorted_sum), (reference_sample, distorted_sample)| {
+					(
+						reference_sum + reference_sample,
+						distorted_sum + distorted_sample
+					)
+				}
+			);
+			let (reference_mean, distorted_mean) =
+				(reference_sum / sample_count, distorted_sum / sample_count);
+
+			let (mut reference_variance, mut distorted_variance, mut covariance) = (0.0, 0.0, 0.0);
+			for (reference_sample, distorted_sample) in window_samples() {
+				let reference_deviation = reference_sample - reference_mean;
+				let distorted_deviation = distorted_sample - distorted_mean;
+
+				reference_variance += reference_deviation * reference_deviation;
+				distorted_variance += distorted_deviation * distorted_deviation;
+				covariance += reference_deviation * distorted_deviation;
+			}
+			reference_variance /= sample_count;
+			distorted_variance /= sample_count;
+			covariance /= sample_count;
+
+			structural_similarity_sum += ((2.0 * reference_mean * distorted_mean + SSIM_C1)
+				* (2.0 * covariance + SSIM_C2))
+				/ ((reference_mean * reference_mean + distorted_mean * distorted_mean + SSIM_C1)
+					* (reference_variance + distorted_variance + SSIM_C2));
+			window_count += 1;
+		}
+	}
+
+	structural_similarity_sum / window_count as f64
+}
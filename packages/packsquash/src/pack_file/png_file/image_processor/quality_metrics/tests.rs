@@ -0,0 +1,48 @@
+use pretty_assertions::assert_eq;
+
+use super::*;
+
+/// Returns a 16x16 image with a horizontal gray gradient.
+fn gradient() -> Vec<RGBA8> {
+	(0..16 * 16)
+		.map(|i| {
+			let gray_level = (i % 16 * 16) as u8;
+			RGBA8::new(gray_level, gray_level, gray_level, 255)
+		})
+		.collect()
+}
+
+#[test]
+fn identical_images_have_perfect_quality() {
+	let metrics = ImageQualityMetrics::compute(&gradient(), &gradient(), 16);
+
+	assert_eq!(metrics.peak_signal_to_noise_ratio, f64::INFINITY);
+	assert!((metrics.structural_similarity - 1.0).abs() < 1e-9);
+}
+
+#[test]
+fn distorted_images_have_lower_quality() {
+	let posterized = gradient()
+		.into_iter()
+		.map(|pixel| {
+			let gray_level = pixel.r & 0b1000_0000;
+			RGBA8::new(gray_level, gray_level, gray_level, 255)
+		})
+		.collect::<Vec<_>>();
+
+	let metrics = ImageQualityMetrics::compute(&gradient(), &posterized, 16);
+
+	assert!(metrics.peak_signal_to_noise_ratio < 20.0);
+	assert!(metrics.structural_similarity < 0.9);
+}
+
+#[test]
+fn transparent_pixel_colors_do_not_matter() {
+	let transparent = |gray_level| RGBA8::new(gray_level, gray_level, gray_level, 0);
+
+	assert_eq!(
+		ImageQualityMetrics::compute(&[transparent(0)], &[transparent(255)], 1)
+			.peak_signal_to_noise_ratio,
+		f64::INFINITY
+	);
+}
@@ -0,0 +1,175 @@
+//! Contains routines to downscale images made up of cells, such as animation frames or font
+//! glyphs, resampling each cell independently so that no colors bleed between them.
+
+use std::f64::consts::PI;
+
+use crate::config::DownscalingFilter;
+use crate::pack_index::TextureDownscaling;
+
+#[cfg(test)]
+mod tests;
+
+/// The number of lobes of the Lanczos filter kernel.
+const LANCZOS_LOBES: f64 = 3.0;
+
+/// Downscales the specified RGBA8 pixel data of an image with the specified width according
+/// to the specified downscaling and filter, returning the new image width, height and pixel
+/// data. `None` is returned if the image is not made up of whole cells of the expected size.
+pub fn downscale(
+	pixel_data: &[u8],
+	image_width: u32,
+	downscaling: &TextureDownscaling,
+	filter: DownscalingFilter
+) -> Option<(u32, u32, Vec<u8>)> {
+	let (cell_width, cell_height) = downscaling.cell_size();
+	let (downscaled_cell_width, downscaled_cell_height) = downscaling.downscaled_cell_size();
+
+	let pixel_count = pixel_data.len() / 4;
+	let image_height = (pixel_count / image_width as usize) as u32;
+	if pixel_count != image_width as usize * image_height as usize
+		|| image_width % cell_width != 0
+		|| image_height % cell_height != 0
+	{
+		return None;
+	}
+
+	let columns = image_width / cell_width;
+	let rows = image_height / cell_height;
+	let downscaled_width = columns * downscaled_cell_width;
+	let downscaled_height = rows * downscaled_cell_height;
+
+	let horizontal_weights = filter_weights(cell_width, downscaled_cell_width, filter);
+	let vertical_weights = filter_weights(cell_height, downscaled_cell_height, filter);
+
+	let source_pixel = |x: u32, y: u32| {
+		let i = (y as usize * image_width as usize + x as usize) * 4;
+		&pixel_data[i..i + 4]
+	};
+
+	let mut downscaled_pixel_data =
+		vec![0; downscaled_width as usize * downscaled_height as usize * 4];
+	let mut horizontally_resampled_cell =
+		vec![[0.0; 4]; downscaled_cell_width as usize * cell_height as usize];
+
+	for row in 0..rows {
+		for column in 0..columns {
+			let (cell_x, cell_y) = (column * cell_width, row * cell_height);
+
+			// Resample horizontally first, working with premultiplied alpha so that the colors
+			// of transparent pixels do not bleed into visible ones
+			for y in 0..cell_height {
+				for (x, weights) in horizontal_weights.iter().enumerate() {
+					let mut resampled_pixel = [0.0; 4];
+
+					for &(source_x, weight) in weights {
+						let pixel = source_pixel(cell_x + source_x, cell_y + y);
+						let alpha = pixel[3] as f64 / 255.0;
+
+						for channel in 0..3 {
+							resampled_pixel[channel] += pixel[channel] as f64 * alpha * weight;
+						}
+						resampled_pixel[3] += pixel[3] as f64 * weight;
+					}
+
+					horizontally_resampled_cell[y as usize * downscaled_cell_width as usize + x] =
+						resampled_pixel;
+				}
+			}
+
+			// Then resample vertically, writing the result to the downscaled image
+			for x in 0..downscaled_cell_width as usize {
+				for (y, weights) in vertical_weights.iter().enumerate() {
+					let mut resampled_pixel = [0.0; 4];
+
+					for &(source_y, weight) in weights {
+						let pixel = horizontally_resampled_cell
+							[source_y as usize * downscaled_cell_width as usize + x];
+
+						for (channel, value) in pixel.into_iter().enumerate() {
+							resampled_pixel[channel] += value * weight;
+						}
+					}
+
+					let alpha = resampled_pixel[3].clamp(0.0, 255.0);
+					let i = ((row as usize * downscaled_cell_height as usize + y)
+						* downscaled_width as usize
+						+ column as usize * downscaled_cell_width as usize
+						+ x) * 4;
+
+					for channel in 0..3 {
+						downscaled_pixel_data[i + channel] = if alpha > 0.0 {
+							(resampled_pixel[channel] * 255.0 / alpha)
+								.round()
+								.clamp(0.0, 255.0) as u8
+						} else {
+							0
+						};
+					}
+					downscaled_pixel_data[i + 3] = alpha.round() as u8;
+				}
+			}
+		}
+	}
+
+	Some((downscaled_width, downscaled_height, downscaled_pixel_data))
+}
+
+/// Computes the source pixels and weights that contribute to each destination pixel when
+/// resampling a row or column of pixels from a source size to a smaller destination size.
+fn filter_weights(
+	source_size: u32,
+	destination_size: u32,
+	filter: DownscalingFilter
+) -> Vec<Vec<(u32, f64)>> {
+	let scale = source_size as f64 / destination_size as f64;
+
+	(0..destination_size)
+		.map(|destination_position| {
+			let center = (destination_position as f64 + 0.5) * scale;
+
+			match filter {
+				DownscalingFilter::Nearest => {
+					vec![((center as u32).min(source_size - 1), 1.0)]
+				}
+				DownscalingFilter::Lanczos => {
+					let support = LANCZOS_LOBES * scale;
+					let first_position = (center - support).floor().max(0.0) as u32;
+					let last_position = ((center + support).ceil() as u32).min(source_size);
+
+					let mut weights = (first_position..last_position)
+						.map(|position| (position, lanczos((position as f64 + 0.5 - center) / scale)))
+						.filter(|(_, weight)| *weight != 0.0)
+						.collect::<Vec<_>>();
+
+					let weight_sum = weights.iter().map(|(_, weight)| weight).sum::<f64>();
+					if weight_sum == 0.0 {
+						return vec![((center as u32).min(source_size - 1), 1.0)];
+					}
+
+					for (_, weight) in &mut weights {
+						*weight /= weight_sum;
+					}
+
+					weights
+				}
+			}
+		})
+		.collect()
+}
+
+/// Evaluates the Lanczos filter kernel at the specified position.
+fn lanczos(x: f64) -> f64 {
+	let sinc = |x: f64| {
+		if x == 0.0 {
+			1.0
+		} else {
+			(PI * x).sin() / (PI * x)
+		}
+	};
+
+	if x.abs() < LANCZOS_LOBES {
+		sinc(x) * sinc(x / LANCZOS_LOBES)
+	} else {
+		0.0
+	}
+}
@@ -0,0 +1,61 @@
+use std::num::NonZeroU16;
+
+use pretty_assertions::assert_eq;
+
+use super::*;
+
+/// Returns the RGBA8 pixel data of an opaque pixel of the specified gray level.
+fn pixel(gray_level: u8) -> [u8; 4] {
+	[gray_level, gray_level, gray_level, 255]
+}
+
+#[test]
+fn cells_are_resampled_independently() {
+	// A 2x4 vertical strip of two 2x2 frames, downscaled to two 1x1 frames
+	let pixel_data = [
+		pixel(0),
+		pixel(0),
+		pixel(0),
+		pixel(0),
+		pixel(200),
+		pixel(200),
+		pixel(200),
+		pixel(200)
+	]
+	.concat();
+	let downscaling = TextureDownscaling::new(2, 2, NonZeroU16::new(1).unwrap());
+
+	for filter in [DownscalingFilter::Nearest, DownscalingFilter::Lanczos] {
+		assert_eq!(
+			downscale(&pixel_data, 2, &downscaling, filter),
+			Some((1, 2, [pixel(0), pixel(200)].concat()))
+		);
+	}
+}
+
+#[test]
+fn transparent_pixel_colors_do_not_bleed() {
+	let pixel_data = [[255, 0, 0, 255], [0, 0, 255, 0]].concat();
+	let downscaling = TextureDownscaling::new(2, 1, NonZeroU16::new(1).unwrap());
+
+	let (_, _, downscaled_pixel_data) =
+		downscale(&pixel_data, 2, &downscaling, DownscalingFilter::Lanczos)
+			.expect("The image is made up of whole cells");
+
+	assert_eq!(downscaled_pixel_data[..3], [255, 0, 0]);
+}
+
+#[test]
+fn images_not_made_up_of_whole_cells_are_rejected() {
+	let downscaling = TextureDownscaling::new(2, 2, NonZeroU16::new(1).unwrap());
+
+	assert_eq!(
+		downscale(
+			&pixel(0).repeat(6),
+			2,
+			&downscaling,
+			DownscalingFilter::Nearest
+		),
+		None
+	);
+}
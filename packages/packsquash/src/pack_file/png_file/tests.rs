@@ -1,3 +1,4 @@
+use enumset::EnumSet;
 use rgb::FromSlice;
 use spng::{DecodeFlags, Format};
 use std::{env, fs};
@@ -5,7 +6,8 @@ use std::{env, fs};
 use tokio_stream::StreamExt;
 use tokio_test::io::Builder;
 
-use crate::config::ColorQuantizationTarget;
+use crate::config::{AlphaBinarization, ColorQuantizationTarget};
+use crate::pack_file::asset_type::compile_asset_types_globset;
 
 use super::*;
 
@@ -15,6 +17,9 @@ static ENDERMAN_EYES_DATA: &[u8] = include_bytes!("enderman_eyes.png");
 /// 7x when color quantized and dithered with the default options.
 static DITHERBOMB_DATA: &[u8] = include_bytes!("ditherbomb.png");
 static SINGLE_BLUE_COLOR: &[u8] = include_bytes!("blue.png");
+/// A smooth gradient with 1024 different colors, whose color quantization quality decreases
+/// sharply with the palette size.
+static GRADIENT_DATA: &[u8] = include_bytes!("gradient.png");
 
 /// Processes the given input data as a [PngFile], using the provided settings,
 /// expecting a successful result.
@@ -35,7 +40,10 @@ async fn successful_process_test(
 		read: Builder::new().read(input_data).build(),
 		asset_type,
 		file_length_hint: input_data_len,
-		optimization_settings: settings
+		optimization_settings: settings,
+		animation_frame_deduplication: None,
+		texture_downscaling: None,
+		depends_on_other_pack_files: false
 	}
 	.process();
 
@@ -252,6 +260,144 @@ async fn ditherbomb_can_be_defused() {
 	.await
 }
 
+#[test]
+fn minimum_quality_quantization_works() {
+	let quantize = |minimum_quality| {
+		ProcessedImage::read(GRADIENT_DATA)
+			.expect("No error should happen while reading the gradient PNG")
+			.quantize_color(
+				ColorQuantizationTarget::EightBitDepth,
+				0.85,
+				minimum_quality
+			)
+			.expect("No error should happen while quantizing the gradient PNG")
+	};
+
+	// Without a minimum quality, the palette size of the quantization target is used
+	match quantize(0) {
+		Some(ProcessedImage::Indexed { palette, .. }) => assert_eq!(palette.len(), 256),
+		_ => panic!("The gradient should be quantized without a minimum quality")
+	}
+
+	// A 256 color palette is enough for a moderate quality, but a 16 color one is not
+	match quantize(50) {
+		Some(ProcessedImage::Indexed {
+			palette,
+			quantization_quality,
+			..
+		}) => {
+			assert!(palette.len() > 16);
+			assert!(quantization_quality >= 50);
+		}
+		_ => panic!("The gradient should be quantized to reach a moderate quality")
+	}
+
+	// No palette is enough for a high quality, so quantization is rejected
+	assert!(quantize(95).is_none());
+}
+
+#[tokio::test]
+async fn insufficient_quality_quantization_is_rejected() {
+	successful_process_test(
+		GRADIENT_DATA,
+		PngFileOptions {
+			color_quantization_target: ColorQuantizationTarget::EightBitDepth,
+			color_quantization_minimum_quality: 95,
+			..Default::default()
+		},
+		true,  // Quantization is rejected, so the pixels are kept
+		false, // Not necessarily smaller
+		false, // Not necessarily the same color type
+		None,  // Same resolution
+		PackFileAssetType::GenericTexture,
+		"insufficient_quality_quantization_is_rejected"
+	)
+	.await
+}
+
+#[tokio::test]
+async fn cutout_texture_alpha_binarization_works() {
+	successful_process_test(
+		PNG_DATA,
+		PngFileOptions {
+			color_quantization_target: ColorQuantizationTarget::None,
+			alpha_binarization: AlphaBinarization::Always,
+			..Default::default()
+		},
+		false,          // Partially transparent pixels may change
+		true,           // Smaller size
+		false,          // Not necessarily the same color type
+		Some((16, 16)), // Same resolution
+		PackFileAssetType::CutoutTexture,
+		"cutout_texture_alpha_binarization_works"
+	)
+	.await
+}
+
+#[tokio::test]
+async fn leaves_alpha_is_not_binarized_by_default() {
+	let leaves_texture_path = "assets/minecraft/textures/block/oak_leaves.png";
+	assert!(
+		compile_asset_types_globset(EnumSet::only(PackFileAssetType::CutoutTexture))
+			.is_match(leaves_texture_path),
+		"Leaves should be recognized as cutout textures"
+	);
+
+	successful_process_test(
+		PNG_DATA,
+		PngFileOptions {
+			color_quantization_target: ColorQuantizationTarget::None,
+			..Default::default()
+		},
+		true,           // Partially transparent pixels are kept
+		false,          // Not necessarily smaller
+		false,          // Not necessarily the same color type
+		Some((16, 16)), // Same resolution
+		PackFileAssetType::CutoutTexture,
+		"leaves_alpha_is_not_binarized_by_default"
+	)
+	.await
+}
+
+#[tokio::test]
+async fn material_map_data_is_preserved() {
+	successful_process_test(
+		PNG_DATA,
+		PngFileOptions {
+			color_quantization_target: ColorQuantizationTarget::EightBitDepth,
+			alpha_binarization: AlphaBinarization::Always,
+			downscaling_target_resolution: Some(NonZeroU16::new(8).unwrap()),
+			..Default::default()
+		},
+		true,           // Same pixels
+		false,          // Not necessarily smaller
+		false,          // Not necessarily the same color type
+		Some((16, 16)), // Same resolution
+		PackFileAssetType::PbrSpecularMap,
+		"material_map_data_is_preserved"
+	)
+	.await
+}
+
+#[tokio::test]
+async fn data_texture_is_kept_exact() {
+	successful_process_test(
+		PNG_DATA,
+		PngFileOptions {
+			color_quantization_target: ColorQuantizationTarget::EightBitDepth,
+			downsize_if_single_color: true,
+			..Default::default()
+		},
+		true,           // Same pixels
+		false,          // Not necessarily smaller
+		true,           // Same color type
+		Some((16, 16)), // Same resolution
+		PackFileAssetType::DataTexture,
+		"data_texture_is_kept_exact"
+	)
+	.await
+}
+
 #[tokio::test]
 async fn single_color_image_is_downsized() {
 	successful_process_test(
@@ -270,13 +416,73 @@ async fn single_color_image_is_downsized() {
 	.await
 }
 
+#[tokio::test]
+async fn texture_is_downscaled() {
+	successful_process_test(
+		PNG_DATA,
+		PngFileOptions {
+			downscaling_target_resolution: Some(NonZeroU16::new(8).unwrap()),
+			..Default::default()
+		},
+		false,        // Not the same pixels
+		true,         // Smaller file size
+		false,        // Maybe different color type
+		Some((8, 8)), // Power of two dimensions are kept
+		PackFileAssetType::GenericTexture,
+		"texture_is_downscaled"
+	)
+	.await
+}
+
+#[tokio::test]
+async fn pack_icon_is_resized_and_flattened() {
+	successful_process_test(
+		PNG_DATA,
+		PngFileOptions {
+			pack_icon_resolution: Some(NonZeroU16::new(8).unwrap()),
+			pack_icon_background_color: Some([0, 0, 0]),
+			..Default::default()
+		},
+		false,        // Not the same pixels
+		true,         // Smaller file size
+		false,        // Maybe different color type
+		Some((8, 8)), // Resized to the pack icon resolution
+		PackFileAssetType::PackIcon,
+		"pack_icon_is_resized_and_flattened"
+	)
+	.await
+}
+
+#[tokio::test]
+async fn non_square_pack_icon_is_resized_keeping_its_aspect_ratio() {
+	let settings = PngFileOptions {
+		pack_icon_resolution: Some(NonZeroU16::new(16).unwrap()),
+		..Default::default()
+	};
+
+	successful_process_test(
+		ENDERMAN_EYES_DATA,
+		settings,
+		false,         // Not the same pixels
+		false,         // Not necessarily smaller
+		false,         // Maybe different color type
+		Some((16, 8)), // Resized keeping the aspect ratio
+		PackFileAssetType::PackIcon,
+		"non_square_pack_icon_is_resized_keeping_its_aspect_ratio"
+	)
+	.await
+}
+
 #[tokio::test]
 async fn invalid_input_is_handled() {
 	let mut data_stream = PngFile {
 		read: Builder::new().read(&[]).build(),
 		asset_type: PackFileAssetType::GenericTexture,
 		file_length_hint: 0,
-		optimization_settings: Default::default()
+		optimization_settings: Default::default(),
+		animation_frame_deduplication: None,
+		texture_downscaling: None,
+		depends_on_other_pack_files: false
 	}
 	.process();
 
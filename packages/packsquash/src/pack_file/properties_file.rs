@@ -10,7 +10,9 @@ use tokio_util::codec::{Decoder, FramedRead};
 
 use crate::config::PropertiesFileOptions;
 
-use super::{AsyncReadAndSizeHint, PackFile, PackFileAssetType, PackFileConstructor};
+use super::{
+	AsyncReadAndSizeHint, PackFile, PackFileAssetType, PackFileConstructor, PackFileContext
+};
 
 #[cfg(test)]
 mod tests;
@@ -143,7 +145,8 @@ impl<T: AsyncRead + Send + Unpin + 'static> PackFileConstructor<T> for Propertie
 	fn new(
 		file_read_producer: impl FnOnce() -> Option<AsyncReadAndSizeHint<T>>,
 		_: PackFileAssetType,
-		optimization_settings: Self::OptimizationSettings
+		optimization_settings: Self::OptimizationSettings,
+		_: &PackFileContext<'_>
 	) -> Option<Self> {
 		file_read_producer().map(|(read, file_length_hint)| Self {
 			read,
@@ -1,5 +1,7 @@
 //! Contains code to optimize shader files.
 
+use std::any::Any;
+use std::sync::Arc;
 use std::{borrow::Cow, io, str::Utf8Error};
 
 use bytes::BytesMut;
@@ -11,10 +13,17 @@ use tokio_util::codec::{Decoder, FramedRead};
 
 use crate::config::{ShaderFileOptions, ShaderSourceTransformationStrategy};
 use crate::pack_file::shader_file::parser::{ParsedSymbol, Transpilable};
+use crate::pack_index::ImportableShaders;
+pub use parser::has_moj_import_directive;
 use parser::{ParseError, Parser};
 
-use super::{AsyncReadAndSizeHint, PackFile, PackFileAssetType, PackFileConstructor};
+use super::{
+	AsyncReadAndSizeHint, PackFile, PackFileAssetType, PackFileConstructor, PackFileContext
+};
 
+mod constant_folder;
+mod dead_code_eliminator;
+mod identifier_mangler;
 mod parser;
 
 #[cfg(test)]
@@ -31,6 +40,9 @@ pub struct ShaderFile<T: AsyncRead + Send + Unpin + 'static> {
 	read: T,
 	file_length_hint: usize,
 	is_vertex_or_fragment_shader: bool,
+	shader_path: String,
+	importable_shaders: Arc<ImportableShaders>,
+	imports_shaders: bool,
 	optimization_settings: ShaderFileOptions
 }
 
@@ -39,6 +51,11 @@ pub struct ShaderFile<T: AsyncRead + Send + Unpin + 'static> {
 pub struct OptimizerDecoder {
 	optimization_settings: ShaderFileOptions,
 	is_vertex_or_fragment_shader: bool,
+	/// The relative path of the shader, from which relative `#moj_import` directives are
+	/// resolved.
+	shader_path: String,
+	/// The shaders that `#moj_import` directives may import.
+	importable_shaders: Arc<ImportableShaders>,
 	reached_eof: bool
 }
 
@@ -71,18 +88,28 @@ impl Decoder for OptimizerDecoder {
 		}
 		self.reached_eof = true;
 
-		let shader_parser = Parser::new();
+		let shader_parser = Parser::new(&self.shader_path, &self.importable_shaders);
 		let source_transformation_strategy =
 			self.optimization_settings.source_transformation_strategy;
 
 		if self.is_vertex_or_fragment_shader {
-			// Vertex or fragment shaders must be parseable as translation units
+			let is_top_level_shader = self
+				.optimization_settings
+				.is_top_level_shader
+				.unwrap_or(true);
+
+			// Vertex or fragment shaders must be parseable as translation units. Only top-level
+			// shaders can have their syntax tree optimized, as other shaders may reference any
+			// of their symbols
 			process_shader_as::<TranslationUnit>(
 				src,
 				&shader_parser,
-				self.optimization_settings
-					.is_top_level_shader
-					.unwrap_or(true),
+				is_top_level_shader,
+				if is_top_level_shader {
+					SyntaxTreeOptimizations::for_top_level_shader(&self.optimization_settings)
+				} else {
+					SyntaxTreeOptimizations::default()
+				},
 				source_transformation_strategy
 			)
 		} else {
@@ -97,6 +124,7 @@ impl Decoder for OptimizerDecoder {
 				src,
 				&shader_parser,
 				false,
+				SyntaxTreeOptimizations::default(),
 				source_transformation_strategy
 			)
 			.or_else(|_| {
@@ -104,11 +132,18 @@ impl Decoder for OptimizerDecoder {
 					src,
 					&shader_parser,
 					false,
+					SyntaxTreeOptimizations::default(),
 					source_transformation_strategy
 				)
 			})
 			.or_else(|_| {
-				process_shader_as::<Expr>(src, &shader_parser, false, source_transformation_strategy)
+				process_shader_as::<Expr>(
+					src,
+					&shader_parser,
+					false,
+					SyntaxTreeOptimizations::default(),
+					source_transformation_strategy
+				)
 			})
 			.or_else(|err| {
 				if let OptimizationError::InvalidShader(err) = err {
@@ -135,6 +170,8 @@ impl<T: AsyncRead + Send + Unpin + 'static> PackFile for ShaderFile<T> {
 			OptimizerDecoder {
 				optimization_settings: self.optimization_settings,
 				is_vertex_or_fragment_shader: self.is_vertex_or_fragment_shader,
+				shader_path: self.shader_path,
+				importable_shaders: self.importable_shaders,
 				reached_eof: false
 			},
 			self.file_length_hint
@@ -144,6 +181,11 @@ impl<T: AsyncRead + Send + Unpin + 'static> PackFile for ShaderFile<T> {
 	fn is_compressed(&self) -> bool {
 		false
 	}
+
+	fn depends_on_other_pack_files(&self) -> bool {
+		// Imported shaders are expanded in minified and prettified shaders
+		self.imports_shaders
+	}
 }
 
 impl<T: AsyncRead + Send + Unpin + 'static> PackFileConstructor<T> for ShaderFile<T> {
@@ -152,28 +194,74 @@ impl<T: AsyncRead + Send + Unpin + 'static> PackFileConstructor<T> for ShaderFil
 	fn new(
 		file_read_producer: impl FnOnce() -> Option<AsyncReadAndSizeHint<T>>,
 		asset_type: PackFileAssetType,
-		optimization_settings: Self::OptimizationSettings
+		optimization_settings: Self::OptimizationSettings,
+		pack_file_context: &PackFileContext<'_>
 	) -> Option<Self> {
+		let is_vertex_or_fragment_shader =
+			!matches!(asset_type, PackFileAssetType::TranslationUnitSegment);
+		let imports_shaders = is_vertex_or_fragment_shader
+			&& pack_file_context
+				.pack_index
+				.imports_shaders(pack_file_context.relative_path.as_str());
+
 		file_read_producer().map(|(read, file_length_hint)| Self {
 			read,
 			// The file is too big to fit in memory if this conversion fails anyway
 			file_length_hint: file_length_hint.try_into().unwrap_or(usize::MAX),
-			is_vertex_or_fragment_shader: !matches!(
-				asset_type,
-				PackFileAssetType::TranslationUnitSegment
-			),
+			is_vertex_or_fragment_shader,
+			shader_path: pack_file_context.relative_path.as_str().to_string(),
+			importable_shaders: Arc::clone(pack_file_context.pack_index.importable_shaders()),
+			imports_shaders,
 			optimization_settings
 		})
 	}
 }
 
+/// The optimizations that may be applied to the syntax tree of a minified translation unit.
+/// They are only safe for top-level translation units whose preprocessor directives were
+/// expanded, as other shaders may depend on any of their symbols.
+#[derive(Clone, Copy, Default)]
+struct SyntaxTreeOptimizations {
+	eliminate_dead_code: bool,
+	mangle_identifiers: bool
+}
+
+impl SyntaxTreeOptimizations {
+	/// Returns the syntax tree optimizations that the specified settings enable for a
+	/// top-level shader.
+	fn for_top_level_shader(optimization_settings: &ShaderFileOptions) -> Self {
+		Self {
+			eliminate_dead_code: optimization_settings.dead_code_elimination,
+			mangle_identifiers: optimization_settings.identifier_mangling
+		}
+	}
+
+	/// Applies these optimizations to the specified translation unit. Constant expressions
+	/// are folded before eliminating dead code, so that code that becomes unreachable due
+	/// to that folding is eliminated too, and identifiers are mangled last, so that the
+	/// shortest identifiers are given to the remaining symbols.
+	fn apply(self, translation_unit: &mut TranslationUnit) {
+		if self.eliminate_dead_code {
+			constant_folder::fold_constants(translation_unit);
+			dead_code_eliminator::eliminate_dead_code(translation_unit);
+		}
+
+		if self.mangle_identifiers {
+			identifier_mangler::mangle_identifiers(translation_unit);
+		}
+	}
+}
+
 /// Processes the shader code at the specified source buffer, trying to parse it as `T`.
 /// An error is returned when the source can't be parsed as the specified symbol, which
 /// may or may not be a format error depending on how the source is interpreted by Minecraft.
+/// If the source is a translation unit that is minified, the specified syntax tree
+/// optimizations are applied to it.
 fn process_shader_as<T: Extractable<TranslationUnit> + 'static>(
 	src: &mut BytesMut,
 	shader_parser: &Parser,
 	is_top_level_translation_unit: bool,
+	syntax_tree_optimizations: SyntaxTreeOptimizations,
 	source_transformation_strategy: ShaderSourceTransformationStrategy
 ) -> Result<Option<<OptimizerDecoder as Decoder>::Item>, OptimizationError>
 where
@@ -184,7 +272,7 @@ where
 		source_transformation_strategy
 	) {
 		(
-			Ok(Some(symbol)),
+			Ok(Some(mut symbol)),
 			ShaderSourceTransformationStrategy::Minify | ShaderSourceTransformationStrategy::Prettify
 		) => {
 			// The shader is valid and safe to transform, and we want to transform it
@@ -193,10 +281,29 @@ where
 				ShaderSourceTransformationStrategy::Minify
 			);
 
+			if minify {
+				if let Some(translation_unit) =
+					(&mut *symbol as &mut dyn Any).downcast_mut::<TranslationUnit>()
+				{
+					syntax_tree_optimizations.apply(translation_unit);
+				}
+			}
+
+			let transpiled_source = symbol.transpile(minify);
+
+			// Minified shaders may not be smaller than their source, for example if it was already
+			// minified or imports big shaders. The source is valid, so keep it then
+			if minify && transpiled_source.len() >= src.len() {
+				return Ok(Some((
+					Cow::Borrowed("Validated and copied"),
+					src.split_off(0)
+				)));
+			}
+
 			let mut buf = src.split_off(0);
 			buf.clear();
 
-			buf.extend_from_slice(symbol.transpile(minify).as_bytes());
+			buf.extend_from_slice(transpiled_source.as_bytes());
 
 			Ok(Some((
 				Cow::Borrowed(if minify { "Minified" } else { "Prettified" }),
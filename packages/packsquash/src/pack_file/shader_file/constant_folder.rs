@@ -0,0 +1,728 @@
+//! Contains code to fold constant expressions of GLSL translation units, and remove the
+//! statements that become unreachable due to that folding.
+
+use ahash::AHashMap;
+use glsl_lang::ast::{
+	ArraySpecifier, ArraySpecifierDimensionData, BinaryOp, BinaryOpData, CaseLabelData,
+	CompoundStatementData, Condition, ConditionData, Declaration, DeclarationData, Expr, ExprData,
+	ExprStatementData, ExternalDeclarationData, ForInitStatementData, FullySpecifiedType,
+	FunIdentifierData, FunctionParameterDeclarationData, FunctionPrototype, InitDeclaratorListData,
+	Initializer, InitializerData, IterationStatementData, JumpStatementData,
+	SelectionRestStatementData, SmolStr, Statement, StatementData, StorageQualifierData,
+	TranslationUnit, TypeQualifierSpecData, TypeSpecifier, TypeSpecifierNonArrayData, UnaryOp,
+	UnaryOpData
+};
+use glsl_lang::transpiler::glsl::{show_expr, FormattingSettings, FormattingState};
+
+/// Folds the constant expressions of the specified top-level translation unit, replacing them
+/// with their values. Scalar `const` variables initialized to constant values are replaced
+/// with those values too. Branches of `if` statements and ternary expressions whose condition
+/// is a constant that makes them unreachable, `while` loops whose condition is constantly
+/// false, and statements after `return`, `break` and `continue` are removed.
+///
+/// Expressions are only folded when their value can be computed exactly as GLSL specifies,
+/// and can be represented as a literal without changing its type, so the semantics of the
+/// translation unit are preserved. Expressions are not replaced with literals that are longer
+/// than them, such as floats with many digits, but their values are still used to fold the
+/// expressions that contain them. Preprocessor directives must have been expanded before
+/// calling this function, as they are not considered.
+pub fn fold_constants(translation_unit: &mut TranslationUnit) {
+	let mut constant_folder = ConstantFolder {
+		scopes: vec![AHashMap::new()]
+	};
+
+	for external_declaration in &mut translation_unit.0 {
+		match &mut external_declaration.content {
+			ExternalDeclarationData::FunctionDefinition(function_definition) => {
+				// The parameters of a function share their scope with its body
+				constant_folder.in_new_scope(|constant_folder| {
+					constant_folder.fold_function_prototype(&mut function_definition.prototype);
+					constant_folder
+						.fold_statement_list(&mut function_definition.statement.statement_list);
+				})
+			}
+			ExternalDeclarationData::Declaration(declaration) => {
+				constant_folder.fold_declaration(declaration);
+			}
+			ExternalDeclarationData::Preprocessor(_) => {}
+		}
+	}
+}
+
+/// The value of a constant scalar expression.
+#[derive(Clone, Copy)]
+enum Constant {
+	Bool(bool),
+	Int(i32),
+	UInt(u32),
+	Float(f32)
+}
+
+impl Constant {
+	/// Returns the value of the specified expression, if it is a literal, or a literal
+	/// negated with the unary minus operator.
+	fn of(expression: &Expr) -> Option<Self> {
+		match &expression.content {
+			ExprData::BoolConst(value) => Some(Self::Bool(*value)),
+			ExprData::IntConst(value) => Some(Self::Int(*value)),
+			ExprData::UIntConst(value) => Some(Self::UInt(*value)),
+			ExprData::FloatConst(value) => Some(Self::Float(*value)),
+			ExprData::Unary(operator, operand) if **operator == UnaryOpData::Minus => {
+				Self::of(operand)?.unary(operator)
+			}
+			_ => None
+		}
+	}
+
+	/// Returns the literal expression that represents this value, if any.
+	///
+	/// Negative values are not represented as literals: they would need to be negated with
+	/// the unary minus operator, and minified code can't tell that operator apart from the
+	/// decrement operator when it follows a binary minus operator.
+	fn to_expression(self) -> Option<ExprData> {
+		match self {
+			Self::Bool(value) => Some(ExprData::BoolConst(value)),
+			Self::Int(value) => (value >= 0).then_some(ExprData::IntConst(value)),
+			Self::UInt(value) => Some(ExprData::UIntConst(value)),
+			Self::Float(value) => {
+				(value.is_finite() && value.is_sign_positive()).then_some(ExprData::FloatConst(value))
+			}
+		}
+	}
+
+	/// Returns whether this value can initialize a variable of the specified type, without
+	/// implicit conversions.
+	fn has_type(self, type_specifier: &TypeSpecifier) -> bool {
+		type_specifier.array_specifier.is_none()
+			&& matches!(
+				(self, &type_specifier.ty.content),
+				(Self::Bool(_), TypeSpecifierNonArrayData::Bool)
+					| (Self::Int(_), TypeSpecifierNonArrayData::Int)
+					| (Self::UInt(_), TypeSpecifierNonArrayData::UInt)
+					| (Self::Float(_), TypeSpecifierNonArrayData::Float)
+			)
+	}
+
+	/// Applies the specified unary operator to this value, as GLSL does.
+	fn unary(self, operator: &UnaryOp) -> Option<Self> {
+		match (&operator.content, self) {
+			(UnaryOpData::Add, value) => Some(value),
+			(UnaryOpData::Minus, Self::Int(value)) => Some(Self::Int(value.wrapping_neg())),
+			(UnaryOpData::Minus, Self::UInt(value)) => Some(Self::UInt(value.wrapping_neg())),
+			(UnaryOpData::Minus, Self::Float(value)) => Some(Self::Float(-value)),
+			(UnaryOpData::Not, Self::Bool(value)) => Some(Self::Bool(!value)),
+			(UnaryOpData::Complement, Self::Int(value)) => Some(Self::Int(!value)),
+			(UnaryOpData::Complement, Self::UInt(value)) => Some(Self::UInt(!value)),
+			_ => None
+		}
+	}
+
+	/// Applies the specified binary operator to this value and another one, as GLSL does.
+	/// Values of different types are not operated on, as GLSL would implicitly convert them.
+	/// Operations whose result GLSL leaves undefined or implementation-dependent, such as
+	/// divisions by zero or shifts by the bit width or more, are not computed either.
+	fn binary(self, operator: &BinaryOp, other: Self) -> Option<Self> {
+		use BinaryOpData::*;
+
+		Some(match (self, &operator.content, other) {
+			(Self::Bool(a), Or, Self::Bool(b)) => Self::Bool(a || b),
+			(Self::Bool(a), Xor | NonEqual, Self::Bool(b)) => Self::Bool(a != b),
+			(Self::Bool(a), And, Self::Bool(b)) => Self::Bool(a && b),
+			(Self::Bool(a), Equal, Self::Bool(b)) => Self::Bool(a == b),
+			(Self::Int(a), operator, Self::Int(b)) => match operator {
+				BitOr => Self::Int(a | b),
+				BitXor => Self::Int(a ^ b),
+				BitAnd => Self::Int(a & b),
+				Equal => Self::Bool(a == b),
+				NonEqual => Self::Bool(a != b),
+				Lt => Self::Bool(a < b),
+				Gt => Self::Bool(a > b),
+				Lte => Self::Bool(a <= b),
+				Gte => Self::Bool(a >= b),
+				LShift if (0..32).contains(&b) => Self::Int(a << b),
+				RShift if (0..32).contains(&b) => Self::Int(a >> b),
+				Add => Self::Int(a.wrapping_add(b)),
+				Sub => Self::Int(a.wrapping_sub(b)),
+				Mult => Self::Int(a.wrapping_mul(b)),
+				// The results of divisions with negative operands are not fully specified
+				Div if a >= 0 && b > 0 => Self::Int(a / b),
+				Mod if a >= 0 && b > 0 => Self::Int(a % b),
+				_ => return None
+			},
+			(Self::UInt(a), operator, Self::UInt(b)) => match operator {
+				BitOr => Self::UInt(a | b),
+				BitXor => Self::UInt(a ^ b),
+				BitAnd => Self::UInt(a & b),
+				Equal => Self::Bool(a == b),
+				NonEqual => Self::Bool(a != b),
+				Lt => Self::Bool(a < b),
+				Gt => Self::Bool(a > b),
+				Lte => Self::Bool(a <= b),
+				Gte => Self::Bool(a >= b),
+				LShift if b < 32 => Self::UInt(a << b),
+				RShift if b < 32 => Self::UInt(a >> b),
+				Add => Self::UInt(a.wrapping_add(b)),
+				Sub => Self::UInt(a.wrapping_sub(b)),
+				Mult => Self::UInt(a.wrapping_mul(b)),
+				Div if b > 0 => Self::UInt(a / b),
+				Mod if b > 0 => Self::UInt(a % b),
+				_ => return None
+			},
+			(Self::Float(a), operator, Self::Float(b)) => match operator {
+				Equal => Self::Bool(a == b),
+				NonEqual => Self::Bool(a != b),
+				Lt => Self::Bool(a < b),
+				Gt => Self::Bool(a > b),
+				Lte => Self::Bool(a <= b),
+				Gte => Self::Bool(a >= b),
+				Add => Self::Float(a + b),
+				Sub => Self::Float(a - b),
+				Mult => Self::Float(a * b),
+				Div if b != 0.0 => Self::Float(a / b),
+				_ => return None
+			},
+			_ => return None
+		})
+	}
+}
+
+/// A variable declared in some scope of a translation unit.
+struct ScopedVariable {
+	/// The constant value of the variable, if it is a `const` variable with a known value.
+	value: Option<Constant>,
+	/// Whether the variable is referenced in places where it can't be replaced with its value,
+	/// so its declaration is needed.
+	referenced_by_name: bool
+}
+
+/// Folds the constant expressions of a translation unit, keeping track of the scopes of
+/// the variables declared in it.
+struct ConstantFolder {
+	scopes: Vec<AHashMap<SmolStr, ScopedVariable>>
+}
+
+impl ConstantFolder {
+	/// Calls the specified function in a new innermost scope.
+	fn in_new_scope(&mut self, f: impl FnOnce(&mut Self)) {
+		self.scopes.push(AHashMap::new());
+		f(self);
+		self.scopes.pop();
+	}
+
+	/// Adds the specified newly declared variable to the innermost scope.
+	fn declare_variable(&mut self, name: &SmolStr, value: Option<Constant>) {
+		self.scopes.last_mut().unwrap().insert(
+			name.clone(),
+			ScopedVariable {
+				value,
+				referenced_by_name: false
+			}
+		);
+	}
+
+	/// Returns the variable with the specified name that is visible from the innermost scope.
+	fn variable(&mut self, name: &SmolStr) -> Option<&mut ScopedVariable> {
+		self.scopes
+			.iter_mut()
+			.rev()
+			.find_map(|scope| scope.get_mut(name))
+	}
+
+	/// Returns the value of the specified folded expression, if it is constant. Unlike
+	/// [Constant::of], this also considers the values of `const` variables and operations
+	/// that were not replaced with literals, because the literals would be longer.
+	fn value(&self, expression: &Expr) -> Option<Constant> {
+		match &expression.content {
+			ExprData::Variable(identifier) => {
+				self.scopes
+					.iter()
+					.rev()
+					.find_map(|scope| scope.get(&identifier.0))?
+					.value
+			}
+			ExprData::Unary(operator, operand) => self.value(operand)?.unary(operator),
+			ExprData::Binary(operator, left_operand, right_operand) => self
+				.value(left_operand)?
+				.binary(operator, self.value(right_operand)?),
+			_ => Constant::of(expression)
+		}
+	}
+
+	/// Returns whether the specified statement declares local `const` variables that are no
+	/// longer needed, as every reference to them was replaced with their value.
+	fn declares_unneeded_constants(&self, statement: &Statement) -> bool {
+		let StatementData::Declaration(declaration) = &statement.content else {
+			return false;
+		};
+		let DeclarationData::InitDeclaratorList(init_declarator_list) = &declaration.content else {
+			return false;
+		};
+		let Some(name) = &init_declarator_list.head.name else {
+			return false;
+		};
+
+		let innermost_scope = self.scopes.last().unwrap();
+		self.scopes.len() > 1
+			&& is_const(&init_declarator_list.head.ty)
+			&& [name]
+				.into_iter()
+				.chain(
+					init_declarator_list
+						.tail
+						.iter()
+						.map(|declaration| &declaration.ident.ident)
+				)
+				.all(|name| {
+					matches!(
+						innermost_scope.get(&name.0),
+						Some(ScopedVariable {
+							value: Some(_),
+							referenced_by_name: false
+						})
+					)
+				})
+	}
+
+	fn fold_function_prototype(&mut self, function_prototype: &mut FunctionPrototype) {
+		for parameter in &mut function_prototype.parameters {
+			match &mut parameter.content {
+				FunctionParameterDeclarationData::Named(_, declarator) => {
+					self.fold_type_specifier(&mut declarator.ty);
+					self.fold_array_specifier(declarator.ident.array_spec.as_mut());
+					self.declare_variable(&declarator.ident.ident.0, None);
+				}
+				FunctionParameterDeclarationData::Unnamed(_, type_specifier) => {
+					self.fold_type_specifier(type_specifier)
+				}
+			}
+		}
+	}
+
+	fn fold_declaration(&mut self, declaration: &mut Declaration) {
+		let DeclarationData::InitDeclaratorList(init_declarator_list) = &mut declaration.content
+		else {
+			// Function prototypes, precision declarations, interface blocks and invariant
+			// declarations don't declare variables with constant values
+			return;
+		};
+
+		let InitDeclaratorListData { head, tail } = &mut init_declarator_list.content;
+		self.fold_type_specifier(&mut head.ty.ty);
+		self.fold_array_specifier(head.array_specifier.as_mut());
+
+		// The scope of a variable starts right after its declarator, so its initializer may
+		// reference a variable it shadows
+		if let Some(initializer) = &mut head.initializer {
+			self.fold_initializer(initializer);
+		}
+		if let Some(name) = &head.name {
+			let value = head.initializer.as_ref().and_then(|initializer| {
+				self.initializer_value(initializer, &head.ty, head.array_specifier.is_none())
+			});
+
+			self.declare_variable(&name.0, value);
+		}
+
+		for declaration in tail {
+			self.fold_array_specifier(declaration.ident.array_spec.as_mut());
+
+			if let Some(initializer) = &mut declaration.initializer {
+				self.fold_initializer(initializer);
+			}
+			let value = declaration.initializer.as_ref().and_then(|initializer| {
+				self.initializer_value(
+					initializer,
+					&head.ty,
+					declaration.ident.array_spec.is_none()
+				)
+			});
+
+			self.declare_variable(&declaration.ident.ident.0, value);
+		}
+	}
+
+	/// Folds the constant expressions of the specified list of statements, removing the
+	/// statements that are no longer needed or unreachable.
+	fn fold_statement_list(&mut self, statements: &mut Vec<Statement>) {
+		statements.retain_mut(|statement| self.fold_statement(statement));
+
+		// Whether local constants are referenced by name is only known after folding every
+		// statement in their scope
+		statements.retain(|statement| !self.declares_unneeded_constants(statement));
+
+		// Statements after a jump statement that leaves the current block can't be reached.
+		// Discards are not considered, as they may not terminate helper invocations
+		if let Some(jump_statement_index) = statements.iter().position(|statement| {
+			matches!(
+				&statement.content,
+				StatementData::Jump(jump_statement) if !matches!(
+					jump_statement.content,
+					JumpStatementData::Discard
+				)
+			)
+		}) {
+			statements.truncate(jump_statement_index + 1);
+		}
+	}
+
+	/// Folds the constant expressions of the specified statement, returning whether the
+	/// statement is still needed.
+	fn fold_statement(&mut self, statement: &mut Statement) -> bool {
+		match &mut statement.content {
+			StatementData::Declaration(declaration) => self.fold_declaration(declaration),
+			StatementData::Expression(expression_statement) => {
+				if let Some(expression) = &mut expression_statement.0 {
+					self.fold_expression(expression);
+				}
+			}
+			StatementData::Selection(selection_statement) => {
+				self.fold_expression(&mut selection_statement.cond);
+
+				// Only the branch selected by a constant condition is kept, in its own scope
+				let selected_statement = match (
+					&selection_statement.rest.content,
+					self.value(&selection_statement.cond)
+				) {
+					(
+						SelectionRestStatementData::Statement(statement),
+						Some(Constant::Bool(true))
+					)
+					| (SelectionRestStatementData::Else(statement, _), Some(Constant::Bool(true)))
+					| (SelectionRestStatementData::Else(_, statement), Some(Constant::Bool(false))) => {
+						Some(Some((**statement).clone()))
+					}
+					(SelectionRestStatementData::Statement(_), Some(Constant::Bool(false))) => {
+						Some(None)
+					}
+					_ => None
+				};
+
+				match selected_statement {
+					Some(Some(mut selected_statement)) => {
+						if !self.fold_sub_statement(&mut selected_statement) {
+							return false;
+						}
+
+						statement.content = match selected_statement.content {
+							StatementData::Declaration(_) => StatementData::Compound(
+								CompoundStatementData {
+									statement_list: vec![selected_statement]
+								}
+								.into()
+							),
+							content => content
+						};
+					}
+					Some(None) => return false,
+					None => match &mut selection_statement.rest.content {
+						SelectionRestStatementData::Statement(statement) => {
+							self.fold_sub_statement(statement);
+						}
+						SelectionRestStatementData::Else(statement, else_statement) => {
+							self.fold_sub_statement(statement);
+							self.fold_sub_statement(else_statement);
+						}
+					}
+				}
+			}
+			StatementData::Switch(switch_statement) => {
+				self.fold_expression(&mut switch_statement.head);
+
+				// Statements after jump statements may be reachable through case labels, so
+				// switch bodies are not handled as regular statement lists
+				self.in_new_scope(|constant_folder| {
+					switch_statement
+						.body
+						.retain_mut(|statement| constant_folder.fold_statement(statement))
+				});
+			}
+			StatementData::CaseLabel(case_label) => {
+				if let CaseLabelData::Case(expression) = &mut case_label.content {
+					self.fold_expression(expression);
+				}
+			}
+			StatementData::Iteration(iteration_statement) => match &mut iteration_statement.content {
+				IterationStatementData::While(condition, statement) => {
+					let mut is_needed = true;
+
+					self.in_new_scope(|constant_folder| {
+						constant_folder.fold_condition(condition);
+
+						is_needed = !matches!(
+							&condition.content,
+							ConditionData::Expr(expression)
+								if matches!(constant_folder.value(expression), Some(Constant::Bool(false)))
+						);
+						if is_needed {
+							constant_folder.fold_sub_statement(statement);
+						}
+					});
+
+					return is_needed;
+				}
+				IterationStatementData::DoWhile(statement, expression) => {
+					self.fold_sub_statement(statement);
+					self.fold_expression(expression);
+				}
+				IterationStatementData::For(init_statement, rest_statement, statement) => self
+					.in_new_scope(|constant_folder| {
+						match &mut init_statement.content {
+							ForInitStatementData::Expression(Some(expression)) => {
+								constant_folder.fold_expression(expression)
+							}
+							ForInitStatementData::Expression(None) => {}
+							ForInitStatementData::Declaration(declaration) => {
+								constant_folder.fold_declaration(declaration)
+							}
+						}
+
+						if let Some(condition) = &mut rest_statement.condition {
+							constant_folder.fold_condition(condition);
+						}
+						if let Some(expression) = &mut rest_statement.post_expr {
+							constant_folder.fold_expression(expression);
+						}
+
+						constant_folder.fold_sub_statement(statement);
+					})
+			},
+			StatementData::Jump(jump_statement) => {
+				if let JumpStatementData::Return(Some(expression)) = &mut jump_statement.content {
+					self.fold_expression(expression);
+				}
+			}
+			StatementData::Compound(compound_statement) => self.in_new_scope(|constant_folder| {
+				constant_folder.fold_statement_list(&mut compound_statement.statement_list)
+			})
+		}
+
+		true
+	}
+
+	/// Folds the constant expressions of the specified statement, which is nested in a
+	/// selection or iteration statement and has its own scope. If the statement is no longer
+	/// needed, it is replaced with an empty statement, as such statements must be present.
+	/// Returns whether the statement was needed.
+	fn fold_sub_statement(&mut self, statement: &mut Statement) -> bool {
+		let mut is_needed = true;
+		self.in_new_scope(|constant_folder| is_needed = constant_folder.fold_statement(statement));
+
+		if !is_needed {
+			statement.content = StatementData::Expression(ExprStatementData(None).into());
+		}
+
+		is_needed
+	}
+
+	fn fold_condition(&mut self, condition: &mut Condition) {
+		match &mut condition.content {
+			ConditionData::Expr(expression) => self.fold_expression(expression),
+			ConditionData::Assignment(fully_specified_type, identifier, initializer) => {
+				self.fold_type_specifier(&mut fully_specified_type.ty);
+				self.fold_initializer(initializer);
+				self.declare_variable(&identifier.0, None);
+			}
+		}
+	}
+
+	fn fold_initializer(&mut self, initializer: &mut Initializer) {
+		match &mut initializer.content {
+			InitializerData::Simple(expression) => self.fold_expression(expression),
+			InitializerData::List(initializers) => {
+				for initializer in initializers {
+					self.fold_initializer(initializer);
+				}
+			}
+		}
+	}
+
+	fn fold_type_specifier(&mut self, type_specifier: &mut TypeSpecifier) {
+		self.fold_array_specifier(type_specifier.array_specifier.as_mut());
+
+		if let TypeSpecifierNonArrayData::Struct(struct_specifier) = &mut type_specifier.ty.content {
+			for field in &mut struct_specifier.fields {
+				self.fold_type_specifier(&mut field.ty);
+
+				for identifier in &mut field.identifiers {
+					self.fold_array_specifier(identifier.array_spec.as_mut());
+				}
+			}
+		}
+	}
+
+	fn fold_array_specifier(&mut self, array_specifier: Option<&mut ArraySpecifier>) {
+		for dimension in array_specifier
+			.into_iter()
+			.flat_map(|array_specifier| &mut array_specifier.dimensions)
+		{
+			if let ArraySpecifierDimensionData::ExplicitlySized(expression) = &mut dimension.content {
+				self.fold_expression(expression);
+			}
+		}
+	}
+
+	fn fold_expression(&mut self, expression: &mut Expr) {
+		let folded_expression = match &mut expression.content {
+			ExprData::Variable(identifier) => {
+				let name_length = identifier.0.len();
+				self.variable(&identifier.0).and_then(|variable| {
+					let value = variable
+						.value
+						.and_then(Constant::to_expression)
+						.filter(|value| minified_length(&Expr::from(value.clone())) <= name_length);
+					variable.referenced_by_name |= value.is_none();
+					value
+				})
+			}
+			ExprData::IntConst(_)
+			| ExprData::UIntConst(_)
+			| ExprData::BoolConst(_)
+			| ExprData::FloatConst(_)
+			| ExprData::DoubleConst(_) => None,
+			ExprData::Unary(operator, operand) => {
+				self.fold_expression(operand);
+
+				// Negated literals are already as folded as they can be
+				self.value(operand)
+					.and_then(|operand| operand.unary(operator))
+					.and_then(Constant::to_expression)
+			}
+			ExprData::Binary(operator, left_operand, right_operand) => {
+				self.fold_expression(left_operand);
+				self.fold_expression(right_operand);
+
+				match (
+					self.value(left_operand),
+					&operator.content,
+					self.value(right_operand)
+				) {
+					// The right operand of logical operators is not evaluated when the left
+					// operand determines the result, and it is the result otherwise
+					(Some(Constant::Bool(false)), BinaryOpData::And, _) => {
+						Some(ExprData::BoolConst(false))
+					}
+					(Some(Constant::Bool(true)), BinaryOpData::Or, _) => {
+						Some(ExprData::BoolConst(true))
+					}
+					(Some(Constant::Bool(true)), BinaryOpData::And, _)
+					| (Some(Constant::Bool(false)), BinaryOpData::Or, _) => Some(right_operand.content.clone()),
+					(Some(left_operand), _, Some(right_operand)) => left_operand
+						.binary(operator, right_operand)
+						.and_then(Constant::to_expression),
+					_ => None
+				}
+			}
+			ExprData::Ternary(condition, true_expression, false_expression) => {
+				self.fold_expression(condition);
+
+				// Only the selected expression is evaluated
+				match self.value(condition) {
+					Some(Constant::Bool(true)) => {
+						self.fold_expression(true_expression);
+						Some(true_expression.content.clone())
+					}
+					Some(Constant::Bool(false)) => {
+						self.fold_expression(false_expression);
+						Some(false_expression.content.clone())
+					}
+					_ => {
+						self.fold_expression(true_expression);
+						self.fold_expression(false_expression);
+						None
+					}
+				}
+			}
+			ExprData::Assignment(left_expression, _, right_expression)
+			| ExprData::Bracket(left_expression, right_expression)
+			| ExprData::Comma(left_expression, right_expression) => {
+				self.fold_expression(left_expression);
+				self.fold_expression(right_expression);
+				None
+			}
+			ExprData::PostInc(expression) | ExprData::PostDec(expression) => {
+				self.fold_expression(expression);
+				None
+			}
+			ExprData::Dot(expression, _) => {
+				// Selecting fields of literals is not valid, so variables are kept as-is
+				if let ExprData::Variable(identifier) = &expression.content {
+					if let Some(variable) = self.variable(&identifier.0) {
+						variable.referenced_by_name = true;
+					}
+				} else {
+					self.fold_expression(expression);
+				}
+				None
+			}
+			ExprData::FunCall(function_identifier, arguments) => {
+				match &mut function_identifier.content {
+					FunIdentifierData::Expr(expression) => {
+						if !matches!(expression.content, ExprData::Variable(_)) {
+							self.fold_expression(expression);
+						}
+					}
+					FunIdentifierData::TypeSpecifier(type_specifier) => {
+						self.fold_type_specifier(type_specifier)
+					}
+				}
+
+				for argument in arguments {
+					self.fold_expression(argument);
+				}
+				None
+			}
+		};
+
+		// Literals may be longer than the expressions they would replace
+		if let Some(folded_expression) = folded_expression.map(Expr::from) {
+			if minified_length(&folded_expression) <= minified_length(expression) {
+				expression.content = folded_expression.content;
+			}
+		}
+	}
+
+	/// Returns the constant value that the specified initializer gives to a variable of the
+	/// specified type, if the variable is `const`, is not an array and its value is known.
+	fn initializer_value(
+		&self,
+		initializer: &Initializer,
+		fully_specified_type: &FullySpecifiedType,
+		is_not_array: bool
+	) -> Option<Constant> {
+		match &initializer.content {
+			InitializerData::Simple(expression) if is_not_array && is_const(fully_specified_type) => {
+				self.value(expression)
+					.filter(|value| value.has_type(&fully_specified_type.ty))
+			}
+			_ => None
+		}
+	}
+}
+
+/// Returns whether the specified type is `const`-qualified.
+fn is_const(fully_specified_type: &FullySpecifiedType) -> bool {
+	fully_specified_type
+		.qualifier
+		.iter()
+		.flat_map(|qualifier| &qualifier.qualifiers)
+		.any(|qualifier| {
+			matches!(
+				&qualifier.content,
+				TypeQualifierSpecData::Storage(storage_qualifier)
+					if storage_qualifier.content == StorageQualifierData::Const
+			)
+		})
+}
+
+/// Returns the length of the minified GLSL code of the specified expression.
+fn minified_length(expression: &Expr) -> usize {
+	let mut code = String::new();
+	show_expr(
+		&mut code,
+		expression,
+		&mut FormattingState::from(&FormattingSettings::minifying())
+	)
+	.ok();
+
+	code.len()
+}
@@ -0,0 +1,219 @@
+//! Contains code to remove the functions and global variables of GLSL translation units that
+//! do not affect their behavior.
+
+use ahash::AHashSet;
+use glsl_lang::ast::{
+	DeclarationData, Expr, ExprData, ExternalDeclaration, ExternalDeclarationData, FunIdentifierData,
+	InitDeclaratorList, SmolStr, StorageQualifierData, TranslationUnit, TypeQualifierSpecData,
+	TypeSpecifierNonArrayData, UnaryOpData
+};
+use glsl_lang::visitor::{Host, Visit, Visitor};
+
+/// The name of the function that is the entry point of a shader.
+const ENTRY_POINT_FUNCTION: &str = "main";
+
+/// Removes the functions that are unreachable from the entry point of the specified top-level
+/// translation unit, and the global variables that reachable code does not reference.
+///
+/// Only global variables without qualifiers, or with the `const` qualifier, and whose
+/// initializers don't have side effects, are removed. Other global variables, such as
+/// uniforms, samplers and `in` or `out` interface variables, are part of the interface of
+/// the shader, so they are always kept. Preprocessor directives must have been expanded
+/// before calling this function, as they are not considered.
+pub fn eliminate_dead_code(translation_unit: &mut TranslationUnit) {
+	let function_names = translation_unit
+		.0
+		.iter()
+		.filter_map(|external_declaration| function_name(external_declaration).cloned())
+		.collect::<AHashSet<_>>();
+
+	let symbols = translation_unit
+		.0
+		.iter()
+		.map(|external_declaration| Symbol::of(external_declaration, &function_names))
+		.collect::<Vec<_>>();
+
+	// Find the symbols reachable from the entry point and the declarations that are always
+	// kept, following the references in every reachable declaration until no more are found
+	let mut referenced_names = AHashSet::from_iter([SmolStr::from(ENTRY_POINT_FUNCTION)]);
+	let mut is_reachable = vec![false; symbols.len()];
+	let mut found_reachable_declarations = true;
+	while found_reachable_declarations {
+		found_reachable_declarations = false;
+
+		for (external_declaration, (symbol, is_reachable)) in translation_unit
+			.0
+			.iter()
+			.zip(symbols.iter().zip(&mut is_reachable))
+		{
+			if !*is_reachable && symbol.is_reachable(&referenced_names) {
+				*is_reachable = true;
+				found_reachable_declarations = true;
+
+				let mut reference_collector = ReferenceCollector {
+					referenced_names: &mut referenced_names
+				};
+				external_declaration.visit(&mut reference_collector);
+			}
+		}
+	}
+
+	let mut is_reachable = is_reachable.into_iter();
+	translation_unit
+		.0
+		.retain(|_| is_reachable.next().unwrap_or(true));
+}
+
+/// A symbol of a translation unit that may be removed if it is not reachable.
+enum Symbol {
+	/// A function definition or prototype, with the specified name.
+	Function(SmolStr),
+	/// A declaration of global variables without side effects, with the specified names.
+	GlobalVariables(Vec<SmolStr>),
+	/// A declaration that is always kept.
+	Kept
+}
+
+impl Symbol {
+	/// Returns the symbol that the specified external declaration defines.
+	fn of(external_declaration: &ExternalDeclaration, function_names: &AHashSet<SmolStr>) -> Self {
+		if let Some(function_name) = function_name(external_declaration) {
+			return Self::Function(function_name.clone());
+		}
+
+		match &external_declaration.content {
+			ExternalDeclarationData::Declaration(declaration) => match &declaration.content {
+				DeclarationData::InitDeclaratorList(init_declarator_list)
+					if is_removable_global_variable_declaration(
+						init_declarator_list,
+						function_names
+					) =>
+				{
+					Self::GlobalVariables(
+						init_declarator_list
+							.head
+							.name
+							.iter()
+							.chain(
+								init_declarator_list
+									.tail
+									.iter()
+									.map(|declaration| &declaration.ident.ident)
+							)
+							.map(|name| name.0.clone())
+							.collect()
+					)
+				}
+				_ => Self::Kept
+			},
+			_ => Self::Kept
+		}
+	}
+
+	/// Returns whether this symbol is reachable, given the names referenced by the code
+	/// known to be reachable.
+	fn is_reachable(&self, referenced_names: &AHashSet<SmolStr>) -> bool {
+		match self {
+			Self::Function(name) => referenced_names.contains(name),
+			Self::GlobalVariables(names) => names.iter().any(|name| referenced_names.contains(name)),
+			Self::Kept => true
+		}
+	}
+}
+
+/// Returns the name of the function defined or declared by the specified external
+/// declaration, if any.
+fn function_name(external_declaration: &ExternalDeclaration) -> Option<&SmolStr> {
+	match &external_declaration.content {
+		ExternalDeclarationData::FunctionDefinition(function_definition) => {
+			Some(&function_definition.prototype.name.0)
+		}
+		ExternalDeclarationData::Declaration(declaration) => match &declaration.content {
+			DeclarationData::FunctionPrototype(function_prototype) => {
+				Some(&function_prototype.name.0)
+			}
+			_ => None
+		},
+		ExternalDeclarationData::Preprocessor(_) => None
+	}
+}
+
+/// Returns whether the specified global variable declaration can be removed if the variables
+/// it declares are not referenced: it must only declare private variables, which may be
+/// `const`, without declaring a struct type, and its initializers must not have side effects.
+fn is_removable_global_variable_declaration(
+	init_declarator_list: &InitDeclaratorList,
+	function_names: &AHashSet<SmolStr>
+) -> bool {
+	let head = &init_declarator_list.head;
+
+	let has_only_const_qualifiers = head
+		.ty
+		.qualifier
+		.iter()
+		.flat_map(|qualifier| &qualifier.qualifiers)
+		.all(|qualifier| {
+			matches!(
+				&qualifier.content,
+				TypeQualifierSpecData::Storage(storage_qualifier)
+					if storage_qualifier.content == StorageQualifierData::Const
+			)
+		});
+
+	let mut side_effect_detector = SideEffectDetector {
+		function_names,
+		has_side_effects: false
+	};
+	init_declarator_list.visit(&mut side_effect_detector);
+
+	head.name.is_some()
+		&& has_only_const_qualifiers
+		&& !matches!(head.ty.ty.ty.content, TypeSpecifierNonArrayData::Struct(_))
+		&& !side_effect_detector.has_side_effects
+}
+
+/// A visitor that collects the names of the variables and functions referenced in a GLSL
+/// symbol. Local variables that shadow global symbols are not told apart from them, so the
+/// collected names are a superset of the global symbols actually referenced.
+struct ReferenceCollector<'names> {
+	referenced_names: &'names mut AHashSet<SmolStr>
+}
+
+impl Visitor for ReferenceCollector<'_> {
+	fn visit_expr(&mut self, expression: &Expr) -> Visit {
+		if let ExprData::Variable(identifier) = &expression.content {
+			self.referenced_names.insert(identifier.0.clone());
+		}
+
+		Visit::Children
+	}
+}
+
+/// A visitor that detects whether evaluating a GLSL symbol may have side effects, i.e.,
+/// whether it assigns, increments or decrements variables, or calls functions defined in
+/// the translation unit. Built-in functions and constructors don't have side effects.
+struct SideEffectDetector<'names> {
+	function_names: &'names AHashSet<SmolStr>,
+	has_side_effects: bool
+}
+
+impl Visitor for SideEffectDetector<'_> {
+	fn visit_expr(&mut self, expression: &Expr) -> Visit {
+		self.has_side_effects |= match &expression.content {
+			ExprData::Assignment(..) | ExprData::PostInc(_) | ExprData::PostDec(_) => true,
+			ExprData::Unary(operator, _) => {
+				matches!(operator.content, UnaryOpData::Inc | UnaryOpData::Dec)
+			}
+			ExprData::FunCall(function_identifier, _) => matches!(
+				&function_identifier.content,
+				FunIdentifierData::Expr(expression) if !matches!(
+					&expression.content,
+					ExprData::Variable(identifier) if !self.function_names.contains(&identifier.0)
+				)
+			),
+			_ => false
+		};
+
+		Visit::Children
+	}
+}
@@ -0,0 +1,7 @@
+#define FOG_INTENSITY 0.5
+
+#moj_import <minecraft:example_import.glsl>
+
+vec4 fog_color(vec4 color) {
+    return color * FOG_INTENSITY;
+}
@@ -0,0 +1 @@
+const float BRIGHTNESS = 1.5;
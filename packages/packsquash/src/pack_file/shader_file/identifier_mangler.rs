@@ -0,0 +1,662 @@
+//! Contains code to mangle the identifiers of GLSL translation units, renaming their symbols to
+//! the shortest identifiers that do not change the meaning of the code.
+
+use ahash::{AHashMap, AHashSet};
+use glsl_lang::ast::{
+	ArraySpecifier, ArraySpecifierDimensionData, CaseLabelData, ConditionData, Declaration,
+	DeclarationData, Expr, ExprData, ExternalDeclarationData, ForInitStatementData,
+	FunIdentifierData, FunctionParameterDeclarationData, FunctionPrototype, Identifier, Initializer,
+	InitializerData, IterationStatementData, JumpStatementData, SelectionRestStatementData, SmolStr,
+	Statement, StatementData, TranslationUnit, TypeName, TypeSpecifier, TypeSpecifierNonArrayData
+};
+use glsl_lang::visitor::{Host, Visit, Visitor};
+
+/// The name of the function that is the entry point of a shader, which must be preserved.
+const ENTRY_POINT_FUNCTION: &str = "main";
+/// The maximum length of mangled identifiers. Identifiers of up to three characters are
+/// enough for more than 200,000 symbols, far more than what shaders have in practice, so
+/// only reserved words up to this length need to be avoided.
+const MAXIMUM_MANGLED_IDENTIFIER_LENGTH: usize = 3;
+/// The characters that mangled identifiers may start with.
+const IDENTIFIER_START_CHARACTERS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+/// The characters that mangled identifiers may contain after their first character.
+/// Underscores are excluded, so that reserved identifiers that start with `gl_` or
+/// contain two consecutive underscores are never generated.
+const IDENTIFIER_CHARACTERS: &[u8] =
+	b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+/// The GLSL keywords and reserved words of up to [`MAXIMUM_MANGLED_IDENTIFIER_LENGTH`]
+/// characters.
+const RESERVED_WORDS: &[&str] = &["asm", "do", "for", "if", "in", "int", "out"];
+/// The names of the GLSL built-in functions. User-defined functions may overload them, so
+/// renaming such functions would change which overloads calls to them resolve to.
+const BUILT_IN_FUNCTIONS: &[&str] = &[
+	"radians",
+	"degrees",
+	"sin",
+	"cos",
+	"tan",
+	"asin",
+	"acos",
+	"atan",
+	"sinh",
+	"cosh",
+	"tanh",
+	"asinh",
+	"acosh",
+	"atanh",
+	"pow",
+	"exp",
+	"log",
+	"exp2",
+	"log2",
+	"sqrt",
+	"inversesqrt",
+	"abs",
+	"sign",
+	"floor",
+	"trunc",
+	"round",
+	"roundEven",
+	"ceil",
+	"fract",
+	"mod",
+	"modf",
+	"min",
+	"max",
+	"clamp",
+	"mix",
+	"step",
+	"smoothstep",
+	"isnan",
+	"isinf",
+	"floatBitsToInt",
+	"floatBitsToUint",
+	"intBitsToFloat",
+	"uintBitsToFloat",
+	"fma",
+	"frexp",
+	"ldexp",
+	"packUnorm2x16",
+	"packSnorm2x16",
+	"packUnorm4x8",
+	"packSnorm4x8",
+	"unpackUnorm2x16",
+	"unpackSnorm2x16",
+	"unpackUnorm4x8",
+	"unpackSnorm4x8",
+	"packHalf2x16",
+	"unpackHalf2x16",
+	"packDouble2x32",
+	"unpackDouble2x32",
+	"length",
+	"distance",
+	"dot",
+	"cross",
+	"normalize",
+	"ftransform",
+	"faceforward",
+	"reflect",
+	"refract",
+	"matrixCompMult",
+	"outerProduct",
+	"transpose",
+	"determinant",
+	"inverse",
+	"lessThan",
+	"lessThanEqual",
+	"greaterThan",
+	"greaterThanEqual",
+	"equal",
+	"notEqual",
+	"any",
+	"all",
+	"not",
+	"uaddCarry",
+	"usubBorrow",
+	"umulExtended",
+	"imulExtended",
+	"bitfieldExtract",
+	"bitfieldInsert",
+	"bitfieldReverse",
+	"bitCount",
+	"findLSB",
+	"findMSB",
+	"textureSize",
+	"textureQueryLod",
+	"textureQueryLevels",
+	"textureSamples",
+	"texture",
+	"textureProj",
+	"textureLod",
+	"textureOffset",
+	"texelFetch",
+	"texelFetchOffset",
+	"textureProjOffset",
+	"textureLodOffset",
+	"textureProjLod",
+	"textureProjLodOffset",
+	"textureGrad",
+	"textureGradOffset",
+	"textureProjGrad",
+	"textureProjGradOffset",
+	"textureGather",
+	"textureGatherOffset",
+	"textureGatherOffsets",
+	"texture1D",
+	"texture1DProj",
+	"texture1DLod",
+	"texture1DProjLod",
+	"texture2D",
+	"texture2DProj",
+	"texture2DLod",
+	"texture2DProjLod",
+	"texture3D",
+	"texture3DProj",
+	"texture3DLod",
+	"texture3DProjLod",
+	"textureCube",
+	"textureCubeLod",
+	"shadow1D",
+	"shadow2D",
+	"shadow1DProj",
+	"shadow2DProj",
+	"shadow1DLod",
+	"shadow2DLod",
+	"shadow1DProjLod",
+	"shadow2DProjLod",
+	"atomicCounterIncrement",
+	"atomicCounterDecrement",
+	"atomicCounter",
+	"atomicAdd",
+	"atomicMin",
+	"atomicMax",
+	"atomicAnd",
+	"atomicOr",
+	"atomicXor",
+	"atomicExchange",
+	"atomicCompSwap",
+	"imageSize",
+	"imageSamples",
+	"imageLoad",
+	"imageStore",
+	"imageAtomicAdd",
+	"imageAtomicMin",
+	"imageAtomicMax",
+	"imageAtomicAnd",
+	"imageAtomicOr",
+	"imageAtomicXor",
+	"imageAtomicExchange",
+	"imageAtomicCompSwap",
+	"EmitStreamVertex",
+	"EndStreamPrimitive",
+	"EmitVertex",
+	"EndPrimitive",
+	"dFdx",
+	"dFdy",
+	"dFdxFine",
+	"dFdyFine",
+	"dFdxCoarse",
+	"dFdyCoarse",
+	"fwidth",
+	"fwidthFine",
+	"fwidthCoarse",
+	"interpolateAtCentroid",
+	"interpolateAtSample",
+	"interpolateAtOffset",
+	"noise1",
+	"noise2",
+	"noise3",
+	"noise4",
+	"barrier",
+	"memoryBarrier",
+	"memoryBarrierAtomicCounter",
+	"memoryBarrierBuffer",
+	"memoryBarrierShared",
+	"memoryBarrierImage",
+	"groupMemoryBarrier"
+];
+
+/// Mangles the identifiers of the specified top-level translation unit, renaming its local
+/// variables, function parameters and functions other than the entry point to the shortest
+/// identifiers that are not used by any other symbol.
+///
+/// Global variables, such as uniforms, samplers and `in` or `out` interface variables, are
+/// never renamed, because Minecraft and the other shader stages reference them by name. This
+/// includes everything core shader JSON definitions reference. Struct and interface block
+/// names and fields are preserved too. Preprocessor directives must have been expanded before
+/// calling this function, as they are not considered.
+pub fn mangle_identifiers(translation_unit: &mut TranslationUnit) {
+	// Mangled identifiers must not be any identifier that the translation unit already uses,
+	// so that they never clash with preserved symbols
+	let mut identifier_collector = IdentifierCollector::default();
+	translation_unit.visit(&mut identifier_collector);
+
+	let mut identifier_generator = IdentifierGenerator {
+		next_index: 0,
+		unavailable_identifiers: &identifier_collector.identifiers
+	};
+
+	// Every overload of a function is renamed to the same identifier, so that overload
+	// resolution is not affected. Functions that are declared but not defined may be
+	// defined elsewhere, so they are not renamed
+	let mut function_identifiers = AHashMap::new();
+	for external_declaration in &translation_unit.0 {
+		if let ExternalDeclarationData::FunctionDefinition(function_definition) =
+			&external_declaration.content
+		{
+			let function_name = &function_definition.prototype.name.0;
+
+			if function_name != ENTRY_POINT_FUNCTION
+				&& !BUILT_IN_FUNCTIONS.contains(&function_name.as_str())
+				&& !function_identifiers.contains_key(function_name)
+			{
+				let mangled_identifier = identifier_generator
+					.next()
+					.unwrap_or_else(|| function_name.clone());
+
+				function_identifiers.insert(function_name.clone(), mangled_identifier);
+			}
+		}
+	}
+
+	// Local symbols of different functions can't clash with each other, so the identifiers of
+	// each function start from the first one not used by functions
+	for external_declaration in &mut translation_unit.0 {
+		let mut mangler = IdentifierMangler {
+			function_identifiers: &function_identifiers,
+			local_identifier_generator: identifier_generator.clone(),
+			scopes: vec![AHashMap::new()]
+		};
+
+		match &mut external_declaration.content {
+			ExternalDeclarationData::FunctionDefinition(function_definition) => {
+				// The parameters of a function share their scope with its body
+				mangler.mangle_function_prototype(&mut function_definition.prototype);
+
+				for statement in &mut function_definition.statement.statement_list {
+					mangler.mangle_statement(statement);
+				}
+			}
+			ExternalDeclarationData::Declaration(declaration) => {
+				mangler.mangle_global_declaration(declaration)
+			}
+			ExternalDeclarationData::Preprocessor(_) => {}
+		}
+	}
+}
+
+/// A visitor that collects every identifier and type name used in a GLSL symbol.
+#[derive(Default)]
+struct IdentifierCollector {
+	identifiers: AHashSet<SmolStr>
+}
+
+impl Visitor for IdentifierCollector {
+	fn visit_identifier(&mut self, identifier: &Identifier) -> Visit {
+		self.identifiers.insert(identifier.0.clone());
+		Visit::Children
+	}
+
+	fn visit_type_name(&mut self, type_name: &TypeName) -> Visit {
+		self.identifiers.insert(type_name.0.clone());
+		Visit::Children
+	}
+}
+
+/// Generates the shortest valid identifiers that are not reserved or unavailable, in
+/// ascending length order.
+#[derive(Clone)]
+struct IdentifierGenerator<'ids> {
+	next_index: usize,
+	unavailable_identifiers: &'ids AHashSet<SmolStr>
+}
+
+impl Iterator for IdentifierGenerator<'_> {
+	type Item = SmolStr;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		loop {
+			let identifier = nth_identifier(self.next_index)?;
+			self.next_index += 1;
+
+			if !RESERVED_WORDS.contains(&identifier.as_str())
+				&& !BUILT_IN_FUNCTIONS.contains(&identifier.as_str())
+				&& !self.unavailable_identifiers.contains(identifier.as_str())
+			{
+				return Some(identifier.into());
+			}
+		}
+	}
+}
+
+/// Returns the identifier at the specified index of the sequence of every identifier of
+/// up to [`MAXIMUM_MANGLED_IDENTIFIER_LENGTH`] characters, sorted by ascending length, or
+/// `None` if the index is past the end of that sequence.
+fn nth_identifier(mut index: usize) -> Option<String> {
+	let mut identifiers_of_length = IDENTIFIER_START_CHARACTERS.len();
+
+	for length in 1..=MAXIMUM_MANGLED_IDENTIFIER_LENGTH {
+		if index < identifiers_of_length {
+			let mut identifier =
+				vec![IDENTIFIER_START_CHARACTERS[index % IDENTIFIER_START_CHARACTERS.len()]];
+			index /= IDENTIFIER_START_CHARACTERS.len();
+
+			for _ in 1..length {
+				identifier.push(IDENTIFIER_CHARACTERS[index % IDENTIFIER_CHARACTERS.len()]);
+				index /= IDENTIFIER_CHARACTERS.len();
+			}
+
+			// Both character sets only contain ASCII characters
+			return Some(String::from_utf8(identifier).unwrap());
+		}
+
+		index -= identifiers_of_length;
+		identifiers_of_length *= IDENTIFIER_CHARACTERS.len();
+	}
+
+	None
+}
+
+/// Renames the symbols declared and referenced in an external declaration of a translation
+/// unit, keeping track of the scopes of local symbols.
+struct IdentifierMangler<'a, 'ids> {
+	function_identifiers: &'a AHashMap<SmolStr, SmolStr>,
+	local_identifier_generator: IdentifierGenerator<'ids>,
+	scopes: Vec<AHashMap<SmolStr, SmolStr>>
+}
+
+impl IdentifierMangler<'_, '_> {
+	/// Renames the specified newly declared local symbol, adding it to the innermost scope.
+	fn declare_local(&mut self, identifier: &mut Identifier) {
+		let mangled_identifier = self
+			.local_identifier_generator
+			.next()
+			.unwrap_or_else(|| identifier.0.clone());
+
+		self.scopes
+			.last_mut()
+			.unwrap()
+			.insert(identifier.0.clone(), mangled_identifier.clone());
+
+		identifier.0 = mangled_identifier;
+	}
+
+	/// Returns the mangled identifier of the local symbol with the specified name that is
+	/// visible from the innermost scope, if any.
+	fn local_identifier(&self, name: &SmolStr) -> Option<&SmolStr> {
+		self.scopes.iter().rev().find_map(|scope| scope.get(name))
+	}
+
+	/// Calls the specified function in a new innermost scope.
+	fn in_new_scope(&mut self, f: impl FnOnce(&mut Self)) {
+		self.scopes.push(AHashMap::new());
+		f(self);
+		self.scopes.pop();
+	}
+
+	fn mangle_global_declaration(&mut self, declaration: &mut Declaration) {
+		match &mut declaration.content {
+			// Global variables are preserved, but their initializers may call functions
+			DeclarationData::InitDeclaratorList(init_declarator_list) => {
+				let head = &mut init_declarator_list.head;
+				self.mangle_type_specifier(&mut head.ty.ty);
+				self.mangle_array_specifier(head.array_specifier.as_mut());
+				if let Some(initializer) = &mut head.initializer {
+					self.mangle_initializer(initializer);
+				}
+
+				for declaration in &mut init_declarator_list.tail {
+					self.mangle_array_specifier(declaration.ident.array_spec.as_mut());
+					if let Some(initializer) = &mut declaration.initializer {
+						self.mangle_initializer(initializer);
+					}
+				}
+			}
+			DeclarationData::FunctionPrototype(function_prototype) => {
+				self.mangle_function_prototype(function_prototype)
+			}
+			DeclarationData::Precision(_, _)
+			| DeclarationData::Block(_)
+			| DeclarationData::Invariant(_) => {}
+		}
+	}
+
+	fn mangle_function_prototype(&mut self, function_prototype: &mut FunctionPrototype) {
+		if let Some(mangled_identifier) = self.function_identifiers.get(&function_prototype.name.0) {
+			function_prototype.name.0 = mangled_identifier.clone();
+		}
+
+		for parameter in &mut function_prototype.parameters {
+			match &mut parameter.content {
+				FunctionParameterDeclarationData::Named(_, declarator) => {
+					self.mangle_type_specifier(&mut declarator.ty);
+					self.mangle_array_specifier(declarator.ident.array_spec.as_mut());
+					self.declare_local(&mut declarator.ident.ident);
+				}
+				FunctionParameterDeclarationData::Unnamed(_, type_specifier) => {
+					self.mangle_type_specifier(type_specifier)
+				}
+			}
+		}
+	}
+
+	fn mangle_local_declaration(&mut self, declaration: &mut Declaration) {
+		match &mut declaration.content {
+			// The scope of a local variable starts right after its declarator, so its
+			// initializer may reference a variable it shadows
+			DeclarationData::InitDeclaratorList(init_declarator_list) => {
+				let head = &mut init_declarator_list.head;
+				self.mangle_type_specifier(&mut head.ty.ty);
+				self.mangle_array_specifier(head.array_specifier.as_mut());
+				if let Some(initializer) = &mut head.initializer {
+					self.mangle_initializer(initializer);
+				}
+				if let Some(name) = &mut head.name {
+					self.declare_local(name);
+				}
+
+				for declaration in &mut init_declarator_list.tail {
+					self.mangle_array_specifier(declaration.ident.array_spec.as_mut());
+					if let Some(initializer) = &mut declaration.initializer {
+						self.mangle_initializer(initializer);
+					}
+					self.declare_local(&mut declaration.ident.ident);
+				}
+			}
+			DeclarationData::FunctionPrototype(function_prototype) => {
+				if let Some(mangled_identifier) =
+					self.function_identifiers.get(&function_prototype.name.0)
+				{
+					function_prototype.name.0 = mangled_identifier.clone();
+				}
+			}
+			DeclarationData::Precision(_, _)
+			| DeclarationData::Block(_)
+			| DeclarationData::Invariant(_) => {}
+		}
+	}
+
+	fn mangle_statement(&mut self, statement: &mut Statement) {
+		match &mut statement.content {
+			StatementData::Declaration(declaration) => self.mangle_local_declaration(declaration),
+			StatementData::Expression(expression_statement) => {
+				if let Some(expression) = &mut expression_statement.0 {
+					self.mangle_expression(expression);
+				}
+			}
+			StatementData::Selection(selection_statement) => {
+				self.mangle_expression(&mut selection_statement.cond);
+
+				match &mut selection_statement.rest.content {
+					SelectionRestStatementData::Statement(statement) => {
+						self.in_new_scope(|mangler| mangler.mangle_statement(statement))
+					}
+					SelectionRestStatementData::Else(statement, else_statement) => {
+						self.in_new_scope(|mangler| mangler.mangle_statement(statement));
+						self.in_new_scope(|mangler| mangler.mangle_statement(else_statement));
+					}
+				}
+			}
+			StatementData::Switch(switch_statement) => {
+				self.mangle_expression(&mut switch_statement.head);
+
+				self.in_new_scope(|mangler| {
+					for statement in &mut switch_statement.body {
+						mangler.mangle_statement(statement);
+					}
+				});
+			}
+			StatementData::CaseLabel(case_label) => {
+				if let CaseLabelData::Case(expression) = &mut case_label.content {
+					self.mangle_expression(expression);
+				}
+			}
+			StatementData::Iteration(iteration_statement) => match &mut iteration_statement.content {
+				IterationStatementData::While(condition, statement) => self.in_new_scope(|mangler| {
+					mangler.mangle_condition(condition);
+					mangler.mangle_statement(statement);
+				}),
+				IterationStatementData::DoWhile(statement, expression) => {
+					self.in_new_scope(|mangler| mangler.mangle_statement(statement));
+					self.mangle_expression(expression);
+				}
+				IterationStatementData::For(init_statement, rest_statement, statement) => self
+					.in_new_scope(|mangler| {
+						match &mut init_statement.content {
+							ForInitStatementData::Expression(Some(expression)) => {
+								mangler.mangle_expression(expression)
+							}
+							ForInitStatementData::Expression(None) => {}
+							ForInitStatementData::Declaration(declaration) => {
+								mangler.mangle_local_declaration(declaration)
+							}
+						}
+
+						if let Some(condition) = &mut rest_statement.condition {
+							mangler.mangle_condition(condition);
+						}
+						if let Some(expression) = &mut rest_statement.post_expr {
+							mangler.mangle_expression(expression);
+						}
+
+						mangler.mangle_statement(statement);
+					})
+			},
+			StatementData::Jump(jump_statement) => {
+				if let JumpStatementData::Return(Some(expression)) = &mut jump_statement.content {
+					self.mangle_expression(expression);
+				}
+			}
+			StatementData::Compound(compound_statement) => self.in_new_scope(|mangler| {
+				for statement in &mut compound_statement.statement_list {
+					mangler.mangle_statement(statement);
+				}
+			})
+		}
+	}
+
+	fn mangle_condition(&mut self, condition: &mut glsl_lang::ast::Condition) {
+		match &mut condition.content {
+			ConditionData::Expr(expression) => self.mangle_expression(expression),
+			ConditionData::Assignment(fully_specified_type, identifier, initializer) => {
+				self.mangle_type_specifier(&mut fully_specified_type.ty);
+				self.mangle_initializer(initializer);
+				self.declare_local(identifier);
+			}
+		}
+	}
+
+	fn mangle_initializer(&mut self, initializer: &mut Initializer) {
+		match &mut initializer.content {
+			InitializerData::Simple(expression) => self.mangle_expression(expression),
+			InitializerData::List(initializers) => {
+				for initializer in initializers {
+					self.mangle_initializer(initializer);
+				}
+			}
+		}
+	}
+
+	fn mangle_type_specifier(&mut self, type_specifier: &mut TypeSpecifier) {
+		self.mangle_array_specifier(type_specifier.array_specifier.as_mut());
+
+		// Struct names and fields are preserved, but their array sizes may reference
+		// local constants
+		if let TypeSpecifierNonArrayData::Struct(struct_specifier) = &mut type_specifier.ty.content {
+			for field in &mut struct_specifier.fields {
+				self.mangle_type_specifier(&mut field.ty);
+
+				for identifier in &mut field.identifiers {
+					self.mangle_array_specifier(identifier.array_spec.as_mut());
+				}
+			}
+		}
+	}
+
+	fn mangle_array_specifier(&mut self, array_specifier: Option<&mut ArraySpecifier>) {
+		for dimension in array_specifier
+			.into_iter()
+			.flat_map(|array_specifier| &mut array_specifier.dimensions)
+		{
+			if let ArraySpecifierDimensionData::ExplicitlySized(expression) = &mut dimension.content {
+				self.mangle_expression(expression);
+			}
+		}
+	}
+
+	fn mangle_expression(&mut self, expression: &mut Expr) {
+		match &mut expression.content {
+			ExprData::Variable(identifier) => {
+				if let Some(mangled_identifier) = self.local_identifier(&identifier.0) {
+					identifier.0 = mangled_identifier.clone();
+				}
+			}
+			ExprData::IntConst(_)
+			| ExprData::UIntConst(_)
+			| ExprData::BoolConst(_)
+			| ExprData::FloatConst(_)
+			| ExprData::DoubleConst(_) => {}
+			ExprData::Unary(_, expression)
+			| ExprData::PostInc(expression)
+			| ExprData::PostDec(expression)
+			// Fields are preserved, but the expression whose field is selected is not
+			| ExprData::Dot(expression, _) => self.mangle_expression(expression),
+			ExprData::Binary(_, left_expression, right_expression)
+			| ExprData::Assignment(left_expression, _, right_expression)
+			| ExprData::Bracket(left_expression, right_expression)
+			| ExprData::Comma(left_expression, right_expression) => {
+				self.mangle_expression(left_expression);
+				self.mangle_expression(right_expression);
+			}
+			ExprData::Ternary(condition, true_expression, false_expression) => {
+				self.mangle_expression(condition);
+				self.mangle_expression(true_expression);
+				self.mangle_expression(false_expression);
+			}
+			ExprData::FunCall(function_identifier, arguments) => {
+				match &mut function_identifier.content {
+					FunIdentifierData::Expr(expression) => match &mut expression.content {
+						// Local variables hide functions with the same name
+						ExprData::Variable(identifier)
+							if self.local_identifier(&identifier.0).is_none() =>
+						{
+							if let Some(mangled_identifier) =
+								self.function_identifiers.get(&identifier.0)
+							{
+								identifier.0 = mangled_identifier.clone();
+							}
+						}
+						_ => self.mangle_expression(expression)
+					},
+					FunIdentifierData::TypeSpecifier(type_specifier) => {
+						self.mangle_type_specifier(type_specifier)
+					}
+				}
+
+				for argument in arguments {
+					self.mangle_expression(argument);
+				}
+			}
+		}
+	}
+}
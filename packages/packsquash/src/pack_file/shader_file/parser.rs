@@ -1,6 +1,7 @@
 //! GLSL parsing and transpilation code, based on `glsl_lang` and its companion crates.
 
 use crate::pack_file::strip_utf8_bom;
+use crate::pack_index::{ImportableShaders, VANILLA_INCLUDE_SHADERS_DIRECTORY};
 use aho_corasick::AhoCorasick;
 use glsl_lang::ast::{
 	Expr, ExternalDeclarationData, FileId, Statement, TranslationUnit, TypeSpecifierNonArrayData
@@ -19,7 +20,7 @@ use std::convert::Infallible;
 use std::fmt;
 use std::fmt::Write;
 use std::ops::{Deref, DerefMut};
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use std::str::Utf8Error;
 use std::sync::LazyLock;
 use thiserror::Error;
@@ -36,8 +37,12 @@ type NonPreprocessingLexer<'i> = glsl_lang_lexer::v2_min::str::Lexer<'i>;
 /// directives in any position. As a compromise, it is possible to inject preprocessor directives
 /// back into the AST in external declaration position after parsing, but this is only guaranteed
 /// to yield correct results if such directives originally were in such position.
-type PreprocessingLexer<'r, 'p, 'flag> =
-	glsl_lang_lexer::v2_full::fs::Lexer<'r, 'p, ImportFileSystem<'flag>>;
+type PreprocessingLexer<'r, 'p, 'i, 'flag> =
+	glsl_lang_lexer::v2_full::fs::Lexer<'r, 'p, ImportFileSystem<'i, 'flag>>;
+
+/// The prefix of the names of the macros used to guard the source code of imported shaders,
+/// so that each shader is imported at most once, like Minecraft does.
+const IMPORT_GUARD_MACRO_PREFIX: &str = "_PACKSQUASH_IMPORTED_";
 
 /// An error that may happen while parsing a GLSL grammar symbol.
 #[derive(Error, Debug)]
@@ -65,13 +70,16 @@ pub enum ParseError {
 }
 
 /// A GLSL parser capable of parsing a GLSL grammar symbol.
-pub struct Parser {
-	parse_options: ParseOptions
+pub struct Parser<'a> {
+	parse_options: ParseOptions,
+	shader_path: &'a str,
+	importable_shaders: &'a ImportableShaders
 }
 
-impl Parser {
-	/// Creates a new GLSL parser.
-	pub fn new() -> Self {
+impl<'a> Parser<'a> {
+	/// Creates a new GLSL parser for the shader at the specified relative path, which resolves
+	/// `#moj_import` directives to the specified importable shaders.
+	pub fn new(shader_path: &'a str, importable_shaders: &'a ImportableShaders) -> Self {
 		Self {
 			parse_options: ParseOptions {
 				// GLSL specification, section 3.3: "Version 1.10 of the language does not
@@ -81,7 +89,9 @@ impl Parser {
 				target_vulkan: false,
 				source_id: FileId::default(),
 				allow_rs_ident: false
-			}
+			},
+			shader_path,
+			importable_shaders
 		}
 	}
 
@@ -104,15 +114,25 @@ impl Parser {
 
 		let found_unresolvable_moj_import = Cell::new(false);
 
-		let mut preprocessor =
-			Processor::new_with_fs(ImportFileSystem::new(&found_unresolvable_moj_import));
+		let mut preprocessor = Processor::new_with_fs(ImportFileSystem::new(
+			self.importable_shaders,
+			&found_unresolvable_moj_import
+		));
 
-		// Imports using the <...> syntax are known as "system imports", and the preprocessor needs
-		// to know at least one system include directory to resolve them
-		preprocessor.system_paths_mut().push(PathBuf::new());
+		// Imports using the <...> syntax are known as "system imports", and are resolved from the
+		// vanilla include shaders directory. Imports using the "..." syntax are resolved from the
+		// directory of the importing shader
+		preprocessor
+			.system_paths_mut()
+			.push(PathBuf::from(VANILLA_INCLUDE_SHADERS_DIRECTORY));
 
 		TranslationUnit::parse_with_options::<PreprocessingLexer>(
-			preprocessor.open_source(&source, ""),
+			preprocessor.open_source(
+				&source,
+				Path::new(self.shader_path)
+					.parent()
+					.unwrap_or_else(|| Path::new(""))
+			),
 			&self.parse_options
 		)
 		.map(|(mut translation_unit, _, lexer_iter)| {
@@ -242,50 +262,117 @@ impl Parser {
 /// The virtual filesystem used by [`PreprocessingLexer`] to expand `#moj_import`
 /// directives to the contents of the files they reference.
 ///
-/// This filesystem records whether any `#moj_import` directive was expanded.
-struct ImportFileSystem<'flag> {
+/// This filesystem records whether any `#moj_import` directive could not be expanded.
+struct ImportFileSystem<'i, 'flag> {
+	importable_shaders: &'i ImportableShaders,
+	imported_shaders: Cell<usize>,
 	found_unresolvable_moj_import: &'flag Cell<bool>
 }
 
-impl<'flag> ImportFileSystem<'flag> {
-	/// Creates a new virtual filesystem used by the preprocessing shader lexer.
+impl<'i, 'flag> ImportFileSystem<'i, 'flag> {
+	/// Creates a new virtual filesystem used by the preprocessing shader lexer, which
+	/// resolves `#moj_import` directives to the specified importable shaders.
 	/// `found_unresolvable_moj_import` will be set to `true` if any `#moj_import`
-	/// directive is found, because it is not possible to expand them at the
-	/// moment.
-	fn new(found_unresolvable_moj_import: &'flag Cell<bool>) -> Self {
+	/// directive references a shader that is not importable.
+	fn new(
+		importable_shaders: &'i ImportableShaders,
+		found_unresolvable_moj_import: &'flag Cell<bool>
+	) -> Self {
 		Self {
+			importable_shaders,
+			imported_shaders: Cell::new(0),
 			found_unresolvable_moj_import
 		}
 	}
 }
 
-impl FileSystem for ImportFileSystem<'_> {
+impl FileSystem for ImportFileSystem<'_, '_> {
 	type Error = Infallible;
 
-	fn canonicalize(&self, _: &Path) -> Result<PathBuf, Self::Error> {
-		Ok(PathBuf::new())
+	fn canonicalize(&self, path: &Path) -> Result<PathBuf, Self::Error> {
+		Ok(import_relative_path(path).map_or_else(|| path.to_owned(), PathBuf::from))
 	}
 
 	fn exists(&self, _: &Path) -> bool {
 		true
 	}
 
-	fn read(&self, _: &Path) -> Result<Cow<'_, str>, Self::Error> {
-		// For now, just record that a #moj_import was found. A #moj_import'ed file
-		// may contain preprocessor directives whose knowledge may be necessary to properly
-		// expand other preprocessor directives, and thus keep the GLSL source semantics
-		// when doing AST transformations and transpiling. We can't resolve #moj_import
-		// at the moment because we can't access pack files here, so if such a directive
-		// is found, we know we should not proceed with any transformations: doing AST
-		// transformations and transpiling is appropriate if and only if we can expand all
-		// the preprocessor directives. Also consider that not expanding can cause failure
-		// to correctly parse shaders that depend on imported text to be syntactically
-		// correct, but those should be not that common to find in practice
-		self.found_unresolvable_moj_import.set(true);
-		Ok("".into())
+	fn read(&self, path: &Path) -> Result<Cow<'_, str>, Self::Error> {
+		// A #moj_import'ed file may contain preprocessor directives whose knowledge may be
+		// necessary to properly expand other preprocessor directives, and thus keep the GLSL
+		// source semantics when doing AST transformations and transpiling. If the imported
+		// file is not known, record that fact, so that no transformations are done: doing
+		// AST transformations and transpiling is appropriate if and only if we can expand all
+		// the preprocessor directives. Imported files may be outside the pack, such as vanilla
+		// include shaders when their directory was not configured, or shaders of other packs
+		let Some(source) = path
+			.to_str()
+			.and_then(|path| self.importable_shaders.get(path))
+		else {
+			self.found_unresolvable_moj_import.set(true);
+			return Ok("".into());
+		};
+
+		// Minecraft imports each file at most once per shader, ignoring further imports of
+		// it. The preprocessor expands every import, so emulate that behavior, which also
+		// keeps import cycles from being expanded forever, with a guard macro
+		let import_id = self.imported_shaders.get();
+		self.imported_shaders.set(import_id + 1);
+
+		Ok(format!(
+			"#ifndef {IMPORT_GUARD_MACRO_PREFIX}{import_id}\n\
+			#define {IMPORT_GUARD_MACRO_PREFIX}{import_id}\n\
+			{source}\n\
+			#endif\n"
+		)
+		.into())
 	}
 }
 
+/// Returns whether the specified shader source code has `#moj_import` directives. Directives
+/// split across lines with newline escapes are not detected.
+pub fn has_moj_import_directive(source: &str) -> bool {
+	static MOJ_IMPORT_DIRECTIVE_MATCHER: LazyLock<Regex> =
+		LazyLock::new(|| Regex::new("(?m:^[[:space:]]*#[[:space:]]*moj_import)").unwrap());
+
+	MOJ_IMPORT_DIRECTIVE_MATCHER.is_match(source)
+}
+
+/// Resolves the path of a shader imported by a `#moj_import` directive, relative to either
+/// the directory of the importing shader or the vanilla include shaders directory, to the
+/// relative path of the imported shader, following Minecraft resource location rules.
+/// `None` is returned if the path can't be resolved.
+fn import_relative_path(path: &Path) -> Option<String> {
+	let mut components = vec![];
+
+	for component in path.components() {
+		match component {
+			Component::Normal(component) => {
+				let component = component.to_str()?;
+
+				// System imports may reference include shaders of any namespace with the
+				// <namespace:path> syntax since Minecraft 1.21.2
+				match component.split_once(':') {
+					Some((namespace, component))
+						if components.join("/") == VANILLA_INCLUDE_SHADERS_DIRECTORY =>
+					{
+						components = vec!["assets", namespace, "shaders", "include", component];
+					}
+					Some(_) => return None,
+					None => components.push(component)
+				}
+			}
+			Component::CurDir => {}
+			Component::ParentDir => {
+				components.pop()?;
+			}
+			Component::RootDir | Component::Prefix(_) => return None
+		}
+	}
+
+	Some(components.join("/"))
+}
+
 /// A parsed GLSL grammar symbol returned by a [`Parser`].
 #[derive(Debug, Eq, PartialEq)]
 pub struct ParsedSymbol<T> {
@@ -1,6 +1,7 @@
 use crate::pack_file::util::BOM_UTF8;
 use pretty_assertions::assert_eq;
 use std::fmt::Debug;
+use std::sync::LazyLock;
 use tokio_stream::StreamExt;
 use tokio_test::io::Builder;
 
@@ -10,6 +11,24 @@ static FRAGMENT_SHADER_DATA: &[u8] = include_bytes!("example.fsh");
 static NON_TRANSFORMABLE_SHADER_DATA: &[u8] = include_bytes!("example_non_transformable.glsl");
 static FALSE_POSITIVE_SYNTAX_ERROR: &[u8] = include_bytes!("example_false_positive_parse_error.glsl");
 static FALSE_POSITIVE_MISSING_MAIN: &[u8] = include_bytes!("example_false_positive_missing_main.fsh");
+static SHADER_WITH_IMPORTS_DATA: &[u8] = include_bytes!("example_with_imports.fsh");
+static SHADER_WITH_DEAD_CODE_DATA: &[u8] = include_bytes!("example_dead_code.fsh");
+
+/// The relative path of the shaders processed by these tests.
+const SHADER_PATH: &str = "assets/minecraft/shaders/core/example.fsh";
+/// The shaders that the shaders processed by these tests may import.
+static IMPORTABLE_SHADERS: LazyLock<Arc<ImportableShaders>> = LazyLock::new(|| {
+	Arc::new(ImportableShaders::from_iter([
+		(
+			"assets/minecraft/shaders/include/example_import.glsl".into(),
+			include_str!("example_import.glsl").into()
+		),
+		(
+			"assets/minecraft/shaders/core/example_relative_import.glsl".into(),
+			include_str!("example_relative_import.glsl").into()
+		)
+	]))
+});
 
 /// Processes the given input data as a [ShaderFile], using the provided settings,
 /// expecting a successful result that equals the expected string.
@@ -24,7 +43,7 @@ async fn successful_process_test<T: Extractable<TranslationUnit> + PartialEq + D
 	expect_smaller_file_size: bool,
 	expect_output_equal_to_input: bool
 ) {
-	let shader_parser = Parser::new();
+	let shader_parser = Parser::new(SHADER_PATH, &IMPORTABLE_SHADERS);
 
 	let input_data_str = std::str::from_utf8(input_data);
 
@@ -40,6 +59,9 @@ async fn successful_process_test<T: Extractable<TranslationUnit> + PartialEq + D
 		read: Builder::new().read(&input_data_with_maybe_bom).build(),
 		file_length_hint: input_data_with_maybe_bom.len(),
 		is_vertex_or_fragment_shader,
+		shader_path: SHADER_PATH.into(),
+		importable_shaders: Arc::clone(&IMPORTABLE_SHADERS),
+		imports_shaders: false,
 		optimization_settings: settings
 	}
 	.process();
@@ -64,10 +86,25 @@ async fn successful_process_test<T: Extractable<TranslationUnit> + PartialEq + D
 	assert!(!data.is_empty(), "Some data was expected for this input");
 
 	if expect_parseable_input_and_equal_ast_after_processing {
-		let input_ast = shader_parser
+		let mut input_ast = shader_parser
 			.parse::<T>(input_data, is_top_level_translation_unit)
 			.expect("The test input data should be a valid GLSL symbol");
 
+		// Minified top-level shaders are expected to have their syntax tree optimized
+		if matches!(
+			settings.source_transformation_strategy,
+			ShaderSourceTransformationStrategy::Minify
+		) && is_vertex_or_fragment_shader
+			&& is_top_level_translation_unit
+		{
+			if let Some(translation_unit) = input_ast
+				.as_deref_mut()
+				.and_then(|symbol| (symbol as &mut dyn Any).downcast_mut::<TranslationUnit>())
+			{
+				SyntaxTreeOptimizations::for_top_level_shader(&settings).apply(translation_unit);
+			}
+		}
+
 		let processed_ast = shader_parser
 			.parse::<T>(&data, is_top_level_translation_unit)
 			.expect("The result should be a valid GLSL symbol");
@@ -125,6 +162,139 @@ async fn minifying_with_bom_works() {
 	.await
 }
 
+/// Processes the given fragment shader data as a [ShaderFile], using the provided settings,
+/// and returns the processed shader source.
+async fn processed_fragment_shader_source(input_data: &[u8], settings: ShaderFileOptions) -> String {
+	let data = ShaderFile {
+		read: Builder::new().read(input_data).build(),
+		file_length_hint: input_data.len(),
+		is_vertex_or_fragment_shader: true,
+		shader_path: SHADER_PATH.into(),
+		importable_shaders: Arc::clone(&IMPORTABLE_SHADERS),
+		imports_shaders: false,
+		optimization_settings: settings
+	}
+	.process()
+	.map(|result| result.expect("No error should happen while decoding").1)
+	.collect::<Vec<_>>()
+	.await
+	.concat();
+
+	String::from_utf8(data).expect("The processed shader should be UTF-8")
+}
+
+#[tokio::test]
+async fn identifier_mangling_works() {
+	let process_shader = |identifier_mangling| {
+		processed_fragment_shader_source(
+			FRAGMENT_SHADER_DATA,
+			ShaderFileOptions {
+				source_transformation_strategy: ShaderSourceTransformationStrategy::Minify,
+				identifier_mangling,
+				dead_code_elimination: false,
+				..Default::default()
+			}
+		)
+	};
+
+	let mangled_shader = process_shader(true).await;
+	let unmangled_shader = process_shader(false).await;
+
+	eprintln!("Mangled shader source:\n--------------------\n{mangled_shader}\n--------------------");
+
+	assert!(
+		mangled_shader.len() < unmangled_shader.len(),
+		"Mangling identifiers should make the shader smaller"
+	);
+
+	// Local variables, parameters and functions other than main should be renamed
+	for identifier in ["rand(", "co,", "point", "color."] {
+		assert!(
+			!mangled_shader.contains(identifier),
+			"{identifier} should have been mangled"
+		);
+	}
+
+	// Global variables and the entry point should be kept
+	for identifier in [
+		"dummyVariable2;",
+		"texcoord;",
+		"gcolor;",
+		"IViewRotMat;",
+		"main()"
+	] {
+		assert!(
+			mangled_shader.contains(identifier),
+			"{identifier} should have been kept"
+		);
+	}
+}
+
+#[tokio::test]
+async fn dead_code_elimination_works() {
+	successful_process_test::<TranslationUnit>(
+		SHADER_WITH_DEAD_CODE_DATA,
+		false, // No BOM
+		ShaderFileOptions {
+			source_transformation_strategy: ShaderSourceTransformationStrategy::Minify,
+			dead_code_elimination: true,
+			..Default::default()
+		},
+		true,  // Fragment shader
+		true,  // Top-level TU
+		true,  // The output AST should match the input AST
+		true,  // Smaller size
+		false  // Different output text
+	)
+	.await;
+
+	let optimized_shader = processed_fragment_shader_source(
+		SHADER_WITH_DEAD_CODE_DATA,
+		ShaderFileOptions {
+			source_transformation_strategy: ShaderSourceTransformationStrategy::Minify,
+			identifier_mangling: false,
+			dead_code_elimination: true,
+			..Default::default()
+		}
+	)
+	.await;
+
+	eprintln!(
+		"Optimized shader source:\n--------------------\n{optimized_shader}\n--------------------"
+	);
+
+	// Unreachable functions, unused constants and dead branches should be removed
+	for dead_code in [
+		"unusedHelper",
+		"debugColor",
+		"DEBUG_COLORS",
+		"DEBUG_INTENSITY",
+		"CHANNELS",
+		"vec4(0.)"
+	] {
+		assert!(
+			!optimized_shader.contains(dead_code),
+			"{dead_code} should have been removed"
+		);
+	}
+
+	// Interface variables and reachable functions should be kept, and constants folded
+	for live_code in [
+		"uniform sampler2D Sampler0;",
+		"uniform vec4 ColorModulator;",
+		"in vec2 texCoord0;",
+		"out vec4 fragColor;",
+		"modulate(",
+		"float(1)",
+		"main()"
+	] {
+		assert!(
+			optimized_shader.contains(live_code),
+			"{live_code} should have been kept"
+		);
+	}
+}
+
 #[tokio::test]
 async fn prettifying_works() {
 	successful_process_test::<TranslationUnit>(
@@ -161,12 +331,33 @@ async fn passthrough_works() {
 	.await
 }
 
+#[tokio::test]
+async fn minifying_keeps_already_minified_source() {
+	successful_process_test::<TranslationUnit>(
+		b"void main(){}",
+		false, // No BOM
+		ShaderFileOptions {
+			source_transformation_strategy: ShaderSourceTransformationStrategy::Minify,
+			..Default::default()
+		},
+		true,  // Fragment shader
+		true,  // Top-level TU
+		true,  // The output AST should match the input AST
+		false, // Same size
+		true   // Same output text
+	)
+	.await
+}
+
 #[tokio::test]
 async fn invalid_input_is_handled() {
 	let mut data_stream = ShaderFile {
 		read: Builder::new().read(&[]).build(),
 		file_length_hint: 0,
 		is_vertex_or_fragment_shader: true,
+		shader_path: SHADER_PATH.into(),
+		importable_shaders: Arc::clone(&IMPORTABLE_SHADERS),
+		imports_shaders: false,
 		optimization_settings: Default::default()
 	}
 	.process();
@@ -196,6 +387,37 @@ async fn minifying_is_averted_when_preprocessor_expansion_would_be_incomplete()
 	.await
 }
 
+#[tokio::test]
+async fn minifying_works_with_resolvable_imports() {
+	successful_process_test::<TranslationUnit>(
+		SHADER_WITH_IMPORTS_DATA,
+		false, // No BOM
+		ShaderFileOptions {
+			source_transformation_strategy: ShaderSourceTransformationStrategy::Minify,
+			..Default::default()
+		},
+		true,  // Fragment shader
+		true,  // Top-level TU
+		true,  // The output AST should match the input AST
+		true,  // Smaller size
+		false  // Different output text
+	)
+	.await
+}
+
+#[test]
+fn moj_import_directives_are_detected() {
+	assert!(has_moj_import_directive(
+		std::str::from_utf8(SHADER_WITH_IMPORTS_DATA).unwrap()
+	));
+	assert!(has_moj_import_directive(
+		"  # moj_import <fog.glsl>\nvoid main() {}"
+	));
+	assert!(!has_moj_import_directive(
+		std::str::from_utf8(FRAGMENT_SHADER_DATA).unwrap()
+	));
+}
+
 #[tokio::test]
 async fn tentative_parsing_errors_are_not_fatal() {
 	successful_process_test::<TranslationUnit>(
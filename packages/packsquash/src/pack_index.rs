@@ -0,0 +1,670 @@
+//! Contains helper structs to gather data from pack files that is relevant for optimizing
+//! other pack files, which must be available before processing the files.
+
+use std::collections::hash_map::Entry;
+use std::path::Path;
+use std::sync::Arc;
+
+use ahash::{AHashMap, AHashSet};
+use bytes::BytesMut;
+use enumset::EnumSet;
+use serde_json::Value;
+use tokio::io::AsyncReadExt;
+
+use crate::config::{FileOptions, JsonFileOptions, PngFileOptions, ProcessedSquashOptions};
+use crate::pack_file::asset_type::{compile_asset_types_globset, PackFileAssetType};
+use crate::pack_file::{decode_png_pixels, has_moj_import_directive, parse_json, strip_utf8_bom};
+use crate::vfs::{IteratorTraversalOptions, VirtualFileSystem};
+use crate::RelativePath;
+
+use self::animation::animation_frame_size;
+pub use self::animation::AnimationFrameDeduplication;
+use self::atlas::{atlas_identifier_and_asset_type, AtlasDefinitions};
+pub use self::sound_fingerprints::{SoundFingerprint, SoundFingerprints};
+pub use self::sounds::SoundProperties;
+use self::sounds::{path_without_extension, sound_definitions_asset_type, SoundDefinitions};
+pub use self::texture_downscaling::TextureDownscaling;
+use self::texture_downscaling::{
+	font_bitmap_glyph_grids, font_definition_asset_type, material_map_texture_path
+};
+
+mod animation;
+mod atlas;
+mod sound_fingerprints;
+mod sounds;
+mod texture_downscaling;
+
+/// The locale Minecraft falls back to when a translation key is missing in the selected
+/// language.
+pub const FALLBACK_LOCALE: &str = "en_us";
+/// The relative path of the directory where vanilla Minecraft include shaders are, and where
+/// `#moj_import` directives with the `<...>` syntax look for include shaders by default.
+pub const VANILLA_INCLUDE_SHADERS_DIRECTORY: &str = "assets/minecraft/shaders/include";
+
+/// A set of translated strings contained in a language file, indexed by their key.
+pub type Translations = AHashMap<String, String>;
+
+/// The source code of the include shaders that other shaders may import with `#moj_import`
+/// directives, indexed by their relative path.
+pub type ImportableShaders = AHashMap<String, String>;
+
+/// Cross-file data about a pack, gathered in a single pass over its files before they
+/// are processed. Only the data needed for the configured optimizations is gathered.
+///
+/// Gathering this data is a best-effort process: pack files that can't be read or parsed
+/// are just skipped, so that the corresponding errors are reported when they are processed.
+#[derive(Default)]
+pub struct PackIndex {
+	fallback_translations: AHashMap<String, Arc<Translations>>,
+	directory_listed_atlas_sprites: Option<AHashSet<String>>,
+	animation_frame_deduplications: AHashMap<String, Arc<AnimationFrameDeduplication>>,
+	texture_downscalings: AHashMap<String, TextureDownscaling>,
+	interdependent_textures: AHashSet<String>,
+	textures_with_material_maps: AHashSet<String>,
+	sound_definitions: SoundDefinitions,
+	missing_sound_files: Vec<(String, String)>,
+	unreferenced_sound_files: Vec<String>,
+	non_square_pack_icon: Option<String>,
+	sound_fingerprints: Arc<SoundFingerprints>,
+	importable_shaders: Arc<ImportableShaders>,
+	importing_shaders: AHashSet<String>
+}
+
+impl PackIndex {
+	/// Creates a new pack index for the pack read by the specified virtual filesystem,
+	/// according to the specified squash options.
+	pub async fn new<F: VirtualFileSystem>(
+		vfs: &F,
+		processed_squash_options: &ProcessedSquashOptions
+	) -> Self {
+		let squash_options = &processed_squash_options.options;
+		let mut pack_index = Self::default();
+
+		let gather_fallback_translations = squash_options.file_options.values().any(|file_options| {
+			matches!(
+				file_options,
+				FileOptions::JsonFileOptions(JsonFileOptions {
+					delete_redundant_translations: true,
+					..
+				})
+			)
+		});
+
+		// Which textures are directory-listed only matters for pseudo-directory concealment
+		let gather_atlas_sprites = squash_options
+			.global_options
+			.size_increasing_zip_obfuscation;
+
+		// Font glyph cells and material maps only matter if some texture may be downscaled
+		let may_downscale_textures = squash_options.file_options.values().any(|file_options| {
+			matches!(
+				file_options,
+				FileOptions::PngFileOptions(PngFileOptions {
+					downscaling_target_resolution: Some(_),
+					..
+				})
+			)
+		});
+
+		// Animated textures only need to be decoded if some of them may have their duplicate
+		// frames removed
+		let gather_animation_frame_deduplications =
+			squash_options.file_options.values().any(|file_options| {
+				matches!(
+					file_options,
+					FileOptions::PngFileOptions(PngFileOptions {
+						deduplicate_animation_frames: true,
+						..
+					})
+				)
+			});
+
+		let data_texture_globset =
+			compile_asset_types_globset(EnumSet::only(PackFileAssetType::DataTexture));
+		let material_map_globset = compile_asset_types_globset(
+			PackFileAssetType::PbrNormalMap
+				| PackFileAssetType::PbrSpecularMap
+				| PackFileAssetType::PbrEmissiveMap
+		);
+		let audio_globset = compile_asset_types_globset(
+			PackFileAssetType::GenericOggVorbisAudio | PackFileAssetType::GenericAudio
+		);
+		let include_shader_globset =
+			compile_asset_types_globset(EnumSet::only(PackFileAssetType::TranslationUnitSegment));
+		let top_level_shader_globset = compile_asset_types_globset(
+			PackFileAssetType::VertexShader | PackFileAssetType::FragmentShader
+		);
+		let pack_icon_globset =
+			compile_asset_types_globset(EnumSet::only(PackFileAssetType::PackIcon));
+
+		let mut atlas_definitions = AtlasDefinitions::new();
+		let mut atlas_definitions_valid = true;
+		let mut texture_paths = vec![];
+		let mut font_glyph_grids = vec![];
+		let mut sound_definitions_valid = true;
+		let mut sound_file_paths = vec![];
+		let mut importable_shaders = ImportableShaders::new();
+		let mut animation_frame_deduplication_tasks = vec![];
+
+		for pack_file in vfs
+			.file_iterator(
+				&squash_options.pack_directory,
+				IteratorTraversalOptions {
+					ignore_system_and_hidden_files: squash_options
+						.global_options
+						.ignore_system_and_hidden_files
+				}
+			)
+			.flatten()
+		{
+			if let Some((namespace, FALLBACK_LOCALE, asset_type)) =
+				language_file_namespace_and_locale(&pack_file.relative_path)
+					.filter(|_| gather_fallback_translations)
+			{
+				let Some(Value::Object(translations)) =
+					read_json(vfs, &pack_file.file_path, asset_type).await
+				else {
+					continue;
+				};
+
+				Arc::make_mut(
+					pack_index
+						.fallback_translations
+						.entry(namespace.to_string())
+						.or_default()
+				)
+				.extend(translations.into_iter().filter_map(|(key, value)| {
+					if let Value::String(translation) = value {
+						Some((key, translation))
+					} else {
+						None
+					}
+				}));
+			}
+
+			if gather_atlas_sprites {
+				let relative_path = pack_file.relative_path.as_str();
+
+				if let Some((atlas, asset_type)) = atlas_identifier_and_asset_type(relative_path) {
+					// If we can't tell which sprite sources an atlas has, we can't tell
+					// which textures are not directory-listed either
+					atlas_definitions_valid &=
+						match read_json(vfs, &pack_file.file_path, asset_type).await {
+							Some(definition) => atlas_definitions.add(atlas, &definition).is_some(),
+							None => false
+						};
+				} else if relative_path.ends_with(".png")
+					&& png_file_options(processed_squash_options, relative_path)
+						.may_be_directory_listed_atlas_sprite
+				{
+					texture_paths.push(relative_path.to_string());
+				}
+			}
+
+			// Sound event definitions are always gathered, as they are cheap to parse and reveal
+			// mistakes in sound file references
+			if let Some(asset_type) = sound_definitions_asset_type(pack_file.relative_path.as_str()) {
+				// If we can't tell which sound files are referenced, we can't tell which are not
+				sound_definitions_valid &=
+					match read_json(vfs, &pack_file.file_path, asset_type).await {
+						Some(definitions) => pack_index
+							.sound_definitions
+							.add(pack_file.relative_path.as_str(), &definitions)
+							.is_some(),
+						None => false
+					};
+			} else if audio_globset.is_match(pack_file.relative_path.as_str()) {
+				sound_file_paths.push(pack_file.relative_path.as_str().to_string());
+			}
+
+			// Include shaders are always gathered, as they are usually few and small, and any
+			// shader may import them
+			if include_shader_globset.is_match(pack_file.relative_path.as_str()) {
+				if let Some(source) = read_text(vfs, &pack_file.file_path).await {
+					importable_shaders.insert(pack_file.relative_path.as_str().to_string(), source);
+				}
+			} else if top_level_shader_globset.is_match(pack_file.relative_path.as_str()) {
+				// Imported shaders are expanded in the shaders that import them, so these must be
+				// processed again when the imported shaders change
+				if read_text(vfs, &pack_file.file_path)
+					.await
+					.is_some_and(|source| has_moj_import_directive(&source))
+				{
+					pack_index
+						.importing_shaders
+						.insert(pack_file.relative_path.as_str().to_string());
+				}
+			}
+
+			// Minecraft expects pack icons to be square, and may not show them otherwise. Their
+			// header is enough to tell, so this is cheap
+			if !squash_options.global_options.skip_pack_icon
+				&& pack_icon_globset.is_match(pack_file.relative_path.as_str())
+				&& read_png_dimensions(vfs, &pack_file.file_path)
+					.await
+					.is_some_and(|(width, height)| width != height)
+			{
+				pack_index.non_square_pack_icon = Some(pack_file.relative_path.as_str().to_string());
+			}
+
+			if let Some(asset_type) = font_definition_asset_type(pack_file.relative_path.as_str())
+				.filter(|_| may_downscale_textures)
+			{
+				if let Some(font) = read_json(vfs, &pack_file.file_path, asset_type).await {
+					font_glyph_grids.extend(font_bitmap_glyph_grids(&font));
+				}
+			}
+
+			// Textures with material maps can't be downscaled, because their material maps
+			// can't be downscaled either, and shaders expect them to have the same size
+			if may_downscale_textures
+				&& material_map_globset.is_match(pack_file.relative_path.as_str())
+			{
+				if let Some(texture_path) =
+					material_map_texture_path(pack_file.relative_path.as_str())
+				{
+					pack_index.textures_with_material_maps.insert(texture_path);
+				}
+			}
+
+			// Animated textures are decoded to find duplicate frames, if enabled for them, and
+			// their frames are downscaled individually
+			let Some((texture_path, asset_type)) =
+				texture_path_and_metadata_asset_type(&pack_file.relative_path)
+					.filter(|_| gather_animation_frame_deduplications || may_downscale_textures)
+			else {
+				continue;
+			};
+
+			let png_file_options = png_file_options(processed_squash_options, texture_path);
+			// Exact data textures are never changed, and material maps are never downscaled,
+			// so their metadata must be kept as is
+			if png_file_options.preserve_exact_pixel_data
+				|| data_texture_globset.is_match(texture_path)
+			{
+				continue;
+			}
+			let downscaling_target_resolution = png_file_options
+				.downscaling_target_resolution
+				.filter(|_| !material_map_globset.is_match(texture_path));
+			if !png_file_options.deduplicate_animation_frames
+				&& downscaling_target_resolution.is_none()
+			{
+				continue;
+			}
+
+			// Previous runs may have deduplicated frames or downscaled textures even if that is
+			// not the case now, because other pack files changed
+			pack_index
+				.interdependent_textures
+				.insert(texture_path.to_string());
+
+			let Some(texture_metadata) = read_json(vfs, &pack_file.file_path, asset_type).await
+			else {
+				continue;
+			};
+
+			// Only decode textures that are animated
+			if texture_metadata.get("animation").is_none() {
+				continue;
+			}
+
+			let texture_file_path = pack_file.file_path.with_extension("");
+
+			if let Some(target_resolution) = downscaling_target_resolution {
+				if let Some((frame_width, frame_height)) =
+					read_png_dimensions(vfs, &texture_file_path)
+						.await
+						.and_then(|(width, height)| {
+							animation_frame_size(width, height, &texture_metadata).filter(
+								|(frame_width, frame_height)| {
+									width % frame_width == 0 && height % frame_height == 0
+								}
+							)
+						}) {
+					pack_index.texture_downscalings.insert(
+						texture_path.to_string(),
+						TextureDownscaling::new(frame_width, frame_height, target_resolution)
+					);
+				}
+			}
+
+			if !png_file_options.deduplicate_animation_frames {
+				continue;
+			}
+
+			let Some(png_data) = read_file(vfs, texture_file_path).await else {
+				continue;
+			};
+
+			// Decoding and hashing frames is CPU-bound, so do it concurrently with reading
+			// the rest of the pack files, like when processing them
+			let texture_path = texture_path.to_string();
+			animation_frame_deduplication_tasks.push(tokio::spawn(async move {
+				let (width, height, pixel_data) =
+					decode_png_pixels(png_data, png_file_options.maximum_width_and_height).ok()?;
+
+				AnimationFrameDeduplication::new(width, height, &pixel_data, &texture_metadata)
+					.map(|deduplication| (texture_path, Arc::new(deduplication)))
+			}));
+		}
+
+		for animation_frame_deduplication_task in animation_frame_deduplication_tasks {
+			if let Ok(Some((texture_path, deduplication))) = animation_frame_deduplication_task.await
+			{
+				pack_index
+					.animation_frame_deduplications
+					.insert(texture_path, deduplication);
+			}
+		}
+
+		// Font textures are downscaled glyph by glyph, so that glyphs keep being laid out in
+		// the grid their font definition expects
+		for font_glyph_grid in font_glyph_grids {
+			let Some(target_resolution) =
+				png_file_options(processed_squash_options, &font_glyph_grid.texture_path)
+					.downscaling_target_resolution
+			else {
+				continue;
+			};
+
+			let Some((width, height)) = read_png_dimensions(
+				vfs,
+				squash_options
+					.pack_directory
+					.join(&font_glyph_grid.texture_path)
+			)
+			.await
+			else {
+				continue;
+			};
+
+			if width % font_glyph_grid.columns == 0 && height % font_glyph_grid.rows == 0 {
+				pack_index.texture_downscalings.insert(
+					font_glyph_grid.texture_path,
+					TextureDownscaling::new(
+						width / font_glyph_grid.columns,
+						height / font_glyph_grid.rows,
+						target_resolution
+					)
+				);
+			}
+		}
+
+		let textures_with_material_maps = &pack_index.textures_with_material_maps;
+		pack_index
+			.texture_downscalings
+			.retain(|texture_path, _| !textures_with_material_maps.contains(texture_path));
+
+		pack_index.missing_sound_files = pack_index
+			.sound_definitions
+			.missing_sound_files(
+				&sound_file_paths
+					.iter()
+					.map(|sound_file_path| path_without_extension(sound_file_path).to_string())
+					.collect()
+			)
+			.map(|(definitions_path, sound_file_path)| {
+				(
+					definitions_path.to_string(),
+					format!("{sound_file_path}.ogg")
+				)
+			})
+			.collect();
+		pack_index.missing_sound_files.sort_unstable();
+
+		if sound_definitions_valid {
+			pack_index.unreferenced_sound_files = pack_index
+				.sound_definitions
+				.unreferenced_sound_files(sound_file_paths.iter().map(String::as_str))
+				.map(String::from)
+				.collect();
+			pack_index.unreferenced_sound_files.sort_unstable();
+		}
+
+		// Vanilla include shaders can be imported too, unless the pack replaces them
+		if let Some(vanilla_include_shaders_directory) = &squash_options
+			.global_options
+			.vanilla_include_shaders_directory
+		{
+			for include_shader in vfs
+				.file_iterator(
+					vanilla_include_shaders_directory,
+					IteratorTraversalOptions {
+						ignore_system_and_hidden_files: true
+					}
+				)
+				.flatten()
+			{
+				let relative_path = format!(
+					"{VANILLA_INCLUDE_SHADERS_DIRECTORY}/{}",
+					include_shader.relative_path.as_str()
+				);
+
+				if let Entry::Vacant(entry) = importable_shaders.entry(relative_path) {
+					if let Some(source) = read_text(vfs, &include_shader.file_path).await {
+						entry.insert(source);
+					}
+				}
+			}
+		}
+		pack_index.importable_shaders = Arc::new(importable_shaders);
+
+		if gather_atlas_sprites && atlas_definitions_valid {
+			pack_index.directory_listed_atlas_sprites = Some(
+				atlas_definitions
+					.directory_listed_sprites(texture_paths.iter().map(String::as_str))
+					.into_iter()
+					.map(String::from)
+					.collect()
+			);
+		}
+
+		pack_index
+	}
+
+	/// Returns the translations of the fallback locale for the specified namespace, if any
+	/// were gathered.
+	pub fn fallback_translations(&self, namespace: &str) -> Option<&Arc<Translations>> {
+		self.fallback_translations.get(namespace)
+	}
+
+	/// Returns the frame deduplication to apply to the animated texture at the specified
+	/// relative path, if it has duplicate frames that should be removed.
+	pub fn animation_frame_deduplication(
+		&self,
+		texture_path: &str
+	) -> Option<&Arc<AnimationFrameDeduplication>> {
+		self.animation_frame_deduplications.get(texture_path)
+	}
+
+	/// Returns whether the texture at the specified relative path and its metadata file are
+	/// optimized together, so that each must be processed again when the other changes. This is
+	/// the case when the texture may have its animation frames deduplicated or be downscaled.
+	pub fn is_interdependent_with_metadata(&self, texture_path: &str) -> bool {
+		self.interdependent_textures.contains(texture_path)
+	}
+
+	/// Returns the downscaling planned for the texture at the specified relative path, if it
+	/// is made up of cells that should be downscaled individually.
+	pub fn texture_downscaling(&self, texture_path: &str) -> Option<TextureDownscaling> {
+		self.texture_downscalings.get(texture_path).copied()
+	}
+
+	/// Returns whether the texture at the specified relative path has material maps, such as
+	/// normal or specular maps, which prevent it from being downscaled. `false` is returned if
+	/// no texture may be downscaled.
+	pub fn has_material_maps(&self, texture_path: &str) -> bool {
+		self.textures_with_material_maps.contains(texture_path)
+	}
+
+	/// Returns the properties of the sound file at the specified relative path, as defined by
+	/// the sound events that reference it, or `None` if no known sound event references it.
+	pub fn sound_properties(&self, sound_file_path: &str) -> Option<SoundProperties> {
+		self.sound_definitions.sound_properties(sound_file_path)
+	}
+
+	/// Returns the relative paths of the `sounds.json` files that reference sound files which
+	/// are not in the pack, alongside the relative paths of those sound files.
+	pub fn missing_sound_files(&self) -> &[(String, String)] {
+		&self.missing_sound_files
+	}
+
+	/// Returns the relative paths of the sound files in the pack that no sound event
+	/// references, so the game never plays them.
+	pub fn unreferenced_sound_files(&self) -> &[String] {
+		&self.unreferenced_sound_files
+	}
+
+	/// Returns the relative path of the pack icon, if it is not square, so the game may not
+	/// show it.
+	pub fn non_square_pack_icon(&self) -> Option<&str> {
+		self.non_square_pack_icon.as_deref()
+	}
+
+	/// Returns the fingerprints of the sound files of the pack. Unlike the rest of the data of
+	/// this index, they are added while sound files are processed, so they are only complete
+	/// after every sound file is processed.
+	pub fn sound_fingerprints(&self) -> &Arc<SoundFingerprints> {
+		&self.sound_fingerprints
+	}
+
+	/// Returns the source code of the include shaders that other shaders may import, which
+	/// come from the pack and, if configured, vanilla Minecraft.
+	pub fn importable_shaders(&self) -> &Arc<ImportableShaders> {
+		&self.importable_shaders
+	}
+
+	/// Returns whether the vertex or fragment shader at the specified relative path has
+	/// `#moj_import` directives that may be expanded to the source code of importable shaders.
+	pub fn imports_shaders(&self, shader_path: &str) -> bool {
+		!self.importable_shaders.is_empty() && self.importing_shaders.contains(shader_path)
+	}
+
+	/// Returns whether the texture at the specified relative path may be stitched into an
+	/// atlas as a result of the game listing the contents of a directory, according to the
+	/// known atlas definitions and the options of the texture. `true` is returned if this
+	/// could not be determined.
+	pub fn may_be_directory_listed_atlas_sprite(&self, texture_path: &str) -> bool {
+		self.directory_listed_atlas_sprites
+			.as_ref()
+			.map_or(true, |sprites| sprites.contains(texture_path))
+	}
+}
+
+/// Returns the namespace, locale and language asset type of the language file at the
+/// specified relative path, or `None` if the path does not belong to a language file.
+pub fn language_file_namespace_and_locale<'path>(
+	relative_path: &'path RelativePath<'_>
+) -> Option<(&'path str, &'path str, PackFileAssetType)> {
+	match relative_path.as_str().split('/').collect::<Vec<_>>()[..] {
+		["assets", namespace, "lang", file_name] => {
+			let (locale, extension) = file_name.rsplit_once('.')?;
+			let asset_type = match extension {
+				"json" => PackFileAssetType::MinecraftLanguage,
+				"jsonc" => PackFileAssetType::MinecraftLanguageWithComments,
+				_ => return None
+			};
+
+			(!locale.is_empty()).then_some((namespace, locale, asset_type))
+		}
+		_ => None
+	}
+}
+
+/// Returns the relative path of the texture that the texture metadata file at the specified
+/// relative path belongs to, and the asset type of the metadata file, or `None` if the path
+/// does not belong to a texture metadata file.
+pub fn texture_path_and_metadata_asset_type<'path>(
+	relative_path: &'path RelativePath<'_>
+) -> Option<(&'path str, PackFileAssetType)> {
+	let relative_path = relative_path.as_str();
+	let (texture_path, asset_type) = if let Some(path) = relative_path.strip_suffix(".mcmeta") {
+		(path, PackFileAssetType::MinecraftTextureMetadata)
+	} else {
+		(
+			relative_path.strip_suffix(".mcmetac")?,
+			PackFileAssetType::MinecraftTextureMetadataWithComments
+		)
+	};
+
+	(texture_path.starts_with("assets/")
+		&& texture_path.contains("/textures/")
+		&& texture_path.ends_with(".png"))
+	.then_some((texture_path, asset_type))
+}
+
+/// Returns the PNG file options that apply to the texture at the specified relative path,
+/// following the same precedence rules as when processing it.
+fn png_file_options(
+	processed_squash_options: &ProcessedSquashOptions,
+	texture_path: &str
+) -> PngFileOptions {
+	processed_squash_options
+		.file_options_globs
+		.matches(texture_path)
+		.into_iter()
+		.find_map(|i| match processed_squash_options.options.file_options[i] {
+			FileOptions::PngFileOptions(png_file_options) => Some(png_file_options),
+			_ => None
+		})
+		.unwrap_or_default()
+}
+
+/// Reads and parses the JSON file at the specified virtual filesystem path, returning
+/// `None` if any error occurs.
+async fn read_json<F: VirtualFileSystem>(
+	vfs: &F,
+	path: impl AsRef<Path>,
+	asset_type: PackFileAssetType
+) -> Option<Value> {
+	let mut file = vfs.open(path).ok()?;
+	let mut file_data = Vec::with_capacity(file.file_size_hint.try_into().unwrap_or(usize::MAX));
+
+	file.file_read.read_to_end(&mut file_data).await.ok()?;
+
+	parse_json(&file_data, asset_type, true).ok()
+}
+
+/// Reads the UTF-8 text file at the specified virtual filesystem path, without its BOM,
+/// returning `None` if any error occurs.
+async fn read_text<F: VirtualFileSystem>(vfs: &F, path: impl AsRef<Path>) -> Option<String> {
+	let mut file = vfs.open(path).ok()?;
+	let mut file_data = Vec::with_capacity(file.file_size_hint.try_into().unwrap_or(usize::MAX));
+
+	file.file_read.read_to_end(&mut file_data).await.ok()?;
+
+	std::str::from_utf8(strip_utf8_bom(&file_data))
+		.ok()
+		.map(String::from)
+}
+
+/// Reads the width and height of the PNG file at the specified virtual filesystem path from
+/// its header, without decoding it, returning `None` if any error occurs.
+async fn read_png_dimensions<F: VirtualFileSystem>(
+	vfs: &F,
+	path: impl AsRef<Path>
+) -> Option<(u32, u32)> {
+	let mut file = vfs.open(path).ok()?;
+	let mut header = [0; 24];
+
+	file.file_read.read_exact(&mut header).await.ok()?;
+
+	let width = u32::from_be_bytes(header[16..20].try_into().unwrap());
+	let height = u32::from_be_bytes(header[20..24].try_into().unwrap());
+
+	// Signature, IHDR chunk length and type, and then the non-zero width and height
+	(header[..16] == *b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR" && width > 0 && height > 0)
+		.then_some((width, height))
+}
+
+/// Reads the whole file at the specified virtual filesystem path, returning `None` if any
+/// error occurs.
+async fn read_file<F: VirtualFileSystem>(vfs: &F, path: impl AsRef<Path>) -> Option<BytesMut> {
+	let mut file = vfs.open(path).ok()?;
+	let mut file_data = Vec::with_capacity(file.file_size_hint.try_into().unwrap_or(usize::MAX));
+
+	file.file_read.read_to_end(&mut file_data).await.ok()?;
+
+	Some(BytesMut::from(&file_data[..]))
+}
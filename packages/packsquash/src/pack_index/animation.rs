@@ -0,0 +1,182 @@
+//! Contains code to find pixel-identical frames in animated textures, and to remap the frames
+//! their animation metadata references accordingly.
+//!
+//! References:
+//! - <https://minecraft.wiki/w/Resource_pack#Animation>
+//! - Minecraft class `net.minecraft.client.resources.metadata.animation.AnimationMetadataSection`
+
+use ahash::AHashMap;
+use serde_json::Value;
+
+#[cfg(test)]
+mod tests;
+
+/// The size of an RGBA8 pixel, in bytes.
+const PIXEL_SIZE: usize = 4;
+
+/// The result of analyzing the frames of an animated texture laid out as a vertical strip,
+/// which contains duplicate frames that can be removed.
+#[derive(Debug, PartialEq)]
+pub struct AnimationFrameDeduplication {
+	/// The height of each frame, in pixels.
+	frame_height: u32,
+	/// The original indexes of the frames to keep, in ascending order.
+	unique_frames: Vec<usize>,
+	/// The index each original frame has after removing duplicates.
+	frame_mapping: Vec<usize>
+}
+
+impl AnimationFrameDeduplication {
+	/// Analyzes the frames of an animated texture with the specified dimensions, RGBA8 pixel data
+	/// and texture metadata. Returns `None` if the texture is not animated, has no duplicate
+	/// frames, or its frames are laid out or referenced in a way that is not supported.
+	pub fn new(
+		image_width: u32,
+		image_height: u32,
+		pixel_data: &[u8],
+		texture_metadata: &Value
+	) -> Option<Self> {
+		let animation = texture_metadata.get("animation")?.as_object()?;
+		let (frame_width, frame_height) =
+			animation_frame_size(image_width, image_height, texture_metadata)?;
+
+		// Textures with several frames per row are not vertical strips. Removing frames from
+		// them would require rearranging the remaining ones, which is not worth the effort
+		if frame_width != image_width || image_height % frame_height != 0 {
+			return None;
+		}
+
+		let frame_count = (image_height / frame_height) as usize;
+		let frame_size = image_width as usize * frame_height as usize * PIXEL_SIZE;
+		if frame_count < 2 || pixel_data.len() != frame_count * frame_size {
+			return None;
+		}
+
+		// Frames out of bounds are not valid, so do not bother dealing with them
+		if let Some(frames) = animation.get("frames") {
+			for frame in frames.as_array()? {
+				let frame_index = match frame {
+					Value::Object(frame) => frame.get("index")?,
+					frame => frame
+				};
+
+				if frame_index.as_u64()? >= frame_count as u64 {
+					return None;
+				}
+			}
+		}
+
+		let mut unique_frame_indexes = AHashMap::with_capacity(frame_count);
+		let mut unique_frames = Vec::with_capacity(frame_count);
+		let mut frame_mapping = Vec::with_capacity(frame_count);
+
+		for (i, frame) in pixel_data.chunks_exact(frame_size).enumerate() {
+			frame_mapping.push(*unique_frame_indexes.entry(frame).or_insert_with(|| {
+				unique_frames.push(i);
+				unique_frames.len() - 1
+			}));
+		}
+
+		(unique_frames.len() < frame_count).then_some(Self {
+			frame_height,
+			unique_frames,
+			frame_mapping
+		})
+	}
+
+	/// Returns the number of duplicate frames that are removed.
+	pub fn removed_frame_count(&self) -> usize {
+		self.frame_mapping.len() - self.unique_frames.len()
+	}
+
+	/// Returns the RGBA8 pixel data of the texture this deduplication was computed for, with
+	/// its duplicate frames removed, and its new height in pixels. `None` is returned if the
+	/// pixel data does not have the size it had when the deduplication was computed.
+	pub fn deduplicate_frames(&self, image_width: u32, pixel_data: &[u8]) -> Option<(u32, Vec<u8>)> {
+		let frame_size = image_width as usize * self.frame_height as usize * PIXEL_SIZE;
+
+		(pixel_data.len() == frame_size * self.frame_mapping.len()).then(|| {
+			(
+				self.frame_height * self.unique_frames.len() as u32,
+				self.unique_frames
+					.iter()
+					.flat_map(|frame| &pixel_data[frame * frame_size..(frame + 1) * frame_size])
+					.copied()
+					.collect()
+			)
+		})
+	}
+
+	/// Rewrites the frames of the animation described by the specified texture metadata so
+	/// that it plays identically after removing duplicate frames. When the metadata does not
+	/// list frames explicitly, a frame list equivalent to the implicit one is added.
+	pub fn remap_frames(&self, texture_metadata: &mut Value) {
+		let Some(animation) = texture_metadata
+			.get_mut("animation")
+			.and_then(Value::as_object_mut)
+		else {
+			return;
+		};
+
+		match animation.get_mut("frames").and_then(Value::as_array_mut) {
+			Some(frames) => {
+				for frame in frames {
+					let frame_index = match frame {
+						Value::Object(frame) => frame.get_mut("index"),
+						frame => Some(frame)
+					};
+
+					if let Some(frame_index) = frame_index {
+						if let Some(remapped_index) = frame_index
+							.as_u64()
+							.and_then(|index| self.frame_mapping.get(index as usize))
+						{
+							*frame_index = (*remapped_index).into();
+						}
+					}
+				}
+			}
+			None => {
+				animation.insert(
+					"frames".into(),
+					self.frame_mapping
+						.iter()
+						.map(|&frame_index| Value::from(frame_index))
+						.collect()
+				);
+			}
+		}
+	}
+}
+
+/// Returns the width and height of the frames of the animated texture with the specified
+/// dimensions and texture metadata, in pixels, or `None` if the texture is not animated or
+/// declares invalid frame dimensions.
+pub fn animation_frame_size(
+	image_width: u32,
+	image_height: u32,
+	texture_metadata: &Value
+) -> Option<(u32, u32)> {
+	let animation = texture_metadata.get("animation")?.as_object()?;
+
+	// Returns Some(None) for missing dimensions, and None for invalid ones
+	let frame_dimension = |key: &str| match animation.get(key) {
+		Some(dimension) => dimension
+			.as_u64()
+			.filter(|dimension| *dimension > 0)
+			.map(|dimension| Some(u32::try_from(dimension).unwrap_or(u32::MAX))),
+		None => Some(None)
+	};
+
+	Some(
+		match (frame_dimension("width")?, frame_dimension("height")?) {
+			(Some(width), Some(height)) => (width, height),
+			(Some(width), None) => (width, image_height),
+			(None, Some(height)) => (image_width, height),
+			(None, None) => {
+				let dimension = image_width.min(image_height);
+				(dimension, dimension)
+			}
+		}
+	)
+}
@@ -0,0 +1,90 @@
+use pretty_assertions::assert_eq;
+
+use super::*;
+
+/// Returns the RGBA8 pixel data of a 1x1 pixel frame of the specified gray level.
+fn frame(gray_level: u8) -> [u8; PIXEL_SIZE] {
+	[gray_level, gray_level, gray_level, 255]
+}
+
+/// The pixel data of a 1 pixel wide vertical strip of frames with a ping-pong animation.
+fn ping_pong_strip() -> Vec<u8> {
+	[frame(0), frame(1), frame(2), frame(1), frame(0)].concat()
+}
+
+#[test]
+fn duplicate_frames_are_removed_and_implicit_frames_are_synthesized() {
+	let deduplication = AnimationFrameDeduplication::new(
+		1,
+		5,
+		&ping_pong_strip(),
+		&serde_json::json!({ "animation": { "frametime": 2 } })
+	)
+	.expect("The animation has duplicate frames");
+
+	assert_eq!(deduplication.removed_frame_count(), 2);
+	assert_eq!(
+		deduplication.deduplicate_frames(1, &ping_pong_strip()),
+		Some((3, [frame(0), frame(1), frame(2)].concat()))
+	);
+
+	let mut texture_metadata = serde_json::json!({ "animation": { "frametime": 2 } });
+	deduplication.remap_frames(&mut texture_metadata);
+
+	assert_eq!(
+		texture_metadata,
+		serde_json::json!({ "animation": { "frametime": 2, "frames": [0, 1, 2, 1, 0] } })
+	);
+}
+
+#[test]
+fn explicit_frames_are_remapped() {
+	let mut texture_metadata = serde_json::json!({
+		"animation": { "frames": [4, { "index": 3, "time": 5 }, 2] }
+	});
+
+	AnimationFrameDeduplication::new(1, 5, &ping_pong_strip(), &texture_metadata)
+		.expect("The animation has duplicate frames")
+		.remap_frames(&mut texture_metadata);
+
+	assert_eq!(
+		texture_metadata,
+		serde_json::json!({ "animation": { "frames": [0, { "index": 1, "time": 5 }, 2] } })
+	);
+}
+
+#[test]
+fn unsupported_animations_are_not_deduplicated() {
+	// No duplicate frames
+	assert_eq!(
+		AnimationFrameDeduplication::new(
+			1,
+			3,
+			&[frame(0), frame(1), frame(2)].concat(),
+			&serde_json::json!({ "animation": {} })
+		),
+		None
+	);
+
+	// Out of bounds frame index
+	assert_eq!(
+		AnimationFrameDeduplication::new(
+			1,
+			5,
+			&ping_pong_strip(),
+			&serde_json::json!({ "animation": { "frames": [0, 5] } })
+		),
+		None
+	);
+
+	// Frames laid out in a grid
+	assert_eq!(
+		AnimationFrameDeduplication::new(
+			5,
+			1,
+			&ping_pong_strip(),
+			&serde_json::json!({ "animation": { "width": 1, "height": 1 } })
+		),
+		None
+	);
+}
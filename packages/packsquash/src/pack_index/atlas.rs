@@ -0,0 +1,282 @@
+//! Contains code to find out which textures the game stitches into atlases by listing the
+//! contents of texture directories, according to the atlas definitions of the game and a pack.
+//!
+//! References:
+//! - <https://minecraft.wiki/w/Resource_pack#Atlases>
+//! - Minecraft classes `net.minecraft.client.renderer.texture.atlas.SpriteSources` and
+//!   `net.minecraft.client.renderer.texture.atlas.SpriteResourceLoader`
+
+use ahash::{AHashMap, AHashSet};
+use regex::Regex;
+use serde_json::Value;
+
+use crate::pack_file::asset_type::PackFileAssetType;
+
+#[cfg(test)]
+mod tests;
+
+/// The namespace Minecraft assumes for resource locations that do not specify one.
+const DEFAULT_NAMESPACE: &str = "minecraft";
+
+/// The directory sprite sources of the atlases defined by vanilla Minecraft, as tuples of atlas
+/// identifier, source directory and sprite prefix. Atlas definitions in packs add sources to
+/// these, instead of replacing them. Sprite sources of other types are not relevant because
+/// they do not list directories.
+const VANILLA_DIRECTORY_SOURCES: &[(&str, &str, &str)] = &[
+	("minecraft:blocks", "block", "block/"),
+	("minecraft:blocks", "item", "item/"),
+	("minecraft:blocks", "entity/conduit", "entity/conduit/"),
+	(
+		"minecraft:banner_patterns",
+		"entity/banner",
+		"entity/banner/"
+	),
+	("minecraft:beds", "entity/bed", "entity/bed/"),
+	("minecraft:chests", "entity/chest", "entity/chest/"),
+	(
+		"minecraft:decorated_pot",
+		"entity/decorated_pot",
+		"entity/decorated_pot/"
+	),
+	("minecraft:gui", "gui/sprites", ""),
+	("minecraft:map_decorations", "map/decorations", ""),
+	("minecraft:mob_effects", "mob_effect", ""),
+	("minecraft:paintings", "painting", ""),
+	("minecraft:particles", "particle", ""),
+	(
+		"minecraft:shield_patterns",
+		"entity/shield",
+		"entity/shield/"
+	),
+	(
+		"minecraft:shulker_boxes",
+		"entity/shulker",
+		"entity/shulker/"
+	),
+	("minecraft:signs", "entity/signs", "entity/signs/")
+];
+
+/// A source of sprites for an atlas, as defined in an atlas definition file.
+enum SpriteSource {
+	/// Adds every texture in a directory of any namespace, listing its contents.
+	Directory { source: String, prefix: String },
+	/// Adds a single texture with a known location.
+	Single { sprite: (String, String) },
+	/// Removes the sprites added by previous sources whose location matches some patterns.
+	Filter {
+		namespace: Option<Regex>,
+		path: Option<Regex>
+	},
+	/// Adds sprites generated by recoloring textures with known locations.
+	PalettedPermutations { sprites: Vec<(String, String)> },
+	/// Any other source, which does not list directories.
+	Other
+}
+
+/// The atlas definitions that apply to a pack, which determine the textures that the game
+/// discovers by listing texture directories.
+pub struct AtlasDefinitions {
+	atlases: AHashMap<String, Vec<SpriteSource>>
+}
+
+impl AtlasDefinitions {
+	/// Creates the atlas definitions of vanilla Minecraft.
+	pub fn new() -> Self {
+		let mut atlases = AHashMap::<_, Vec<_>>::new();
+
+		for (atlas, source, prefix) in VANILLA_DIRECTORY_SOURCES {
+			atlases
+				.entry(atlas.to_string())
+				.or_default()
+				.push(SpriteSource::Directory {
+					source: source.to_string(),
+					prefix: prefix.to_string()
+				});
+		}
+
+		Self { atlases }
+	}
+
+	/// Adds the sprite sources defined in the specified atlas definition to the atlas with the
+	/// specified identifier. Returns `None` if the definition is malformed or contains patterns
+	/// that can't be interpreted, in which case the definitions are left unchanged.
+	pub fn add(&mut self, atlas: String, definition: &Value) -> Option<()> {
+		let sources = definition
+			.get("sources")?
+			.as_array()?
+			.iter()
+			.map(parse_sprite_source)
+			.collect::<Option<Vec<_>>>()?;
+
+		self.atlases.entry(atlas).or_default().extend(sources);
+
+		Some(())
+	}
+
+	/// Returns the relative paths of the textures, out of the specified ones, that are
+	/// stitched into some atlas as a result of listing the contents of a directory.
+	pub fn directory_listed_sprites<'path>(
+		&self,
+		texture_paths: impl IntoIterator<Item = &'path str> + Clone
+	) -> AHashSet<&'path str> {
+		let mut directory_listed_sprites = AHashSet::new();
+
+		for sources in self.atlases.values() {
+			// Sprite locations, mapped to the listed texture they were loaded from, if any
+			let mut sprites = AHashMap::new();
+
+			for source in sources {
+				match source {
+					SpriteSource::Directory { source, prefix } => {
+						for texture_path in texture_paths.clone() {
+							let Some((namespace, path)) = texture_namespace_and_path(texture_path)
+							else {
+								continue;
+							};
+
+							let path_in_source = if source.is_empty() {
+								Some(path)
+							} else {
+								path.strip_prefix(source.as_str())
+									.and_then(|path| path.strip_prefix('/'))
+							};
+
+							if let Some(path_in_source) = path_in_source {
+								sprites.insert(
+									(namespace.to_string(), format!("{prefix}{path_in_source}")),
+									Some(texture_path)
+								);
+							}
+						}
+					}
+					SpriteSource::Single { sprite } => {
+						sprites.insert(sprite.clone(), None);
+					}
+					SpriteSource::Filter { namespace, path } => {
+						sprites.retain(|(sprite_namespace, sprite_path), _| {
+							!namespace
+								.as_ref()
+								.map_or(true, |pattern| pattern.is_match(sprite_namespace))
+								|| !path
+									.as_ref()
+									.map_or(true, |pattern| pattern.is_match(sprite_path))
+						});
+					}
+					SpriteSource::PalettedPermutations {
+						sprites: generated_sprites
+					} => {
+						for sprite in generated_sprites {
+							sprites.insert(sprite.clone(), None);
+						}
+					}
+					SpriteSource::Other => {}
+				}
+			}
+
+			directory_listed_sprites.extend(sprites.into_values().flatten());
+		}
+
+		directory_listed_sprites
+	}
+}
+
+/// Returns the identifier and asset type of the atlas definition at the specified relative
+/// path, or `None` if the path does not belong to an atlas definition.
+pub fn atlas_identifier_and_asset_type(relative_path: &str) -> Option<(String, PackFileAssetType)> {
+	let (relative_path, asset_type) = if let Some(path) = relative_path.strip_suffix(".json") {
+		(path, PackFileAssetType::GenericJson)
+	} else {
+		(
+			relative_path.strip_suffix(".jsonc")?,
+			PackFileAssetType::GenericJsonWithComments
+		)
+	};
+
+	let (namespace, atlas) = relative_path
+		.strip_prefix("assets/")?
+		.split_once("/atlases/")?;
+
+	(!namespace.contains('/') && !atlas.is_empty())
+		.then(|| (format!("{namespace}:{atlas}"), asset_type))
+}
+
+/// Returns the namespace and the path relative to the textures directory, without extension,
+/// of the texture at the specified relative path, or `None` if the path does not belong to a
+/// texture.
+fn texture_namespace_and_path(relative_path: &str) -> Option<(&str, &str)> {
+	let (namespace, path) = relative_path
+		.strip_suffix(".png")?
+		.strip_prefix("assets/")?
+		.split_once("/textures/")?;
+
+	(!namespace.contains('/') && !path.is_empty()).then_some((namespace, path))
+}
+
+/// Parses a sprite source object of an atlas definition.
+fn parse_sprite_source(source: &Value) -> Option<SpriteSource> {
+	let source_type = source.get("type")?.as_str()?;
+
+	Some(
+		match source_type
+			.strip_prefix("minecraft:")
+			.unwrap_or(source_type)
+		{
+			"directory" => SpriteSource::Directory {
+				source: source.get("source")?.as_str()?.into(),
+				prefix: source.get("prefix")?.as_str()?.into()
+			},
+			"single" => {
+				let resource = source.get("resource")?.as_str()?;
+				let sprite = match source.get("sprite") {
+					Some(sprite) => sprite.as_str()?,
+					None => resource
+				};
+
+				SpriteSource::Single {
+					sprite: parse_resource_location(sprite)
+				}
+			}
+			"filter" => {
+				let pattern = source.get("pattern")?;
+
+				SpriteSource::Filter {
+					namespace: pattern.get("namespace").map(parse_pattern).transpose()?,
+					path: pattern.get("path").map(parse_pattern).transpose()?
+				}
+			}
+			"paletted_permutations" => {
+				let permutations = source.get("permutations")?.as_object()?;
+
+				SpriteSource::PalettedPermutations {
+					sprites: source
+						.get("textures")?
+						.as_array()?
+						.iter()
+						.map(|texture| texture.as_str().map(parse_resource_location))
+						.collect::<Option<Vec<_>>>()?
+						.into_iter()
+						.flat_map(|(namespace, path)| {
+							permutations
+								.keys()
+								.map(move |suffix| (namespace.clone(), format!("{path}_{suffix}")))
+						})
+						.collect()
+				}
+			}
+			_ => SpriteSource::Other
+		}
+	)
+}
+
+/// Parses a regular expression of a filter sprite source, which must match whole strings.
+fn parse_pattern(pattern: &Value) -> Option<Regex> {
+	Regex::new(&format!("^(?:{})$", pattern.as_str()?)).ok()
+}
+
+/// Parses a resource location string into its namespace and path.
+fn parse_resource_location(resource_location: &str) -> (String, String) {
+	match resource_location.split_once(':') {
+		Some((namespace, path)) => (namespace.into(), path.into()),
+		None => (DEFAULT_NAMESPACE.into(), resource_location.into())
+	}
+}
@@ -0,0 +1,105 @@
+use pretty_assertions::assert_eq;
+
+use super::*;
+
+const TEXTURE_PATHS: &[&str] = &[
+	"assets/minecraft/textures/block/stone.png",
+	"assets/mypack/textures/item/ruby.png",
+	"assets/mypack/textures/gui/title/logo.png",
+	"assets/mypack/textures/custom/glint.png",
+	"assets/mypack/textures/custom/hidden/glint.png"
+];
+
+fn directory_listed_sprites(atlas_definitions: &AtlasDefinitions) -> Vec<&'static str> {
+	let mut sprites = atlas_definitions
+		.directory_listed_sprites(TEXTURE_PATHS.iter().copied())
+		.into_iter()
+		.collect::<Vec<_>>();
+
+	sprites.sort_unstable();
+	sprites
+}
+
+#[test]
+fn vanilla_directory_sources_list_textures_of_every_namespace() {
+	assert_eq!(
+		directory_listed_sprites(&AtlasDefinitions::new()),
+		[
+			"assets/minecraft/textures/block/stone.png",
+			"assets/mypack/textures/item/ruby.png"
+		]
+	);
+}
+
+#[test]
+fn pack_atlas_sources_are_applied_in_order() {
+	let mut atlas_definitions = AtlasDefinitions::new();
+
+	atlas_definitions
+		.add(
+			"minecraft:blocks".into(),
+			&serde_json::json!({
+				"sources": [
+					{ "type": "directory", "source": "custom", "prefix": "custom/" },
+					{ "type": "filter", "pattern": { "namespace": "mypack", "path": "custom/hidden/.*" } },
+					{ "type": "filter", "pattern": { "path": "block/.+" } },
+					{ "type": "single", "resource": "minecraft:block/stone" },
+					{
+						"type": "paletted_permutations",
+						"textures": [ "mypack:item/ruby" ],
+						"palette_key": "mypack:palettes/key",
+						"permutations": { "dark": "mypack:palettes/dark" }
+					}
+				]
+			})
+		)
+		.expect("The atlas definition is valid");
+
+	assert_eq!(
+		directory_listed_sprites(&atlas_definitions),
+		[
+			"assets/mypack/textures/custom/glint.png",
+			"assets/mypack/textures/item/ruby.png"
+		]
+	);
+}
+
+#[test]
+fn malformed_atlas_definitions_are_rejected() {
+	let mut atlas_definitions = AtlasDefinitions::new();
+
+	assert!(atlas_definitions
+		.add(
+			"mypack:custom".into(),
+			&serde_json::json!({
+				"sources": [
+					{ "type": "directory", "source": "custom", "prefix": "custom/" },
+					{ "type": "filter", "pattern": { "path": "(unclosed" } }
+				]
+			})
+		)
+		.is_none());
+
+	assert_eq!(
+		directory_listed_sprites(&atlas_definitions),
+		[
+			"assets/minecraft/textures/block/stone.png",
+			"assets/mypack/textures/item/ruby.png"
+		]
+	);
+}
+
+#[test]
+fn atlas_identifiers_are_derived_from_paths() {
+	assert_eq!(
+		atlas_identifier_and_asset_type("assets/mypack/atlases/custom.jsonc"),
+		Some((
+			"mypack:custom".into(),
+			PackFileAssetType::GenericJsonWithComments
+		))
+	);
+	assert_eq!(
+		atlas_identifier_and_asset_type("assets/mypack/models/atlases/custom.json"),
+		None
+	);
+}
@@ -0,0 +1,134 @@
+//! Contains code to find sound files of a pack that sound nearly identical, according to
+//! perceptual fingerprints computed while their audio is decoded.
+//!
+//! References:
+//! - Haitsma, J., & Kalker, T. (2002). A Highly Robust Audio Fingerprinting System. In
+//!   Proceedings of the 3rd International Conference on Music Information Retrieval.
+
+use std::sync::{Mutex, PoisonError};
+
+#[cfg(test)]
+mod tests;
+
+/// The maximum fraction of differing bits between the fingerprints of two sounds for them
+/// to be considered near-duplicates. Unrelated sounds are expected to differ in about half
+/// of their bits, while lossy re-encodes of the same sound usually differ in a few percent.
+const NEAR_DUPLICATE_BIT_ERROR_RATE: f64 = 0.15;
+/// The maximum relative difference between the durations of two sounds for them to be
+/// considered near-duplicates.
+const DURATION_TOLERANCE: f64 = 0.1;
+
+/// A perceptual fingerprint of a sound, which is a sequence of sub-fingerprints of 16 bits
+/// that describe how its spectrum evolves over time. Similar-sounding sounds have similar
+/// fingerprints, no matter their gain, sampling frequency or channel count.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SoundFingerprint(Vec<u16>);
+
+impl SoundFingerprint {
+	/// Creates a new sound fingerprint from its sub-fingerprints, in chronological order.
+	pub fn new(sub_fingerprints: Vec<u16>) -> Self {
+		Self(sub_fingerprints)
+	}
+
+	/// Returns the fraction of bits that differ between this fingerprint and another one,
+	/// or `None` if their durations are too different to meaningfully compare them.
+	pub fn bit_error_rate(&self, other: &Self) -> Option<f64> {
+		let (shorter, longer) = if self.0.len() <= other.0.len() {
+			(&self.0, &other.0)
+		} else {
+			(&other.0, &self.0)
+		};
+
+		if shorter.is_empty()
+			|| (shorter.len() as f64) < longer.len() as f64 * (1.0 - DURATION_TOLERANCE)
+		{
+			return None;
+		}
+
+		let differing_bits = shorter
+			.iter()
+			.zip(longer)
+			.map(|(a, b)| (a ^ b).count_ones() as usize)
+			.sum::<usize>();
+
+		Some(differing_bits as f64 / (shorter.len() * u16::BITS as usize) as f64)
+	}
+}
+
+/// A thread-safe collection of the fingerprints of the sound files of a pack, which are
+/// added as they are processed.
+#[derive(Default)]
+pub struct SoundFingerprints {
+	fingerprints: Mutex<Vec<(String, SoundFingerprint)>>
+}
+
+impl SoundFingerprints {
+	/// Adds the fingerprint of the sound file at the specified relative path.
+	pub fn add(&self, sound_file_path: String, fingerprint: SoundFingerprint) {
+		self.fingerprints
+			.lock()
+			.unwrap_or_else(PoisonError::into_inner)
+			.push((sound_file_path, fingerprint));
+	}
+
+	/// Returns the clusters of sound files that sound nearly identical, as lists of their
+	/// relative paths sorted in ascending order. Every sound file in a cluster is a near-duplicate
+	/// of some other sound file in it. The clusters are sorted by their first path.
+	pub fn near_duplicate_clusters(&self) -> Vec<Vec<String>> {
+		let mut fingerprints = self
+			.fingerprints
+			.lock()
+			.unwrap_or_else(PoisonError::into_inner);
+
+		// Sort the fingerprints by duration, so that only those with similar durations, which
+		// are next to each other, need to be compared
+		fingerprints.sort_unstable_by(|(path_a, fingerprint_a), (path_b, fingerprint_b)| {
+			fingerprint_a
+				.0
+				.len()
+				.cmp(&fingerprint_b.0.len())
+				.then_with(|| path_a.cmp(path_b))
+		});
+
+		// Each fingerprint starts in its own cluster, identified by the index of a fingerprint
+		// in it. Clusters with near-duplicates are merged as they are found
+		let mut cluster_ids = (0..fingerprints.len()).collect::<Vec<_>>();
+		for (i, (_, fingerprint)) in fingerprints.iter().enumerate() {
+			for (j, (_, other_fingerprint)) in fingerprints.iter().enumerate().skip(i + 1) {
+				let Some(bit_error_rate) = fingerprint.bit_error_rate(other_fingerprint) else {
+					break;
+				};
+
+				if bit_error_rate <= NEAR_DUPLICATE_BIT_ERROR_RATE {
+					let cluster_id = cluster_root(&mut cluster_ids, i);
+					let other_cluster_id = cluster_root(&mut cluster_ids, j);
+					cluster_ids[other_cluster_id] = cluster_id;
+				}
+			}
+		}
+
+		let mut clusters = vec![vec![]; fingerprints.len()];
+		for (i, (sound_file_path, _)) in fingerprints.iter().enumerate() {
+			clusters[cluster_root(&mut cluster_ids, i)].push(sound_file_path.clone());
+		}
+
+		clusters.retain(|cluster| cluster.len() > 1);
+		for cluster in &mut clusters {
+			cluster.sort_unstable();
+		}
+		clusters.sort_unstable();
+
+		clusters
+	}
+}
+
+/// Returns the identifier of the cluster the element at the specified index belongs to,
+/// compressing the path to it along the way.
+fn cluster_root(cluster_ids: &mut [usize], mut index: usize) -> usize {
+	while cluster_ids[index] != index {
+		cluster_ids[index] = cluster_ids[cluster_ids[index]];
+		index = cluster_ids[index];
+	}
+
+	index
+}
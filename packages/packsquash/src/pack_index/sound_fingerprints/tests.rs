@@ -0,0 +1,108 @@
+use pretty_assertions::assert_eq;
+
+use super::*;
+
+/// Generates a pseudorandom fingerprint with the specified number of sub-fingerprints, whose
+/// bits are determined by the specified seed.
+fn random_fingerprint(seed: u32, length: usize) -> Vec<u16> {
+	let mut state = seed;
+
+	(0..length)
+		.map(|_| {
+			state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+			(state >> 16) as u16
+		})
+		.collect()
+}
+
+/// Flips one bit of every `period` sub-fingerprints of the specified fingerprint.
+fn with_flipped_bits(mut fingerprint: Vec<u16>, period: usize) -> Vec<u16> {
+	for sub_fingerprint in fingerprint.iter_mut().step_by(period) {
+		*sub_fingerprint ^= 1;
+	}
+
+	fingerprint
+}
+
+#[test]
+fn near_duplicates_are_clustered() {
+	let sound_fingerprints = SoundFingerprints::default();
+	let fingerprint = random_fingerprint(1, 100);
+
+	sound_fingerprints.add(
+		"assets/mypack/sounds/b.ogg".into(),
+		SoundFingerprint::new(fingerprint.clone())
+	);
+	sound_fingerprints.add(
+		"assets/mypack/sounds/unrelated.ogg".into(),
+		SoundFingerprint::new(random_fingerprint(2, 100))
+	);
+	sound_fingerprints.add(
+		"assets/mypack/sounds/a.ogg".into(),
+		SoundFingerprint::new(with_flipped_bits(fingerprint.clone(), 2))
+	);
+	sound_fingerprints.add(
+		"assets/mypack/sounds/c.ogg".into(),
+		SoundFingerprint::new(fingerprint[..95].to_vec())
+	);
+
+	assert_eq!(
+		sound_fingerprints.near_duplicate_clusters(),
+		vec![vec![
+			"assets/mypack/sounds/a.ogg".to_string(),
+			"assets/mypack/sounds/b.ogg".to_string(),
+			"assets/mypack/sounds/c.ogg".to_string()
+		]]
+	);
+}
+
+#[test]
+fn different_sounds_are_not_clustered() {
+	let sound_fingerprints = SoundFingerprints::default();
+	let fingerprint = random_fingerprint(1, 100);
+
+	sound_fingerprints.add(
+		"assets/mypack/sounds/a.ogg".into(),
+		SoundFingerprint::new(fingerprint.clone())
+	);
+	sound_fingerprints.add(
+		"assets/mypack/sounds/b.ogg".into(),
+		SoundFingerprint::new(random_fingerprint(2, 100))
+	);
+	// A sound that starts like another one, but lasts much longer
+	sound_fingerprints.add(
+		"assets/mypack/sounds/c.ogg".into(),
+		SoundFingerprint::new([fingerprint, random_fingerprint(3, 100)].concat())
+	);
+
+	assert!(sound_fingerprints.near_duplicate_clusters().is_empty());
+}
+
+#[test]
+fn near_duplicate_clusters_are_transitive() {
+	let sound_fingerprints = SoundFingerprints::default();
+	let fingerprint = random_fingerprint(1, 100);
+
+	// The fingerprints of b.ogg and c.ogg differ in 2 out of every 16 bits from the previous
+	// one, but the fingerprints of a.ogg and c.ogg differ in 4, which exceeds the threshold
+	for (sound_file_path, flipped_bits) in [("a", 0), ("b", 0b0011), ("c", 0b1111)] {
+		sound_fingerprints.add(
+			format!("assets/mypack/sounds/{sound_file_path}.ogg"),
+			SoundFingerprint::new(
+				fingerprint
+					.iter()
+					.map(|sub_fingerprint| sub_fingerprint ^ flipped_bits)
+					.collect()
+			)
+		);
+	}
+
+	assert_eq!(
+		sound_fingerprints.near_duplicate_clusters(),
+		vec![vec![
+			"assets/mypack/sounds/a.ogg".to_string(),
+			"assets/mypack/sounds/b.ogg".to_string(),
+			"assets/mypack/sounds/c.ogg".to_string()
+		]]
+	);
+}
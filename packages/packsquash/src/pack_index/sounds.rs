@@ -0,0 +1,187 @@
+//! Contains code to find out how the game plays the sound files of a pack, according to the
+//! sound event definitions in its `sounds.json` files.
+//!
+//! References:
+//! - <https://minecraft.wiki/w/Sounds.json>
+//! - Minecraft classes `net.minecraft.client.resources.sounds.SoundEventRegistrationSerializer`
+//!   and `net.minecraft.client.sounds.SoundManager`
+
+use ahash::{AHashMap, AHashSet};
+use serde_json::Value;
+
+use crate::pack_file::asset_type::PackFileAssetType;
+
+#[cfg(test)]
+mod tests;
+
+/// The namespace Minecraft assumes for resource locations that do not specify one.
+const DEFAULT_NAMESPACE: &str = "minecraft";
+
+/// The properties of a sound file that affect how the game plays it, as defined by the sound
+/// events that reference it.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct SoundProperties {
+	/// Whether some sound event streams the sound file from disk while playing it, instead of
+	/// loading it in advance. Long sounds, such as music and ambient tracks, are streamed.
+	pub streamed: bool
+}
+
+/// A sound file referenced by some sound event.
+struct SoundFileReference {
+	/// The relative path of the first `sounds.json` file found to reference the sound file.
+	definitions_path: String,
+	/// The properties of the sound file, combined from every sound event that references it.
+	properties: SoundProperties
+}
+
+/// The sound event definitions of a pack, which determine the sound files that the game
+/// plays and how it plays them.
+#[derive(Default)]
+pub struct SoundDefinitions {
+	/// The referenced sound files, indexed by their relative path without extension.
+	sound_files: AHashMap<String, SoundFileReference>
+}
+
+impl SoundDefinitions {
+	/// Adds the sound events defined in the `sounds.json` file at the specified relative path.
+	/// Returns `None` if the definitions are malformed, in which case the known sound files are
+	/// left unchanged.
+	pub fn add(&mut self, definitions_path: &str, definitions: &Value) -> Option<()> {
+		let mut sound_files = vec![];
+
+		for sound_event in definitions.as_object()?.values() {
+			let sounds = match sound_event.as_object()?.get("sounds") {
+				Some(sounds) => sounds.as_array()?.as_slice(),
+				None => &[]
+			};
+
+			for sound in sounds {
+				let (name, streamed) = match sound {
+					Value::String(name) => (name.as_str(), false),
+					Value::Object(sound) => {
+						// Sounds of the event type reference other sound events, not files
+						if sound.get("type").and_then(Value::as_str) == Some("event") {
+							continue;
+						}
+
+						(
+							sound.get("name")?.as_str()?,
+							match sound.get("stream") {
+								Some(streamed) => streamed.as_bool()?,
+								None => false
+							}
+						)
+					}
+					_ => return None
+				};
+
+				sound_files.push((sound_file_path(name), streamed));
+			}
+		}
+
+		for (sound_file_path, streamed) in sound_files {
+			self.sound_files
+				.entry(sound_file_path)
+				.or_insert_with(|| SoundFileReference {
+					definitions_path: definitions_path.to_string(),
+					properties: SoundProperties::default()
+				})
+				.properties
+				.streamed |= streamed;
+		}
+
+		Some(())
+	}
+
+	/// Returns the properties of the sound file at the specified relative path, with or without
+	/// extension, or `None` if no sound event references it.
+	pub fn sound_properties(&self, sound_file_path: &str) -> Option<SoundProperties> {
+		self.sound_files
+			.get(path_without_extension(sound_file_path))
+			.map(|reference| reference.properties)
+	}
+
+	/// Returns the relative paths of the `sounds.json` files that reference sound files which
+	/// are not any of the specified ones, without extension, alongside the relative paths of
+	/// those sound files. Sound files in the `minecraft` namespace are not considered missing,
+	/// as they may be provided by the game.
+	pub fn missing_sound_files<'this>(
+		&'this self,
+		sound_file_paths: &'this AHashSet<String>
+	) -> impl Iterator<Item = (&'this str, &'this str)> + 'this {
+		self.sound_files
+			.iter()
+			.filter(|(sound_file_path, _)| {
+				!is_in_default_namespace(sound_file_path)
+					&& !sound_file_paths.contains(sound_file_path.as_str())
+			})
+			.map(|(sound_file_path, reference)| {
+				(
+					reference.definitions_path.as_str(),
+					sound_file_path.as_str()
+				)
+			})
+	}
+
+	/// Returns the specified relative paths of sound files that no sound event references.
+	/// Sound files in the `minecraft` namespace are considered to be referenced, as they may
+	/// replace sound files of the game.
+	pub fn unreferenced_sound_files<'path>(
+		&'path self,
+		sound_file_paths: impl IntoIterator<Item = &'path str> + 'path
+	) -> impl Iterator<Item = &'path str> + 'path {
+		sound_file_paths.into_iter().filter(|sound_file_path| {
+			!is_in_default_namespace(sound_file_path)
+				&& !self
+					.sound_files
+					.contains_key(path_without_extension(sound_file_path))
+		})
+	}
+}
+
+/// Returns the asset type of the `sounds.json` file at the specified relative path, or `None`
+/// if the path does not belong to a sound event definitions file.
+pub fn sound_definitions_asset_type(relative_path: &str) -> Option<PackFileAssetType> {
+	let (relative_path, asset_type) = if let Some(path) = relative_path.strip_suffix(".json") {
+		(path, PackFileAssetType::GenericJson)
+	} else {
+		(
+			relative_path.strip_suffix(".jsonc")?,
+			PackFileAssetType::GenericJsonWithComments
+		)
+	};
+
+	let namespace = relative_path
+		.strip_prefix("assets/")?
+		.strip_suffix("/sounds")?;
+
+	(!namespace.is_empty() && !namespace.contains('/')).then_some(asset_type)
+}
+
+/// Returns the relative path without extension of the specified sound file, whose extension
+/// may be any of the audio formats PackSquash converts to Ogg Vorbis.
+pub fn path_without_extension(sound_file_path: &str) -> &str {
+	sound_file_path
+		.rsplit_once('.')
+		.filter(|(_, extension)| !extension.contains('/'))
+		.map_or(sound_file_path, |(path, _)| path)
+}
+
+/// Returns the relative path without extension of the sound file referenced by the specified
+/// sound name, which is a resource location relative to the sounds directory. Like other
+/// resource locations, sound names without a namespace belong to the `minecraft` namespace,
+/// no matter which namespace their `sounds.json` file belongs to.
+fn sound_file_path(name: &str) -> String {
+	let (namespace, path) = name.split_once(':').unwrap_or((DEFAULT_NAMESPACE, name));
+
+	format!("assets/{namespace}/sounds/{path}")
+}
+
+/// Checks whether the sound file at the specified relative path belongs to the namespace of
+/// the game.
+fn is_in_default_namespace(sound_file_path: &str) -> bool {
+	sound_file_path
+		.strip_prefix("assets/")
+		.and_then(|path| path.strip_prefix(DEFAULT_NAMESPACE))
+		.is_some_and(|path| path.starts_with('/'))
+}
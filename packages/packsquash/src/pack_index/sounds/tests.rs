@@ -0,0 +1,115 @@
+use pretty_assertions::assert_eq;
+
+use super::*;
+
+fn sound_definitions() -> SoundDefinitions {
+	let mut sound_definitions = SoundDefinitions::default();
+
+	sound_definitions
+		.add(
+			"assets/mypack/sounds.json",
+			&serde_json::json!({
+				"music.theme": {
+					"sounds": [ { "name": "mypack:music/theme", "stream": true } ]
+				},
+				"block.chime": {
+					"subtitle": "subtitles.mypack.block.chime",
+					"sounds": [ "mypack:block/chime1", { "name": "mypack:block/chime2", "weight": 2 } ]
+				},
+				"block.chime_alias": {
+					"sounds": [ { "name": "mypack:block.chime", "type": "event" } ]
+				},
+				"mob.cat.purr": {
+					"replace": true,
+					"sounds": [ "mob/cat/purr1", "mypack:missing" ]
+				}
+			})
+		)
+		.expect("The sound definitions are valid");
+
+	sound_definitions
+}
+
+#[test]
+fn sound_properties_are_gathered() {
+	let sound_definitions = sound_definitions();
+
+	assert_eq!(
+		sound_definitions.sound_properties("assets/mypack/sounds/music/theme.ogg"),
+		Some(SoundProperties { streamed: true })
+	);
+	assert_eq!(
+		sound_definitions.sound_properties("assets/mypack/sounds/block/chime2.wav"),
+		Some(SoundProperties { streamed: false })
+	);
+	assert_eq!(
+		sound_definitions.sound_properties("assets/minecraft/sounds/mob/cat/purr1.ogg"),
+		Some(SoundProperties { streamed: false })
+	);
+	assert_eq!(
+		sound_definitions.sound_properties("assets/mypack/sounds/block.chime.ogg"),
+		None
+	);
+}
+
+#[test]
+fn missing_and_unreferenced_sound_files_are_found() {
+	let sound_definitions = sound_definitions();
+	let sound_file_paths = [
+		"assets/mypack/sounds/music/theme.ogg",
+		"assets/mypack/sounds/block/chime1.ogg",
+		"assets/mypack/sounds/block/chime2.wav",
+		"assets/mypack/sounds/block/unused.ogg",
+		"assets/minecraft/sounds/mob/cat/meow1.ogg"
+	];
+
+	assert_eq!(
+		sound_definitions
+			.missing_sound_files(
+				&sound_file_paths
+					.into_iter()
+					.map(|sound_file_path| path_without_extension(sound_file_path).to_string())
+					.collect()
+			)
+			.collect::<Vec<_>>(),
+		[("assets/mypack/sounds.json", "assets/mypack/sounds/missing")]
+	);
+	assert_eq!(
+		sound_definitions
+			.unreferenced_sound_files(sound_file_paths)
+			.collect::<Vec<_>>(),
+		["assets/mypack/sounds/block/unused.ogg"]
+	);
+}
+
+#[test]
+fn malformed_sound_definitions_are_rejected() {
+	let mut sound_definitions = SoundDefinitions::default();
+
+	assert!(sound_definitions
+		.add(
+			"assets/mypack/sounds.json",
+			&serde_json::json!({
+				"block.chime": { "sounds": [ "mypack:block/chime1", { "stream": true } ] }
+			})
+		)
+		.is_none());
+	assert_eq!(
+		sound_definitions.sound_properties("assets/mypack/sounds/block/chime1.ogg"),
+		None
+	);
+}
+
+#[test]
+fn sound_definitions_paths_are_recognized() {
+	assert!(matches!(
+		sound_definitions_asset_type("assets/mypack/sounds.json"),
+		Some(PackFileAssetType::GenericJson)
+	));
+	assert!(matches!(
+		sound_definitions_asset_type("assets/mypack/sounds.jsonc"),
+		Some(PackFileAssetType::GenericJsonWithComments)
+	));
+	assert!(sound_definitions_asset_type("assets/mypack/sounds/sounds.json").is_none());
+	assert!(sound_definitions_asset_type("assets/sounds.json").is_none());
+}
@@ -0,0 +1,172 @@
+//! Contains code to plan how textures are downscaled to a target resolution, taking into
+//! account that some textures are made up of cells, such as animation frames or font glyphs,
+//! that the game expects to have the same size relative to each other, and that some textures
+//! must keep their resolution.
+
+use std::num::NonZeroU16;
+
+use serde_json::Value;
+
+use crate::pack_file::asset_type::PackFileAssetType;
+
+#[cfg(test)]
+mod tests;
+
+/// How a texture made up of a grid of equally sized cells is downscaled. Textures that are
+/// not made up of several cells are considered to be made up of a single cell.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextureDownscaling {
+	cell_width: u32,
+	cell_height: u32,
+	downscaled_cell_width: u32,
+	downscaled_cell_height: u32
+}
+
+impl TextureDownscaling {
+	/// Plans the downscaling of the cells of a texture with the specified size, so that neither
+	/// of their dimensions exceeds the target resolution. Cells with power of two dimensions are
+	/// downscaled by a power of two factor, so that they keep power of two dimensions, which
+	/// is important for them to work well with mipmapping when stitched into an atlas.
+	pub fn new(cell_width: u32, cell_height: u32, target_resolution: NonZeroU16) -> Self {
+		let target_resolution = target_resolution.get() as u32;
+		let largest_dimension = cell_width.max(cell_height);
+
+		let (downscaled_cell_width, downscaled_cell_height) =
+			if largest_dimension <= target_resolution {
+				(cell_width, cell_height)
+			} else if cell_width.is_power_of_two() && cell_height.is_power_of_two() {
+				let mut shift = 0;
+				while largest_dimension >> shift > target_resolution {
+					shift += 1;
+				}
+
+				((cell_width >> shift).max(1), (cell_height >> shift).max(1))
+			} else {
+				let scale = |dimension: u32| {
+					((dimension as u64 * target_resolution as u64 + largest_dimension as u64 / 2)
+						/ largest_dimension as u64)
+						.max(1) as u32
+				};
+
+				(scale(cell_width), scale(cell_height))
+			};
+
+		Self {
+			cell_width,
+			cell_height,
+			downscaled_cell_width,
+			downscaled_cell_height
+		}
+	}
+
+	/// Returns the width and height of the cells of the texture, in pixels.
+	pub fn cell_size(&self) -> (u32, u32) {
+		(self.cell_width, self.cell_height)
+	}
+
+	/// Returns the width and height the cells of the texture have after downscaling it.
+	pub fn downscaled_cell_size(&self) -> (u32, u32) {
+		(self.downscaled_cell_width, self.downscaled_cell_height)
+	}
+
+	/// Returns whether this downscaling changes the size of the texture at all.
+	pub fn downscales(&self) -> bool {
+		self.cell_size() != self.downscaled_cell_size()
+	}
+
+	/// Rewrites the frame width and height explicitly declared in the specified texture
+	/// metadata to their downscaled values.
+	pub fn scale_frame_dimensions(&self, texture_metadata: &mut Value) {
+		let Some(animation) = texture_metadata
+			.get_mut("animation")
+			.and_then(Value::as_object_mut)
+		else {
+			return;
+		};
+
+		for (dimension, downscaled_dimension) in [
+			("width", self.downscaled_cell_width),
+			("height", self.downscaled_cell_height)
+		] {
+			if let Some(dimension) = animation.get_mut(dimension) {
+				*dimension = downscaled_dimension.into();
+			}
+		}
+	}
+}
+
+/// A grid of glyphs contained in a texture, as defined by a bitmap font provider.
+#[derive(Debug, PartialEq)]
+pub struct FontGlyphGrid {
+	/// The relative path of the texture.
+	pub texture_path: String,
+	/// The number of rows of glyphs in the texture.
+	pub rows: u32,
+	/// The number of glyphs in each row of the texture.
+	pub columns: u32
+}
+
+/// Returns the glyph grids defined by the bitmap providers of the specified font definition.
+///
+/// Bitmap providers also define the `height` and `ascent` of their glyphs. The game derives
+/// the scale glyphs are drawn at by dividing that `height` by the height of the glyph cells of
+/// the texture, so glyphs keep being drawn `height` units tall, and `ascent` units above the
+/// baseline, when their cells are downscaled. Therefore, these metrics already scale with the
+/// glyph cells, and are kept as they are.
+pub fn font_bitmap_glyph_grids(font: &Value) -> Vec<FontGlyphGrid> {
+	let Some(providers) = font.get("providers").and_then(Value::as_array) else {
+		return vec![];
+	};
+
+	providers
+		.iter()
+		.filter(|provider| {
+			matches!(
+				provider.get("type").and_then(Value::as_str),
+				Some("bitmap" | "minecraft:bitmap")
+			)
+		})
+		.filter_map(|provider| {
+			let file = provider.get("file")?.as_str()?;
+			let (namespace, path) = file.split_once(':').unwrap_or(("minecraft", file));
+
+			let rows = provider.get("chars")?.as_array()?;
+			let columns = rows.first()?.as_str()?.chars().count();
+
+			(columns > 0).then(|| FontGlyphGrid {
+				texture_path: format!("assets/{namespace}/textures/{path}"),
+				rows: rows.len() as u32,
+				columns: columns as u32
+			})
+		})
+		.collect()
+}
+
+/// Returns the relative path of the texture that the material map at the specified relative
+/// path belongs to, or `None` if the path does not have a material map suffix.
+pub fn material_map_texture_path(material_map_path: &str) -> Option<String> {
+	let material_map_path = material_map_path.strip_suffix(".png")?;
+	let texture_path = ["_n", "_s", "_e"]
+		.into_iter()
+		.find_map(|suffix| material_map_path.strip_suffix(suffix))?;
+
+	Some(format!("{texture_path}.png"))
+}
+
+/// Returns the asset type to parse the font definition at the specified relative path as,
+/// or `None` if the path does not belong to a font definition.
+pub fn font_definition_asset_type(relative_path: &str) -> Option<PackFileAssetType> {
+	let (namespace, path) = relative_path
+		.strip_prefix("assets/")?
+		.split_once("/font/")?;
+
+	if namespace.contains('/') {
+		None
+	} else if path.ends_with(".json") {
+		Some(PackFileAssetType::GenericJson)
+	} else if path.ends_with(".jsonc") {
+		Some(PackFileAssetType::GenericJsonWithComments)
+	} else {
+		None
+	}
+}
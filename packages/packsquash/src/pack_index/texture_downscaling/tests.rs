@@ -0,0 +1,84 @@
+use pretty_assertions::assert_eq;
+
+use super::*;
+
+#[test]
+fn power_of_two_cells_are_downscaled_by_power_of_two_factors() {
+	let downscaling = TextureDownscaling::new(128, 64, NonZeroU16::new(48).unwrap());
+
+	assert!(downscaling.downscales());
+	assert_eq!(downscaling.downscaled_cell_size(), (32, 16));
+}
+
+#[test]
+fn other_cells_are_downscaled_keeping_their_aspect_ratio() {
+	assert_eq!(
+		TextureDownscaling::new(300, 100, NonZeroU16::new(32).unwrap()).downscaled_cell_size(),
+		(32, 11)
+	);
+}
+
+#[test]
+fn small_enough_cells_are_not_downscaled() {
+	assert!(!TextureDownscaling::new(16, 16, NonZeroU16::new(16).unwrap()).downscales());
+}
+
+#[test]
+fn explicit_frame_dimensions_are_scaled() {
+	let mut texture_metadata = serde_json::json!({ "animation": { "height": 64, "frametime": 2 } });
+
+	TextureDownscaling::new(128, 64, NonZeroU16::new(32).unwrap())
+		.scale_frame_dimensions(&mut texture_metadata);
+
+	assert_eq!(
+		texture_metadata,
+		serde_json::json!({ "animation": { "height": 16, "frametime": 2 } })
+	);
+}
+
+#[test]
+fn bitmap_font_glyph_grids_are_parsed() {
+	assert_eq!(
+		font_bitmap_glyph_grids(&serde_json::json!({
+			"providers": [
+				{ "type": "space", "advances": { " ": 4 } },
+				{
+					"type": "bitmap",
+					"file": "mypack:font/icons.png",
+					"height": 16,
+					"ascent": 12,
+					"chars": ["\u{e000}\u{e001}\u{e002}", "\u{e003}\u{e004}\u{e005}"]
+				},
+				{ "type": "minecraft:bitmap", "file": "font/accented.png", "chars": ["\u{c0}"] }
+			]
+		})),
+		[
+			FontGlyphGrid {
+				texture_path: "assets/mypack/textures/font/icons.png".into(),
+				rows: 2,
+				columns: 3
+			},
+			FontGlyphGrid {
+				texture_path: "assets/minecraft/textures/font/accented.png".into(),
+				rows: 1,
+				columns: 1
+			}
+		]
+	);
+}
+
+#[test]
+fn material_maps_are_matched_to_their_textures() {
+	assert_eq!(
+		material_map_texture_path("assets/minecraft/textures/block/stone_n.png").as_deref(),
+		Some("assets/minecraft/textures/block/stone.png")
+	);
+	assert_eq!(
+		material_map_texture_path("assets/minecraft/textures/block/stone_e.png").as_deref(),
+		Some("assets/minecraft/textures/block/stone.png")
+	);
+	assert_eq!(
+		material_map_texture_path("assets/minecraft/textures/block/stone.png"),
+		None
+	);
+}
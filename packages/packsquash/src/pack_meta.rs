@@ -190,6 +190,9 @@ impl PackMeta {
 
 		if self.pack_format_version >= PACK_FORMAT_VERSION_1_13 {
 			asset_type_mask -= PackFileAssetType::LegacyLanguageFile;
+		} else {
+			asset_type_mask -= PackFileAssetType::MinecraftLanguage
+				| PackFileAssetType::MinecraftLanguageWithComments;
 		}
 
 		if self.pack_format_version >= PACK_FORMAT_VERSION_1_17 {
@@ -362,6 +362,25 @@ fn squash(
 								"The number of pack files that will be processed in parallel was reduced to avoid \
 								exceeding open file descriptor limits. Please increase the open file descriptor \
 								limit for optimum performance, or decrease the number of threads"),
+							PackSquasherWarning::MissingSoundFile { sound_definitions_path, sound_file_path } => warn!(
+								"{}: a sound event references {}, which is not in the pack. \
+								Minecraft will not be able to play it unless another pack provides it",
+								sound_definitions_path, sound_file_path
+							),
+							PackSquasherWarning::UnreferencedSoundFile(sound_file_path) => warn!(
+								"{}: no sound event references this sound file, so Minecraft will never play it. \
+								Is it missing from a sounds.json file?",
+								sound_file_path
+							),
+							PackSquasherWarning::NonSquarePackIcon(pack_icon_path) => warn!(
+								"{}: the pack icon is not square, so Minecraft may not show it",
+								pack_icon_path
+							),
+							PackSquasherWarning::NearDuplicateSoundFiles(sound_file_paths) => warn!(
+								"These sound files sound nearly identical, so they might be redundant copies of the \
+								same sound: {}. Consider referencing only one of them in sounds.json files",
+								sound_file_paths.join(", ")
+							),
 							_ => unimplemented!()
 						},
 						_ => unimplemented!()